@@ -0,0 +1,59 @@
+-- A lot of bodies with an update function each, to see how fast update functions run.
+-- Compare the FPS with "Batch function" on and off, or with a different grid size.
+
+SCENE = {
+    title = "Update function benchmark",
+    instructions = "Every body is moved by Lua, either by an update function each or by one batch function for all of them. Watch the FPS in the top left while changing the grid size.",
+}
+
+SIZE = param{name = "Grid size", min = 5, max = 60, default = 30, step = 1, rerun = true}
+BATCH = checkbox{name = "Batch function", default = false, rerun = true}
+
+GRAVITY = 0
+RAD = 0.15
+CENTER_X = SCREEN_X / 2
+CENTER_Y = SCREEN_Y / 2
+
+-- pulls the body around the center of the screen and colors it by its speed
+function swirl(obj)
+    local dx = CENTER_X - obj.x
+    local dy = CENTER_Y - obj.y
+    obj.x_vel = obj.x_vel + (dx - dy * 0.5) * DT
+    obj.y_vel = obj.y_vel + (dy + dx * 0.5) * DT
+
+    local speed = math.sqrt(obj.x_vel * obj.x_vel + obj.y_vel * obj.y_vel)
+    obj.color.r = math.min(speed * 20, 255)
+    obj.color.b = 255 - obj.color.r
+end
+
+function swirl_all(bodies)
+    for i = 1, #bodies do
+        swirl(bodies[i])
+    end
+end
+
+SPACING = math.min(SCREEN_X, SCREEN_Y) * 0.8 / SIZE
+START_X = CENTER_X - SPACING * (SIZE - 1) / 2
+START_Y = CENTER_Y - SPACING * (SIZE - 1) / 2
+
+for row = 0, SIZE - 1 do
+    for col = 0, SIZE - 1 do
+        add_shape{
+            shape = "circle",
+            x = START_X + col * SPACING,
+            y = START_Y + row * SPACING,
+            r = RAD,
+            mass = 1,
+            collision = false,
+            color = {r = 0, g = 100, b = 255},
+            update_function = not BATCH and "swirl" or nil,
+            batch_function = BATCH and "swirl_all" or nil,
+        }
+    end
+end
+
+function update()
+    local mode = BATCH and "one batch function" or "an update function each"
+    draw_text{x = 0.5, y = 0.5, screen = true, size = 1,
+        text = string.format("%d bodies, %s: %.1f FPS", SIZE * SIZE, mode, FPS)}
+end
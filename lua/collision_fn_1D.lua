@@ -1,3 +1,7 @@
+-- vectors come from the built in physics module
+local physics = require("physics")
+local vec = physics.vec
+
 -- this function is called on the circles when they collide
 function collide_fn(c1, c2)
     -- 1D elastic collision
@@ -15,56 +19,6 @@ end
 
 GRAVITY = 0
 
--- defining a Vector class
-Vector = {x = 0, y = 0}
-Vector.__index = Vector
-
-function Vector:create(x, y)
-    local v = {}
-    setmetatable(v, Vector)
-    v.x, v.y = x, y
-    return v
-end
-
-function Vector:magnitude()
-    return math.sqrt(self.x * self.x + self.y * self.y)
-end
-
-function Vector:dist(v)
-    local radius = Vector:create(self.x - v.x, self.y - v.y)
-    return radius:magnitude()
-end
-
-function Vector:dot(v)
-    return self.x * v.x + self.y * v.y
-end
-
-function Vector:cos_angle(v)
-    local mag1, mag2 = self:magnitude(), v:magnitude()
-    return self:dot(v) / (mag1 * mag2)
-end
-
-function Vector:__add(v)
-    return Vector:create(self.x + v.x, self.y + v.y)
-end
-
-function Vector:__sub(v)
-    return Vector:create(self.x - v.x, self.y - v.y)
-end
-
-function Vector:__mul(f)
-    if (type(self) == "table") then
-        return Vector:create(self.x * f, self.y * f)
-    else
-        return Vector:create(f.x * self, f.y * self)
-    end
-end
-
-function Vector:__div(f)
-    return self * (1 / f)
-end
-----
-
 -- checks if the circles are colliding
 local function circle_collide(p1, r1, p2, r2)
     return p1:dist(p2) <= r1 + r2
@@ -103,12 +57,12 @@ end
 -- every frame, check if the circles are colliding and call
 -- the collide_fn if they collide
 function update()
-    local p1 = Vector:create(circ1.x, circ1.y)
-    local p2 = Vector:create(circ2.x, circ2.y)
+    local p1 = vec(circ1.x, circ1.y)
+    local p2 = vec(circ2.x, circ2.y)
 
     if (circle_collide(p1, 1, p2, 1)) then
-        local relative_vel = Vector:create(circ1.x_vel - circ2.x_vel, circ1.y_vel - circ2.x_vel)
-        local radius = Vector:create(p1.x - p2.x, p1.y - p2.y)
+        local relative_vel = vec(circ1.x_vel - circ2.x_vel, circ1.y_vel - circ2.x_vel)
+        local radius = vec(p1.x - p2.x, p1.y - p2.y)
         local cos_angle = relative_vel:cos_angle(radius)
         if (cos_angle < 0) then
             circ1.changed, circ2.changed = true, true
@@ -1,3 +1,22 @@
+SCENE = {
+    title = "Collision Lab",
+    description = "Elastic and inelastic collisions between equal and unequal masses",
+    instructions = [[
+# Setup
+Each row has two balls. Mass A starts moving at 5 m/s toward Mass B, which is at rest.
+- Rows 1 and 2 are perfectly elastic
+- Rows 3 and 4 are perfectly inelastic
+- In rows 2 and 4, Mass B is 5 times heavier
+
+# Procedure
+Unpause with space and watch each collision. Click on a ball to see its velocity, and add graphs to compare them.
+
+# Questions
+- Is momentum conserved in every row?
+- Which rows conserve kinetic energy?
+]],
+}
+
 RAD = 1.75
 
 function make_row(data)
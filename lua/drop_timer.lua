@@ -0,0 +1,69 @@
+-- a ball is dropped from the same height every half second of simulated time,
+-- so the gaps between them grow like 1/2 g t^2
+SCENE = {
+    title = "Drop Timer",
+    description = "Balls dropped every half second show how distance grows with time in free fall",
+    instructions = [[
+A ball is dropped from the line at the top every half second, 8 times. The simulation pauses once the first ball has fallen for 4 seconds.
+- How does the gap between neighboring balls change?
+- Use d = 1/2 g t^2 to predict how far the first ball falls before the second is dropped.
+]],
+}
+
+DROP_INTERVAL = 0.5
+DROP_Y = 2
+BALL_COLORS = {
+    {r = 255, g = 100, b = 100},
+    {r = 100, g = 255, b = 100},
+    {r = 100, g = 100, b = 255},
+}
+
+local balls_dropped = 0
+local first_ball
+
+local drop_timer = every(DROP_INTERVAL, function()
+    balls_dropped = balls_dropped + 1
+    local ball = spawn{
+        shape = "circle",
+        x = SCREEN_X / 2,
+        y = DROP_Y,
+        r = 0.3,
+        mass = 1,
+        collision = false,
+        color = BALL_COLORS[balls_dropped % #BALL_COLORS + 1],
+    }
+    first_ball = first_ball or ball
+    print(string.format("dropped ball %d at t = %.3f s", balls_dropped, TIME))
+end)
+
+-- stop after 8 balls and pause once the first one has fallen for 4 seconds
+after(DROP_INTERVAL * 8 - 0.01, function()
+    cancel(drop_timer)
+end)
+
+after(DROP_INTERVAL + 4, function()
+    PAUSED = true
+end)
+
+goal{description = "Drop all 8 balls", check = function()
+    return balls_dropped >= 8
+end}
+
+-- v = g t, so the first ball should pass 10 m/s about a second after it's dropped
+goal{description = "The first ball reaches 10 m/s", deadline = DROP_INTERVAL + 1.5, check = function()
+    if first_ball == nil then
+        return false
+    end
+    local _, y_vel = first_ball:get_vel()
+    return y_vel >= 10
+end}
+
+-- the clock and a line where the balls are dropped from
+function update()
+    draw_line{x1 = 0, y1 = DROP_Y, x2 = SCREEN_X, y2 = DROP_Y, color = {r = 255, g = 255, b = 255, a = 100}}
+    draw_text{x = 0.5, y = 0.5, text = string.format("t = %.2f s", TIME), screen = true}
+end
+
+add_shape{shape = "rect", status = "static", x = SCREEN_X / 2, y = SCREEN_Y, w = SCREEN_X, h = 1, mass = 1}
+
+GRAVITY = 9.81
@@ -1,23 +1,29 @@
--- since Lua can't access keypresses this will be AI driven
+-- press space to flap, or A to let the autopilot fly
 
 PIPE_WIDTH = 1.5
-PIPE_SPEED = 15
+-- the pipes are made with this speed, so changing it runs the scene again
+PIPE_SPEED = param{name = "Pipe speed", min = 5, max = 30, default = 15, rerun = true}
 PIPE_COLOR = {r = 0, g = 255, b = 0}
 
-GAP_HEIGHT = 7
+GAP_HEIGHT = param{name = "Gap height", min = 4, max = 12, default = 7, on_change = function(value)
+    GAP_HEIGHT = value
+end}
 GAP_MIN_Y = SCREEN_Y / 4
 GAP_MAX_Y = 3 / 4 * SCREEN_Y
 CURRENT_GAP_Y = 0
 
 BIRD_JUMP_ACCEL = 20
-BIRD_JUMP_COOLDOWN = 10
-CURRENT_JUMP_COOLDOWN = 0
+BIRD_JUMP_COOLDOWN = 0.02 -- seconds
+JUMP_READY = true
 BIRD_RADIUS = 0.9
 BIRD_GRAVITY = 0.075
 BIRD_COLOR = {r = 255, g = 255, b = 0}
 
+AUTOPILOT = false
+FLAP_PRESSED = false
+
 local function reset_gap_y()
-    CURRENT_GAP_Y = math.random(0, 100) / 100 * (GAP_MAX_Y - GAP_MIN_Y) + GAP_MIN_Y
+    CURRENT_GAP_Y = rng.uniform(GAP_MIN_Y, GAP_MAX_Y)
 end
 reset_gap_y()
 
@@ -31,26 +37,43 @@ end
 
 local function bird_jump(bird)
     bird.y_vel = bird.y_vel - BIRD_JUMP_ACCEL
-    CURRENT_JUMP_COOLDOWN = BIRD_JUMP_COOLDOWN
+    JUMP_READY = false
+    after(BIRD_JUMP_COOLDOWN, function()
+        JUMP_READY = true
+    end)
 end
 
 local function should_jump(obj)
     local below_gap = obj.y > CURRENT_GAP_Y + GAP_HEIGHT / 2 - (BIRD_RADIUS * 2.5)
-    local jump_available = CURRENT_JUMP_COOLDOWN < 0
+    local jump_available = JUMP_READY
     local not_too_fast = obj.y_vel > -BIRD_JUMP_ACCEL / 8
 
     return below_gap and jump_available and not_too_fast
 end
 
+-- returning true stops space from pausing and A from toggling centered shapes
+function on_key_down(key, repeated)
+    if key == "space" then
+        if not repeated then
+            FLAP_PRESSED = true
+        end
+        return true
+    elseif key == "a" then
+        if not repeated then
+            AUTOPILOT = not AUTOPILOT
+        end
+        return true
+    end
+end
+
 function bird_update(obj)
     obj.y_vel = obj.y_vel + BIRD_GRAVITY * DT
 
-    if should_jump(obj) then
+    if FLAP_PRESSED or (AUTOPILOT and should_jump(obj)) then
         bird_jump(obj)
+        FLAP_PRESSED = false
     end
 
-    CURRENT_JUMP_COOLDOWN = CURRENT_JUMP_COOLDOWN - 1 * DT
-
     return obj
 end
 
@@ -62,6 +85,7 @@ GAP_RESET = false
 function pipe_update(obj)
     if obj.x + PIPE_WIDTH < 0 then
         obj.x = SCREEN_X + PIPE_WIDTH
+        obj.data.passed = obj.data.passed + 1
         GAP_RESET = not GAP_RESET
 
         if GAP_RESET then
@@ -77,6 +101,14 @@ function pipe_update(obj)
     return obj
 end
 
+-- each pipe counts how many times it's gone by in its obj.data
+function update()
+    local top_pipe = OBJECTS and OBJECTS.top_pipe
+    if top_pipe then
+        draw_text{x = 0.5, y = 0.5, text = "Pipes passed: " .. top_pipe.data.passed, screen = true}
+    end
+end
+
 add_shape{
     shape="circle",
     x = SCREEN_X / 8,
@@ -98,7 +130,8 @@ add_shape{
     x_vel = -PIPE_SPEED,
     update_function="pipe_update",
     name="top_pipe",
-    color=PIPE_COLOR
+    color=PIPE_COLOR,
+    data = {passed = 0}
 }
 
 -- lower pipe
@@ -112,7 +145,8 @@ add_shape{
     x_vel = -PIPE_SPEED,
     update_function="pipe_update",
     name="bottom_pipe",
-    color=PIPE_COLOR
+    color=PIPE_COLOR,
+    data = {passed = 0}
 }
 
 -- so the pipes don't fall; the bird has artificial Lua gravity
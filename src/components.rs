@@ -1,9 +1,14 @@
 use specs::prelude::*;
 use specs::Component;
 
+use nphysics2d::force_generator::DefaultForceGeneratorHandle;
+use nphysics2d::joint::DefaultJointConstraintHandle;
 use nphysics2d::object::DefaultBodyHandle;
 
-use crate::ColliderHandle;
+use crate::main_state::joint::JointKind;
+use crate::main_state::keyframe_path::KeyframePath;
+use crate::resources::ShapeInfo;
+use crate::{ColliderHandle, Point};
 
 #[derive(Debug, Copy, Clone, Component)]
 #[storage(VecStorage)]
@@ -17,6 +22,71 @@ pub struct Collider {
     pub coll_handle: ColliderHandle,
 }
 
+/// Anything that lives in the ForceGeneratorSet, e.g. force fields
+#[derive(Debug, Copy, Clone, Component)]
+#[storage(HashMapStorage)]
+pub struct ForceGen {
+    pub gen_handle: DefaultForceGeneratorHandle,
+}
+
+/// A group of particles held together by the LatticeSprings in its ForceGen
+#[derive(Debug, Clone, Component)]
+#[storage(HashMapStorage)]
+pub struct SoftBody {
+    pub particles: Vec<Entity>,
+    /// index into particles for each point of the lattice, row major
+    pub lattice: Vec<Option<usize>>,
+    pub cols: usize,
+    pub rows: usize,
+    /// the shape and settings it was created with, used for serialization
+    pub shape: ShapeInfo,
+    pub resolution: usize,
+    pub mass: f32,
+}
+
+/// Particles are deleted along with the rest of their soft body
+#[derive(Debug, Copy, Clone, Component)]
+#[storage(HashMapStorage)]
+pub struct SoftBodyParticle {
+    pub soft_body: Entity,
+}
+
+/// The two bodies held together by the PulleyConstraint in its ForceGen
+#[derive(Debug, Copy, Clone, Component)]
+#[storage(HashMapStorage)]
+pub struct Pulley {
+    pub body1: Entity,
+    pub body2: Entity,
+}
+
+/// A pin or weld between two bodies that lives in the JointConstraintSet
+#[derive(Debug, Copy, Clone, Component)]
+#[storage(HashMapStorage)]
+pub struct Joint {
+    pub joint_handle: DefaultJointConstraintHandle,
+    pub kind: JointKind,
+    pub body1: Entity,
+    pub body2: Entity,
+    /// in the local space of each body
+    pub anchor1: Point,
+    pub anchor2: Point,
+    /// None if the joint can't break
+    pub break_force: Option<f32>,
+    pub break_torque: Option<f32>,
+    /// Some once the joint breaks, with the seconds left until it stops being drawn.
+    /// Broken joints are already removed from the JointConstraintSet.
+    pub broken_flash: Option<f32>,
+}
+
+/// Moves a kinematic body along a path, see PathFollowSys
+#[derive(Debug, Clone, Component)]
+#[storage(HashMapStorage)]
+pub struct FollowPath {
+    pub path: KeyframePath,
+    /// seconds of simulation time since the body started following the path
+    pub elapsed: f32,
+}
+
 #[derive(Component, Default)]
 #[storage(NullStorage)]
 pub struct InfoDisplayed;
@@ -32,3 +102,8 @@ pub struct Name(pub String);
 #[derive(Debug, Clone, Component)]
 #[storage(HashMapStorage)]
 pub struct UpdateFunction(pub String);
+
+/// A Lua function that's called once a frame with every body that has the same one
+#[derive(Debug, Clone, Component)]
+#[storage(HashMapStorage)]
+pub struct BatchFunction(pub String);
@@ -33,9 +33,27 @@ pub trait LineGraph {
     fn shown(&self) -> bool;
     fn max_len(&self) -> usize;
     fn point_shape(&self) -> PointShape;
-    fn access_field(rigid_body: &RigidBody) -> f32
-    where
-        Self: Sized;
+}
+
+/// The built in graphs, which graph a field of the body they're on
+pub trait BodyGraph: LineGraph {
+    fn access_field(rigid_body: &RigidBody) -> f32;
+}
+
+/// The newest value goes at the right edge and the older ones shift left
+fn push_graph_val(data: &mut VecDeque<[f32; 2]>, max_len: usize, val: f32) {
+    let num_vals = data.len() + 1;
+    let step_incr = 10.0 / max_len as f32;
+
+    data.iter_mut().enumerate().for_each(|(i, [x, _])| {
+        *x = step_incr * i as f32;
+    });
+    if num_vals < max_len {
+        data.push_back([10.0, val]);
+    } else {
+        data.pop_front();
+        data.push_back([10.0, val]);
+    }
 }
 
 impl Graph for dyn LineGraph {
@@ -148,18 +166,7 @@ macro_rules! create_linegraph {
             }
 
             fn add_val(&mut self, val: f32) {
-                let num_vals = self.data.len() + 1;
-                let step_incr = 10.0 / self.max_len() as f32;
-
-                self.data.iter_mut().enumerate().for_each(|(i, [x, _])| {
-                    *x = step_incr * i as f32;
-                });
-                if num_vals < self.max_len {
-                    self.data.push_back([10.0, val]);
-                } else {
-                    self.data.pop_front();
-                    self.data.push_back([10.0, val]);
-                }
+                push_graph_val(&mut self.data, self.max_len, val);
             }
 
             fn name(&self) -> String {
@@ -177,7 +184,9 @@ macro_rules! create_linegraph {
             fn point_shape(&self) -> PointShape {
                 $point_shape
             }
+        }
 
+        impl BodyGraph for $structname {
             fn access_field(rigid_body: &RigidBody) -> f32 {
                 $access_closure(rigid_body)
             }
@@ -228,6 +237,62 @@ create_linegraph!(
     |rigid_body: &RigidBody| rigid_body.position().rotation.angle()
 );
 
+/// A graph of what a Lua function returns, from add_graph() or an expression typed into the
+/// side menu. The function is called with the object's handle by LuaGraphSys.
+pub struct LuaGraph {
+    pub label: String,
+    pub data: VecDeque<[f32; 2]>,
+    pub shown: bool,
+    pub max_len: usize,
+    pub function: rlua::RegistryKey,
+    /// set if it was typed into the side menu, so that it can be made again after a hot reload
+    pub expression: Option<String>,
+}
+
+impl LuaGraph {
+    pub fn new(label: String, function: rlua::RegistryKey, expression: Option<String>) -> Self {
+        LuaGraph {
+            label,
+            data: VecDeque::with_capacity(60 * 10 / 4),
+            shown: true,
+            max_len: 60 * 10,
+            function,
+            expression,
+        }
+    }
+}
+
+impl LineGraph for LuaGraph {
+    fn points(&self) -> (&[[f32; 2]], &[[f32; 2]]) {
+        self.data.as_slices()
+    }
+
+    fn add_val(&mut self, val: f32) {
+        push_graph_val(&mut self.data, self.max_len, val);
+    }
+
+    fn name(&self) -> String {
+        self.label.clone()
+    }
+
+    fn shown(&self) -> bool {
+        self.shown
+    }
+
+    fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    fn point_shape(&self) -> PointShape {
+        PointShape::Dot
+    }
+}
+
+/// Unlike the built in graphs, a body can have any number of these
+#[derive(Component, Default)]
+#[storage(BTreeStorage)]
+pub struct LuaGraphs(pub Vec<LuaGraph>);
+
 impl<'a, 'b> MainState<'a, 'b> {
     pub fn draw_graphs(&self) -> ([Text; 3], MeshBuilder) {
         use specs::prelude::*;
@@ -270,6 +335,24 @@ impl<'a, 'b> MainState<'a, 'b> {
         draw_graphtype!(YPosGraph);
         draw_graphtype!(RotGraph);
 
+        let lua_graphs = self.world.read_storage::<LuaGraphs>();
+        (&lua_graphs, &colors)
+            .join()
+            .for_each(|(LuaGraphs(graphs), color)| {
+                graphs.iter().filter(|graph| graph.shown).for_each(|graph| {
+                    if first_iter {
+                        first_iter = false;
+                        draw_graph_frame(&mut builder);
+                    }
+                    Graph::draw(
+                        graph as &dyn LineGraph,
+                        &mut builder,
+                        color.0,
+                        Some((midpoint, scale_fac)),
+                    );
+                });
+            });
+
         let max_text = graphics::Text::new(
             TextFragment::new(format!("{0:.3}", max)).scale(Scale::uniform(25.0)),
         );
@@ -283,6 +366,86 @@ impl<'a, 'b> MainState<'a, 'b> {
         ([max_text, mid_text, min_text], builder)
     }
 
+    /// Runs f, then puts graphs back on bodies with the same names as the ones
+    /// that had them, so that a hot reloaded scene keeps its graphs
+    pub fn keep_graphs(&mut self, f: impl FnOnce(&mut Self)) {
+        use std::collections::HashMap;
+
+        let mut graphed_names: HashMap<&str, Vec<String>> = HashMap::new();
+        macro_rules! save_graphtype {
+            ( $graphtype:ident ) => {{
+                let graphs = self.world.read_storage::<$graphtype>();
+                let names = self.world.read_storage::<Name>();
+                graphed_names.insert(
+                    stringify!($graphtype),
+                    (&graphs, &names)
+                        .join()
+                        .map(|(_, name)| name.0.clone())
+                        .collect(),
+                );
+            }};
+        }
+
+        save_graphtype!(SpeedGraph);
+        save_graphtype!(RotVelGraph);
+        save_graphtype!(XVelGraph);
+        save_graphtype!(YVelGraph);
+        save_graphtype!(XPosGraph);
+        save_graphtype!(YPosGraph);
+        save_graphtype!(RotGraph);
+
+        // graphs from add_graph() are made again by the scene, but typed in ones aren't
+        let expression_graphs: Vec<(String, String)> = {
+            let lua_graphs = self.world.read_storage::<LuaGraphs>();
+            let names = self.world.read_storage::<Name>();
+            (&lua_graphs, &names)
+                .join()
+                .flat_map(|(LuaGraphs(graphs), name)| {
+                    graphs.iter().filter_map(move |graph| {
+                        graph
+                            .expression
+                            .clone()
+                            .map(|expression| (name.0.clone(), expression))
+                    })
+                })
+                .collect()
+        };
+
+        f(self);
+
+        macro_rules! restore_graphtype {
+            ( $graphtype:ident ) => {
+                let names = graphed_names
+                    .remove(stringify!($graphtype))
+                    .unwrap_or_default();
+                names.iter().for_each(|name| {
+                    if let Some(entity) = self.find_body_by_name(name) {
+                        self.world
+                            .write_storage::<$graphtype>()
+                            .insert(entity, $graphtype::default())
+                            .unwrap();
+                    }
+                });
+            };
+        }
+
+        restore_graphtype!(SpeedGraph);
+        restore_graphtype!(RotVelGraph);
+        restore_graphtype!(XVelGraph);
+        restore_graphtype!(YVelGraph);
+        restore_graphtype!(XPosGraph);
+        restore_graphtype!(YPosGraph);
+        restore_graphtype!(RotGraph);
+
+        expression_graphs
+            .into_iter()
+            .for_each(|(name, expression)| {
+                if let Some(entity) = self.find_body_by_name(&name) {
+                    self.add_expression_graph(entity, expression);
+                }
+            });
+    }
+
     pub fn graph_grab_rect(&self) -> Rect {
         let graph_rect = self.world.fetch::<GraphPosData>().0;
         let scale_fac = graph_rect.w / 10.0;
@@ -335,6 +498,19 @@ impl<'a, 'b> MainState<'a, 'b> {
         add_linegraph_columns!(YVelGraph);
         add_linegraph_columns!(RotVelGraph);
 
+        let lua_graphs = self.world.read_storage::<LuaGraphs>();
+        (&lua_graphs, &names)
+            .join()
+            .for_each(|(LuaGraphs(graphs), name)| {
+                graphs.iter().for_each(|graph| {
+                    let (s0, s1) = graph.points();
+                    columns.push(Column {
+                        name: format!("{} {}", name.0, graph.name()),
+                        data: s0.iter().chain(s1.iter()).map(|[_, val]| *val).collect(),
+                    });
+                });
+            });
+
         let mut writer = csv::Writer::from_path(filename).expect("error creating csv writer");
 
         let names = columns
@@ -10,6 +10,7 @@ use imgui::StyleColor;
 use imgui::*;
 use imgui_gfx_renderer::*;
 
+use crate::components::{ForceGen, PhysicsBody};
 use crate::gui::signals::UiSignal;
 use crate::gui::ui::*;
 use crate::Vector;
@@ -32,6 +33,8 @@ pub enum UiChoice {
     DefaultUI,
     SideMenu(Entity),
     Graph(Entity),
+    LuaConsole,
+    SceneInfo,
 }
 
 pub struct ImGuiWrapper {
@@ -145,12 +148,36 @@ impl ImGuiWrapper {
                 match menu {
                     UiChoice::DefaultUI => make_default_ui(&mut ui),
                     UiChoice::SideMenu(entity) => {
-                        make_sidemenu(&mut ui, world, *entity, &mut self.sent_signals)
+                        // pulleys, joints, and soft bodies don't have a side menu, force
+                        // fields are checked for by make_force_field_sidemenu()
+                        if world.read_storage::<PhysicsBody>().contains(*entity) {
+                            make_sidemenu(&mut ui, world, *entity, &mut self.sent_signals)
+                        } else if world.read_storage::<ForceGen>().contains(*entity) {
+                            make_force_field_sidemenu(
+                                &mut ui,
+                                world,
+                                *entity,
+                                &mut self.sent_signals,
+                            )
+                        }
+                    }
+                    UiChoice::LuaConsole => {
+                        if !make_lua_console(&mut ui, world, &mut self.sent_signals) {
+                            self.shown_menus.remove(&UiChoice::LuaConsole);
+                        }
+                    }
+                    UiChoice::SceneInfo => {
+                        if !make_scene_info(&mut ui, world) {
+                            self.shown_menus.remove(&UiChoice::SceneInfo);
+                        }
                     }
                     _ => unimplemented!(),
                 }
             }
             make_menu_bar(&mut ui, &mut self.sent_signals, world);
+            make_notification(&mut ui, world);
+            make_lab_controls(&mut ui, world, &mut self.sent_signals);
+            make_goals(&mut ui, world, &mut self.sent_signals);
         }
 
         // Render
@@ -181,6 +208,16 @@ impl ImGuiWrapper {
         })
     }
 
+    /// true if the mouse is over a window, so clicks shouldn't go to the simulation
+    pub fn wants_mouse(&self) -> bool {
+        self.imgui.io().want_capture_mouse
+    }
+
+    /// true if a text box is focused, so keypresses shouldn't go to the simulation
+    pub fn wants_keyboard(&self) -> bool {
+        self.imgui.io().want_capture_keyboard
+    }
+
     fn update_mouse(&mut self) {
         self.imgui.io_mut().mouse_pos =
             [self.mouse_state.pos.0 as f32, self.mouse_state.pos.1 as f32];
@@ -2,19 +2,43 @@ use crate::MainState;
 
 use specs::prelude::*;
 
+use crate::components::{FollowPath, PhysicsBody};
 use crate::gui::graphs::{
     RotGraph, RotVelGraph, SpeedGraph, XPosGraph, XVelGraph, YPosGraph, YVelGraph,
 };
-use crate::resources::{CreationData, Paused, ShapeInfo};
-use crate::MechanicalWorld;
+use crate::gui::imgui_wrapper::UiChoice;
+use crate::main_state::force_field::ForceFieldKind;
+use crate::main_state::joint::JointKind;
+use crate::main_state::keyframe_path::{Keyframe, KeyframePath};
+use crate::resources::{
+    CreateForceField, CreateJoint, CreatePulley, CreateSoftBody, CreationData, CurrentScene,
+    JointDraft, PathDraft, Paused, PulleyDraft, RecordPath, RecordPathInterval, ShapeInfo,
+};
+use crate::{BodySet, MechanicalWorld, Vector};
+
+use nphysics2d::object::BodyStatus;
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum UiSignal {
     AddShape(ShapeInfo),
+    AddForceField(ForceFieldKind),
+    AddSoftBody(ShapeInfo),
+    AddPulley,
+    AddJoint(JointKind),
+    StartPath(Entity),
+    FinishPath,
+    CancelPath,
+    RemovePath(Entity),
     DeleteShape(Entity),
     DeleteAll,
     TogglePause,
     LoadLua(String),
+    ResetScene,
+    LabControlChanged(usize),
+    RerunScene,
+    ToggleLuaConsole,
+    ToggleSceneInfo,
+    RunLuaRepl(String),
     AddSpeedGraph(Entity),
     AddRotVelGraph(Entity),
     AddXVelGraph(Entity),
@@ -22,7 +46,9 @@ pub enum UiSignal {
     AddXPosGraph(Entity),
     AddYPosGraph(Entity),
     AddRotGraph(Entity),
+    AddExpressionGraph(Entity, String),
     SerializeGraphs,
+    ExportGoalResults,
     SerializeState,
     GravityChanged,
 }
@@ -44,7 +70,110 @@ impl<'a, 'b> MainState<'a, 'b> {
             .iter()
             .for_each(|signal| match signal {
                 UiSignal::AddShape(shape_info) => {
-                    self.world.insert(CreationData(Some(shape_info.clone())))
+                    self.cancel_creation();
+                    self.world.insert(CreationData(Some(shape_info.clone())));
+                }
+                UiSignal::AddForceField(kind) => {
+                    // wind is usually a region like a wind tunnel while the others
+                    // are centered on a point
+                    let shape_info = match kind {
+                        ForceFieldKind::Wind => ShapeInfo::Rectangle(None),
+                        ForceFieldKind::Radial | ForceFieldKind::Vortex => ShapeInfo::Circle(None),
+                    };
+                    self.cancel_creation();
+                    self.world.insert(CreationData(Some(shape_info)));
+                    self.world.insert(CreateForceField(Some(*kind)));
+                }
+                UiSignal::AddSoftBody(shape_info) => {
+                    self.cancel_creation();
+                    self.world.insert(CreationData(Some(shape_info.clone())));
+                    self.world.insert(CreateSoftBody(true));
+                }
+                UiSignal::AddPulley => {
+                    self.cancel_creation();
+                    self.world
+                        .insert(CreatePulley(Some(PulleyDraft::default())));
+                }
+                UiSignal::AddJoint(kind) => {
+                    self.cancel_creation();
+                    self.world.insert(CreateJoint(Some(JointDraft {
+                        kind: *kind,
+                        body: None,
+                    })));
+                }
+                UiSignal::StartPath(entity) => {
+                    let start_pos = {
+                        let physics_bodies = self.world.read_storage::<PhysicsBody>();
+                        let body_set = self.world.fetch::<BodySet>();
+                        body_set
+                            .rigid_body(physics_bodies.get(*entity).unwrap().body_handle)
+                            .unwrap()
+                            .position()
+                            .translation
+                            .vector
+                    };
+                    self.cancel_creation();
+                    self.world.insert(RecordPath(Some(PathDraft {
+                        entity: *entity,
+                        waypoints: vec![start_pos],
+                    })));
+                }
+                UiSignal::FinishPath => {
+                    let draft = self.world.fetch::<RecordPath>().0.clone();
+                    self.world.insert(RecordPath(None));
+
+                    if let Some(draft) = draft {
+                        let interval = self.world.fetch::<RecordPathInterval>().0;
+                        let physics_bodies = self.world.read_storage::<PhysicsBody>();
+                        let physics_body = physics_bodies.get(draft.entity);
+
+                        if let (Some(physics_body), true) =
+                            (physics_body, draft.waypoints.len() >= 2)
+                        {
+                            let keyframes = draft
+                                .waypoints
+                                .iter()
+                                .enumerate()
+                                .map(|(i, pos)| Keyframe {
+                                    time: i as f32 * interval,
+                                    pos: *pos,
+                                })
+                                .collect();
+
+                            // bodies following a path aren't affected by anything else
+                            let mut body_set = self.world.fetch_mut::<BodySet>();
+                            if let Some(body) = body_set.get_mut(physics_body.body_handle) {
+                                body.set_status(BodyStatus::Kinematic);
+                            }
+
+                            self.world
+                                .write_storage::<FollowPath>()
+                                .insert(
+                                    draft.entity,
+                                    FollowPath {
+                                        path: KeyframePath::new(keyframes),
+                                        elapsed: 0.0,
+                                    },
+                                )
+                                .unwrap();
+                        }
+                    }
+                }
+                UiSignal::CancelPath => {
+                    self.world.insert(RecordPath(None));
+                }
+                UiSignal::RemovePath(entity) => {
+                    self.world.write_storage::<FollowPath>().remove(*entity);
+
+                    // the body stays kinematic, so it just stops where it is
+                    let physics_bodies = self.world.read_storage::<PhysicsBody>();
+                    let mut body_set = self.world.fetch_mut::<BodySet>();
+                    if let Some(rigid_body) = physics_bodies
+                        .get(*entity)
+                        .and_then(|physics_body| body_set.rigid_body_mut(physics_body.body_handle))
+                    {
+                        rigid_body.set_linear_velocity(Vector::new(0.0, 0.0));
+                    }
                 }
                 UiSignal::DeleteShape(entity) => {
                     self.delete_entity(*entity);
@@ -58,10 +187,39 @@ impl<'a, 'b> MainState<'a, 'b> {
                     self.reactivate_all();
                 }
                 UiSignal::LoadLua(filename) => {
-                    self.delete_all();
-                    self.lua_update();
-                    self.add_shapes_from_lua_file(format!("lua/{}", filename));
-                    self.lua_update();
+                    self.load_scene(format!("lua/{}", filename));
+                }
+                UiSignal::ResetScene => {
+                    self.reset_scene();
+                }
+                UiSignal::LabControlChanged(index) => {
+                    self.lab_control_changed(*index);
+                }
+                UiSignal::RerunScene => {
+                    let current_scene = self.world.fetch::<CurrentScene>().0.clone();
+                    if let Some(filename) = current_scene {
+                        if self.rerun_scene(filename) {
+                            self.notify(
+                                "The scene had errors, see the Lua Console".to_string(),
+                                true,
+                            );
+                        }
+                    }
+                }
+                UiSignal::RunLuaRepl(line) => {
+                    self.run_lua_repl(line.clone());
+                }
+                UiSignal::ToggleLuaConsole => {
+                    let shown_menus = &mut self.imgui_wrapper.shown_menus;
+                    if !shown_menus.remove(&UiChoice::LuaConsole) {
+                        shown_menus.insert(UiChoice::LuaConsole);
+                    }
+                }
+                UiSignal::ToggleSceneInfo => {
+                    let shown_menus = &mut self.imgui_wrapper.shown_menus;
+                    if !shown_menus.remove(&UiChoice::SceneInfo) {
+                        shown_menus.insert(UiChoice::SceneInfo);
+                    }
                 }
                 //TODO: Figure out how to make macro work in top level of match, e.g.
                 // add_graph_variant!(SpeedGraph) generates the whole match arm
@@ -86,10 +244,16 @@ impl<'a, 'b> MainState<'a, 'b> {
                 UiSignal::AddRotGraph(entity) => {
                     add_graph_variant!(RotGraph, entity);
                 }
+                UiSignal::AddExpressionGraph(entity, expression) => {
+                    self.add_expression_graph(*entity, expression.clone());
+                }
                 UiSignal::SerializeGraphs => {
                     use crate::resources::SaveGraphFilename;
                     self.serialize_graphs_to_csv(self.world.fetch::<SaveGraphFilename>().0.clone());
                 }
+                UiSignal::ExportGoalResults => {
+                    self.export_goal_results();
+                }
                 UiSignal::SerializeState => {
                     use crate::resources::SaveSceneFilename;
                     self.export_lua(self.world.fetch::<SaveSceneFilename>().0.clone());
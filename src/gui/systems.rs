@@ -4,6 +4,9 @@ pub use line_graph_sys::*;
 pub mod graph_transform_sys;
 pub use graph_transform_sys::GraphTransformSys;
 
+pub mod lua_graph_sys;
+pub use lua_graph_sys::LuaGraphSys;
+
 pub mod min_max_graph_sys;
 pub use min_max_graph_sys::MinMaxGraphSys;
 
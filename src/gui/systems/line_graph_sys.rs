@@ -1,7 +1,7 @@
 use specs::prelude::*;
 
 use crate::gui::graphs::{
-    LineGraph, RotGraph, RotVelGraph, SpeedGraph, XPosGraph, XVelGraph, YPosGraph, YVelGraph,
+    BodyGraph, RotGraph, RotVelGraph, SpeedGraph, XPosGraph, XVelGraph, YPosGraph, YVelGraph,
 };
 
 use crate::components::PhysicsBody;
@@ -13,14 +13,14 @@ use microprofile::scope;
 #[derive(Default)]
 pub struct LineGraphSys<T>
 where
-    T: LineGraph + Component,
+    T: BodyGraph + Component,
 {
     _phantom_data: std::marker::PhantomData<T>,
 }
 
 impl<'a, T> System<'a> for LineGraphSys<T>
 where
-    T: LineGraph + Component,
+    T: BodyGraph + Component,
 {
     type SystemData = (
         WriteStorage<'a, T>,
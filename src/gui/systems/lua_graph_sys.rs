@@ -0,0 +1,83 @@
+use specs::prelude::*;
+
+use crate::components::{Color, Name, PhysicsBody};
+use crate::gui::graphs::{LineGraph, LuaGraphs};
+use crate::lua::{console::LuaConsole, lua_entity::LuaEntity, with_memory_limit};
+use crate::resources::{LuaBridgeRes, LuaLimits, LuaRes, Paused};
+use crate::BodySet;
+
+/// Samples the graphs made with add_graph() or typed into the side menu
+pub struct LuaGraphSys;
+
+impl<'a> System<'a> for LuaGraphSys {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteStorage<'a, LuaGraphs>,
+        ReadStorage<'a, PhysicsBody>,
+        ReadStorage<'a, Color>,
+        ReadStorage<'a, Name>,
+        ReadExpect<'a, BodySet>,
+        Read<'a, LuaRes>,
+        ReadExpect<'a, LuaBridgeRes>,
+        WriteExpect<'a, LuaConsole>,
+        Read<'a, LuaLimits>,
+        Read<'a, Paused>,
+        Entities<'a>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut lua_graphs,
+            physics_bodies,
+            colors,
+            names,
+            body_set,
+            lua_res,
+            lua_bridge,
+            mut console,
+            limits,
+            paused,
+            entities,
+        ): Self::SystemData,
+    ) {
+        microprofile::scope!("graphs", "lua_graph");
+        if paused.0 || (&lua_graphs).join().next().is_none() {
+            return;
+        }
+
+        // update functions ran since the bodies were last snapshotted
+        lua_bridge
+            .lock()
+            .unwrap()
+            .snapshot_bodies(&body_set, &physics_bodies, &colors, &entities);
+
+        let lua = lua_res.lock().unwrap();
+        lua.context(|lua_ctx| {
+            // the functions of deleted graphs are cleaned up here
+            lua_ctx.expire_registry_values();
+
+            (&mut lua_graphs, &entities)
+                .join()
+                .for_each(|(LuaGraphs(graphs), entity)| {
+                    let handle = LuaEntity(entity, LuaBridgeRes::clone(&lua_bridge));
+                    graphs.iter_mut().for_each(|graph| {
+                        let result = lua_ctx
+                            .registry_value::<rlua::Function>(&graph.function)
+                            .and_then(|function| {
+                                with_memory_limit(&lua, &limits, || {
+                                    function.call::<_, f32>(handle.clone())
+                                })
+                            });
+                        match result {
+                            Ok(val) => graph.add_val(val),
+                            Err(e) => {
+                                let name = names.get(entity).map(|name| name.0.clone());
+                                console.log_error(&e, name);
+                            }
+                        }
+                    });
+                });
+        });
+    }
+}
@@ -14,6 +14,7 @@ impl<'a> System<'a> for MinMaxGraphSys {
         ReadStorage<'a, XPosGraph>,
         ReadStorage<'a, YPosGraph>,
         ReadStorage<'a, RotGraph>,
+        ReadStorage<'a, LuaGraphs>,
         Read<'a, Paused>,
         Write<'a, GraphMinMax>,
     );
@@ -28,6 +29,7 @@ impl<'a> System<'a> for MinMaxGraphSys {
             xpos_graphs,
             ypos_graphs,
             rot_graphs,
+            lua_graphs,
             paused,
             mut min_max,
         ): Self::SystemData,
@@ -56,6 +58,15 @@ impl<'a> System<'a> for MinMaxGraphSys {
         minmax_graph_storage!(xpos_graphs);
         minmax_graph_storage!(ypos_graphs);
         minmax_graph_storage!(rot_graphs);
+        lua_graphs.join().for_each(|LuaGraphs(graphs)| {
+            graphs.iter().for_each(|graph| {
+                let (s0, s1) = graph.points();
+                s0.iter().chain(s1.iter()).for_each(|[_, v]| {
+                    min = min.min(*v);
+                    max = max.max(*v);
+                });
+            });
+        });
 
         min_max.0 = min;
         min_max.1 = max;
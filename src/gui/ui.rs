@@ -5,8 +5,20 @@ use std::convert::TryInto;
 use nphysics2d as np;
 
 use crate::{
-    components::{Collider, Color, Name, PhysicsBody},
+    components::{
+        Collider, Color, FollowPath, ForceGen, Joint, Name, PhysicsBody, Pulley, SoftBodyParticle,
+    },
     gui::signals::UiSignal,
+    lua::console::{ConsoleEntry, ConsoleEntryKind, LuaConsole},
+    lua::goals::{GoalResultsExport, GoalStatus, Goals},
+    lua::lab_controls::{LabControlKind, LabControls},
+    lua::repl::{complete, LuaRepl},
+    lua::HOOK_INTERVAL,
+    main_state::force_field::{FieldShape, ForceField, ForceFieldKind},
+    main_state::joint::{set_break_thresholds, threshold, JointKind},
+    main_state::keyframe_path::{PathInterpolation, PathMode},
+    main_state::pulley::PulleyConstraint,
+    main_state::soft_body::LatticeSprings,
     resources::*,
     types::*,
 };
@@ -71,6 +83,99 @@ pub fn make_menu_bar(ui: &mut imgui::Ui, signals: &mut Vec<UiSignal>, world: &mu
                 ui,
                 signals
             );
+
+            ui.menu(im_str!("Force Field"), true, || {
+                signal_button!(
+                    "Wind",
+                    UiSignal::AddForceField(ForceFieldKind::Wind),
+                    ui,
+                    signals
+                );
+                signal_button!(
+                    "Radial",
+                    UiSignal::AddForceField(ForceFieldKind::Radial),
+                    ui,
+                    signals
+                );
+                signal_button!(
+                    "Vortex",
+                    UiSignal::AddForceField(ForceFieldKind::Vortex),
+                    ui,
+                    signals
+                );
+            });
+
+            ui.menu(im_str!("Soft Body"), true, || {
+                ui.drag_float(
+                    im_str!("Stiffness"),
+                    &mut world.fetch_mut::<CreateStiffness>().0,
+                )
+                .min(1.0)
+                .max(2000.0)
+                .speed(1.0)
+                .build();
+
+                let mut resolution = world.fetch::<CreateResolution>().0 as i32;
+                ui.drag_int(im_str!("Resolution"), &mut resolution)
+                    .min(2)
+                    .max(20)
+                    .build();
+                world.fetch_mut::<CreateResolution>().0 = resolution.clamp(2, 20) as u16;
+
+                signal_button!(
+                    "Soft Rectangle",
+                    UiSignal::AddSoftBody(ShapeInfo::Rectangle(None)),
+                    ui,
+                    signals
+                );
+                signal_button!(
+                    "Soft Circle",
+                    UiSignal::AddSoftBody(ShapeInfo::Circle(None)),
+                    ui,
+                    signals
+                );
+            });
+
+            ui.menu(im_str!("Pulley"), true, || {
+                ui.drag_float(
+                    im_str!("Ratio"),
+                    &mut world.fetch_mut::<CreatePulleyRatio>().0,
+                )
+                .min(0.1)
+                .max(10.0)
+                .speed(0.05)
+                .build();
+
+                ui.text(im_str!(
+                    "Click a body, then each wheel, then the other body"
+                ));
+                signal_button!("Add Pulley", UiSignal::AddPulley, ui, signals);
+            });
+
+            ui.menu(im_str!("Joint"), true, || {
+                ui.drag_float(
+                    im_str!("Break Force"),
+                    &mut world.fetch_mut::<CreateBreakForce>().0,
+                )
+                .min(0.0)
+                .speed(1.0)
+                .build();
+                ui.drag_float(
+                    im_str!("Break Torque"),
+                    &mut world.fetch_mut::<CreateBreakTorque>().0,
+                )
+                .min(0.0)
+                .speed(1.0)
+                .build();
+
+                ui.text(im_str!("0 means the joint can't break"));
+                ui.text(im_str!(
+                    "Click a body, then click the other body where the joint goes"
+                ));
+                signal_button!("Pin", UiSignal::AddJoint(JointKind::Pin), ui, signals);
+                ui.same_line(0.0);
+                signal_button!("Weld", UiSignal::AddJoint(JointKind::Weld), ui, signals);
+            });
         });
 
         ui.separator();
@@ -103,6 +208,68 @@ pub fn make_menu_bar(ui: &mut imgui::Ui, signals: &mut Vec<UiSignal>, world: &mu
                     .build();
                 world.insert(FrameSteps(frame_steps_i32.try_into().unwrap()));
             }
+
+            ui.checkbox(
+                im_str!("Flash Broken Joints"),
+                &mut world.fetch_mut::<FlashBrokenJoints>().0,
+            );
+
+            {
+                ui.separator();
+                let mut limits = world.fetch_mut::<LuaLimits>();
+
+                let mut instructions_i32 =
+                    limits.instructions_per_frame.min(i32::MAX as i64) as i32;
+                ui.drag_int(im_str!("Lua Instructions Per Frame"), &mut instructions_i32)
+                    .min(HOOK_INTERVAL as i32)
+                    .max(i32::MAX)
+                    .speed(10_000.0)
+                    .build();
+                limits.instructions_per_frame = instructions_i32 as i64;
+
+                let mut memory_i32 = limits.memory_kb.min(i32::MAX as usize) as i32;
+                ui.drag_int(im_str!("Lua Memory Limit (KB)"), &mut memory_i32)
+                    .min(1024)
+                    .max(i32::MAX)
+                    .speed(64.0)
+                    .build();
+                limits.memory_kb = memory_i32 as usize;
+
+                let used_kb = world.fetch::<LuaRes>().lock().unwrap().used_memory() / 1024;
+                ui.text(format!("Lua Memory Used: {} KB", used_kb));
+            }
+
+            {
+                ui.separator();
+                let rng = world.fetch::<LuaRngRes>();
+                let mut rng = rng.lock().unwrap();
+
+                // the scene is reset so that it runs again with the new seed
+                let mut seed = ImString::with_capacity(24);
+                seed.push_str(&rng.settings_seed().to_string());
+                if ui
+                    .input_text(im_str!("Random Seed"), &mut seed)
+                    .chars_decimal(true)
+                    .enter_returns_true(true)
+                    .build()
+                {
+                    if let Ok(seed) = seed.to_str().trim().parse() {
+                        rng.set_settings_seed(seed);
+                        signals.push(UiSignal::ResetScene);
+                    }
+                }
+                if ui.is_item_hovered() {
+                    ui.tooltip_text("Press enter to reset the scene with this seed");
+                }
+                if let Some(scene_seed) = rng.scene_seed() {
+                    ui.text(format!("The scene uses its own seed: {}", scene_seed));
+                }
+
+                ui.checkbox(
+                    im_str!("Seeded math.random"),
+                    &mut rng.settings_replace_math_random,
+                );
+            }
         });
 
         ui.separator();
@@ -124,32 +291,48 @@ pub fn make_menu_bar(ui: &mut imgui::Ui, signals: &mut Vec<UiSignal>, world: &mu
         });
         ui.separator();
         ui.menu(im_str!("Load World"), true, || {
-            let dir = std::path::Path::new("./lua");
-            match std::fs::read_dir(dir) {
-                Ok(dir_entries) => {
-                    dir_entries.for_each(|entry| {
-                        if let Ok(entry) = entry {
-                            let filename = entry.file_name().to_string_lossy().into_owned();
-                            let mut utf8_filename = String::new();
-                            filename.chars().for_each(|c| utf8_filename.push(c));
-                            let imstring_filename = ImString::new(utf8_filename);
-
-                            if &filename.as_str()[filename.len() - 4..] == ".lua" {
-                                let label = imstring_filename;
-                                if ui.small_button(&label) {
-                                    signals.push(UiSignal::LoadLua(filename));
-                                }
-                            }
-                        }
-                    });
+            let mut scene_browser = world.fetch_mut::<SceneBrowser>();
+            scene_browser.refresh();
+
+            // scenes with a SCENE table are listed by title, with the description on hover
+            scene_browser.scenes.iter().for_each(|scene| {
+                let info = scene.info.as_ref();
+                let title = info.and_then(|info| info.title.as_ref());
+                let label = ImString::new(format!(
+                    "{}##{}",
+                    title.unwrap_or(&scene.filename),
+                    scene.filename
+                ));
+                if ui.small_button(&label) {
+                    signals.push(UiSignal::LoadLua(scene.filename.clone()));
                 }
-                Err(e) => println!("Error reading dir: {}", e),
-            }
+                if let Some(description) = info.and_then(|info| info.description.as_ref()) {
+                    if ui.is_item_hovered() {
+                        ui.tooltip_text(description);
+                    }
+                }
+                if title.is_some() {
+                    ui.same_line(0.0);
+                    ui.text_disabled(&scene.filename);
+                }
+            });
         });
         ui.separator();
 
         signal_button!("Clear", UiSignal::DeleteAll, ui, signals);
         ui.separator();
+        signal_button!("Reset Scene", UiSignal::ResetScene, ui, signals);
+        ui.checkbox(
+            im_str!("Hot Reload"),
+            &mut world.fetch_mut::<HotReload>().enabled,
+        );
+        ui.separator();
+        signal_button!("Lua Console", UiSignal::ToggleLuaConsole, ui, signals);
+        ui.separator();
+        if world.fetch::<CurrentSceneInfo>().0.is_some() {
+            signal_button!("Instructions", UiSignal::ToggleSceneInfo, ui, signals);
+            ui.separator();
+        }
         let pause_button_str = if world.fetch::<Paused>().0 {
             im_str!("Unpause")
         } else {
@@ -213,8 +396,9 @@ pub fn make_sidemenu(
         }
 
         let mut mass = physics_body.augmented_mass().linear;
+        // same minimum as the Create menu, a mass of 0 can't be loaded again
         ui.drag_float(im_str!("Mass"), &mut mass)
-            .min(0.0)
+            .min(0.001)
             .max(250.0)
             .speed(0.25)
             .build();
@@ -274,6 +458,193 @@ pub fn make_sidemenu(
         color.0.g = color_arr[1];
         color.0.b = color_arr[2];
 
+        // particles share their springs with the rest of their soft body
+        if let Some(particle) = world.read_storage::<SoftBodyParticle>().get(entity) {
+            let mut force_gen_set = world.fetch_mut::<ForceGeneratorSet>();
+            let springs = world
+                .read_storage::<ForceGen>()
+                .get(particle.soft_body)
+                .and_then(|force_gen| force_gen_set.get_mut(force_gen.gen_handle))
+                .and_then(|gen| gen.downcast_mut::<LatticeSprings>());
+
+            if let Some(springs) = springs {
+                ui.separator();
+                ui.text(im_str!("Soft Body"));
+                ui.drag_float(im_str!("Stiffness"), &mut springs.stiffness)
+                    .min(0.0)
+                    .max(2000.0)
+                    .speed(1.0)
+                    .build();
+                ui.drag_float(im_str!("Damping"), &mut springs.damping)
+                    .min(0.0)
+                    .max(50.0)
+                    .speed(0.05)
+                    .build();
+                ui.separator();
+            }
+        }
+
+        {
+            let pulleys = world.read_storage::<Pulley>();
+            let force_gens = world.read_storage::<ForceGen>();
+            let entities = world.entities();
+            let mut force_gen_set = world.fetch_mut::<ForceGeneratorSet>();
+
+            (&pulleys, &force_gens, &entities)
+                .join()
+                .filter(|(pulley, _, _)| pulley.body1 == entity || pulley.body2 == entity)
+                .enumerate()
+                .for_each(|(i, (pulley, force_gen, pulley_entity))| {
+                    let constraint = match force_gen_set
+                        .get_mut(force_gen.gen_handle)
+                        .and_then(|gen| gen.downcast_mut::<PulleyConstraint>())
+                    {
+                        Some(constraint) => constraint,
+                        None => return,
+                    };
+
+                    // the rope on the second body's side is pulled ratio times as hard
+                    let tension = if pulley.body1 == entity {
+                        constraint.tension
+                    } else {
+                        constraint.tension * constraint.ratio
+                    };
+
+                    let id = ui.push_id(i as i32);
+                    ui.separator();
+                    ui.text(format!("Pulley Tension: {:.3} N", tension));
+                    ui.drag_float(im_str!("Pulley Ratio"), &mut constraint.ratio)
+                        .min(0.1)
+                        .max(10.0)
+                        .speed(0.05)
+                        .build();
+                    ui.drag_float(im_str!("Rope Length"), &mut constraint.length)
+                        .min(0.0)
+                        .speed(0.05)
+                        .build();
+                    signal_button!(
+                        "Delete Pulley",
+                        UiSignal::DeleteShape(pulley_entity),
+                        ui,
+                        signals
+                    );
+                    id.pop(ui);
+                });
+        }
+
+        {
+            let mut joints = world.write_storage::<Joint>();
+            let entities = world.entities();
+            let mut joint_set = world.fetch_mut::<JointConstraintSet>();
+
+            (&mut joints, &entities)
+                .join()
+                .filter(|(joint, _)| {
+                    joint.broken_flash.is_none() && (joint.body1 == entity || joint.body2 == entity)
+                })
+                .enumerate()
+                .for_each(|(i, (joint, joint_entity))| {
+                    let id = ui.push_id(i as i32);
+                    ui.separator();
+                    ui.text(format!("Joint: {}", joint.kind.name()));
+
+                    // 0 means the joint can't break
+                    let mut break_force = joint.break_force.unwrap_or(0.0);
+                    let mut break_torque = joint.break_torque.unwrap_or(0.0);
+                    let force_changed = ui
+                        .drag_float(im_str!("Break Force"), &mut break_force)
+                        .min(0.0)
+                        .speed(1.0)
+                        .build();
+                    let torque_changed = ui
+                        .drag_float(im_str!("Break Torque"), &mut break_torque)
+                        .min(0.0)
+                        .speed(1.0)
+                        .build();
+                    if force_changed || torque_changed {
+                        joint.break_force = threshold(break_force);
+                        joint.break_torque = threshold(break_torque);
+                        set_break_thresholds(
+                            &mut joint_set,
+                            joint.joint_handle,
+                            joint.break_force,
+                            joint.break_torque,
+                        );
+                    }
+
+                    signal_button!(
+                        "Delete Joint",
+                        UiSignal::DeleteShape(joint_entity),
+                        ui,
+                        signals
+                    );
+                    id.pop(ui);
+                });
+        }
+
+        {
+            ui.separator();
+            let recording = world
+                .fetch::<RecordPath>()
+                .0
+                .as_ref()
+                .map(|draft| draft.entity == entity);
+            let mut follow_paths = world.write_storage::<FollowPath>();
+
+            match (recording, follow_paths.get_mut(entity)) {
+                (Some(true), _) => {
+                    ui.text(im_str!("Click to add waypoints"));
+                    ui.drag_float(
+                        im_str!("Seconds Between Waypoints"),
+                        &mut world.fetch_mut::<RecordPathInterval>().0,
+                    )
+                    .min(0.05)
+                    .max(60.0)
+                    .speed(0.05)
+                    .build();
+                    signal_button!("Finish Path", UiSignal::FinishPath, ui, signals);
+                    ui.same_line(0.0);
+                    signal_button!("Cancel Path", UiSignal::CancelPath, ui, signals);
+                }
+                (_, Some(follow_path)) => {
+                    let path = &mut follow_path.path;
+                    ui.text(format!(
+                        "Path: {} waypoints, {:.2} s",
+                        path.keyframes.len(),
+                        path.duration()
+                    ));
+
+                    let mut interpolation_index = PathInterpolation::ALL
+                        .iter()
+                        .position(|interpolation| *interpolation == path.interpolation)
+                        .unwrap();
+                    ComboBox::new(im_str!("Interpolation")).build_simple_string(
+                        ui,
+                        &mut interpolation_index,
+                        &[im_str!("Linear"), im_str!("Spline")],
+                    );
+                    path.interpolation = PathInterpolation::ALL[interpolation_index];
+
+                    let mut mode_index = PathMode::ALL
+                        .iter()
+                        .position(|mode| *mode == path.mode)
+                        .unwrap();
+                    ComboBox::new(im_str!("Mode")).build_simple_string(
+                        ui,
+                        &mut mode_index,
+                        &[im_str!("Loop"), im_str!("Ping Pong")],
+                    );
+                    path.mode = PathMode::ALL[mode_index];
+
+                    signal_button!("Remove Path", UiSignal::RemovePath(entity), ui, signals);
+                }
+                _ => {
+                    signal_button!("Record Path", UiSignal::StartPath(entity), ui, signals);
+                }
+            }
+            ui.separator();
+        }
+
         ui.menu(im_str!("Add Graph"), true, || {
             signal_button!("Graph Speed", UiSignal::AddSpeedGraph(entity), ui, signals);
             signal_button!(
@@ -287,11 +658,487 @@ pub fn make_sidemenu(
             signal_button!("Graph X Pos", UiSignal::AddXPosGraph(entity), ui, signals);
             signal_button!("Graph Y Pos", UiSignal::AddYPosGraph(entity), ui, signals);
             signal_button!("Graph Rotation", UiSignal::AddRotGraph(entity), ui, signals);
+
+            ui.separator();
+            // obj is the body's handle, like the entries of OBJECTS
+            let mut expression = ImString::new(world.fetch::<GraphExpression>().0.clone());
+            ui.input_text(im_str!("Lua Expression"), &mut expression)
+                .resize_buffer(true)
+                .build();
+            let expression = expression.to_string();
+            if ui.small_button(im_str!("Graph Expression")) && !expression.trim().is_empty() {
+                signals.push(UiSignal::AddExpressionGraph(entity, expression.clone()));
+            }
+            world.fetch_mut::<GraphExpression>().0 = expression;
         });
         signal_button!("Delete Shape", UiSignal::DeleteShape(entity), ui, signals);
     });
 }
 
+pub fn make_force_field_sidemenu(
+    ui: &mut imgui::Ui,
+    world: &World,
+    entity: Entity,
+    signals: &mut Vec<UiSignal>,
+) {
+    let mut force_gen_set = world.fetch_mut::<ForceGeneratorSet>();
+    // soft bodies and pulleys have force generators too, but no menu
+    let field = {
+        let force_gens = world.read_storage::<ForceGen>();
+        let gen_handle = match force_gens.get(entity) {
+            Some(force_gen) => force_gen.gen_handle,
+            None => return,
+        };
+        match force_gen_set
+            .get_mut(gen_handle)
+            .and_then(|gen| gen.downcast_mut::<ForceField>())
+        {
+            Some(field) => field,
+            None => return,
+        }
+    };
+
+    let resolution = world.fetch::<Resolution>().0;
+    let win = imgui::Window::new(im_str!("Force Field Info"))
+        .position([0.0, 30.0], imgui::Condition::Always)
+        .size(
+            [resolution.x * 0.40, resolution.y - 30.0],
+            imgui::Condition::Appearing,
+        )
+        .size_constraints(
+            [resolution.x * 0.2, resolution.y - 30.0],
+            [resolution.x * 0.6, resolution.y - 30.0],
+        )
+        .collapsible(false)
+        .movable(false);
+
+    win.build(ui, || {
+        let mut kind_index = ForceFieldKind::ALL
+            .iter()
+            .position(|kind| *kind == field.kind)
+            .unwrap();
+        ComboBox::new(im_str!("Kind")).build_simple_string(
+            ui,
+            &mut kind_index,
+            &[im_str!("Wind"), im_str!("Radial"), im_str!("Vortex")],
+        );
+        field.kind = ForceFieldKind::ALL[kind_index];
+
+        ui.drag_float(im_str!("Strength"), &mut field.strength)
+            .speed(0.1)
+            .build();
+
+        if field.kind == ForceFieldKind::Wind {
+            ui.drag_float(im_str!("Angle"), &mut field.angle)
+                .speed(0.05)
+                .build();
+        }
+
+        let mut center = [field.center.x, field.center.y];
+        ui.drag_float2(im_str!("Position"), &mut center)
+            .speed(0.05)
+            .build();
+        field.center = Vector::new(center[0], center[1]);
+
+        let mut shape_index = match field.shape {
+            FieldShape::Rectangle(_) => 0,
+            FieldShape::Circle(_) => 1,
+        };
+        ComboBox::new(im_str!("Shape")).build_simple_string(
+            ui,
+            &mut shape_index,
+            &[im_str!("Rectangle"), im_str!("Circle")],
+        );
+        field.shape = match (field.shape, shape_index) {
+            (FieldShape::Rectangle(half_extents), 0) => {
+                let mut half_extents = [half_extents.x, half_extents.y];
+                ui.drag_float2(im_str!("Half Extents"), &mut half_extents)
+                    .min(0.01)
+                    .speed(0.05)
+                    .build();
+                FieldShape::Rectangle(Vector::new(half_extents[0], half_extents[1]))
+            }
+            (FieldShape::Circle(mut r), 1) => {
+                ui.drag_float(im_str!("Radius"), &mut r)
+                    .min(0.01)
+                    .speed(0.05)
+                    .build();
+                FieldShape::Circle(r)
+            }
+            (FieldShape::Rectangle(half_extents), _) => {
+                FieldShape::Circle(half_extents.x.max(half_extents.y))
+            }
+            (FieldShape::Circle(r), _) => FieldShape::Rectangle(Vector::new(r, r)),
+        };
+
+        signal_button!("Delete Field", UiSignal::DeleteShape(entity), ui, signals);
+    });
+}
+
+/// Shows Lua errors and print() output with a REPL underneath,
+/// returns false once the window is closed
+/// Shows the Notification at the top of the screen until it's a few seconds old
+pub fn make_notification(ui: &mut imgui::Ui, world: &World) {
+    const SHOWN_FOR: std::time::Duration = std::time::Duration::from_secs(3);
+
+    let mut notification = world.fetch_mut::<Notification>();
+    let message = match &notification.0 {
+        Some(message) if message.shown_at.elapsed() < SHOWN_FOR => message,
+        Some(_) => {
+            notification.0 = None;
+            return;
+        }
+        None => return,
+    };
+    let resolution = world.fetch::<Resolution>().0;
+
+    imgui::Window::new(im_str!("Notification"))
+        .position([resolution.x * 0.5, 40.0], imgui::Condition::Always)
+        .position_pivot([0.5, 0.0])
+        .no_decoration()
+        .always_auto_resize(true)
+        .focus_on_appearing(false)
+        .build(ui, || {
+            if message.error {
+                ui.text_colored([1.0, 0.4, 0.4, 1.0], &message.text);
+            } else {
+                ui.text(&message.text);
+            }
+        });
+}
+
+/// The sliders, checkboxes, and buttons that the scene made with param(), checkbox(), and
+/// button(). Controls with rerun set run the scene again once they're let go of.
+pub fn make_lab_controls(ui: &mut imgui::Ui, world: &World, signals: &mut Vec<UiSignal>) {
+    let mut lab_controls = world.fetch_mut::<LabControls>();
+    if lab_controls.controls.is_empty() {
+        return;
+    }
+    let resolution = world.fetch::<Resolution>().0;
+
+    imgui::Window::new(im_str!("Lab Controls"))
+        .position([resolution.x * 0.75, 40.0], imgui::Condition::FirstUseEver)
+        .always_auto_resize(true)
+        .build(ui, || {
+            lab_controls
+                .controls
+                .iter_mut()
+                .enumerate()
+                .for_each(|(i, control)| {
+                    let label = ImString::new(control.name.clone());
+                    let changed = match &mut control.kind {
+                        LabControlKind::Slider {
+                            min,
+                            max,
+                            step,
+                            value,
+                        } => {
+                            let changed = imgui::Slider::new(&label, *min..=*max)
+                                .display_format(im_str!("%.4g"))
+                                .build(ui, value);
+                            if let (true, Some(step)) = (changed, step) {
+                                *value = (*value / *step).round() * *step;
+                            }
+                            changed
+                        }
+                        LabControlKind::Checkbox { value } => ui.checkbox(&label, value),
+                        LabControlKind::Button => ui.button(&label, [0.0, 0.0]),
+                    };
+                    if changed {
+                        signals.push(UiSignal::LabControlChanged(i));
+                    }
+
+                    // sliders change every frame while they're dragged
+                    let finished = match control.kind {
+                        LabControlKind::Slider { .. } => ui.is_item_deactivated_after_edit(),
+                        _ => changed,
+                    };
+                    if control.rerun && finished {
+                        signals.push(UiSignal::RerunScene);
+                    }
+                });
+        });
+}
+
+/// A checklist of the goals that the scene made with goal(), with the time each one
+/// passed or failed, and where the results can be exported to
+pub fn make_goals(ui: &mut imgui::Ui, world: &World, signals: &mut Vec<UiSignal>) {
+    let goals = world.fetch::<Goals>();
+    if goals.0.is_empty() {
+        return;
+    }
+    let resolution = world.fetch::<Resolution>().0;
+
+    imgui::Window::new(im_str!("Goals"))
+        .position(
+            [resolution.x * 0.75, resolution.y * 0.5],
+            imgui::Condition::FirstUseEver,
+        )
+        .always_auto_resize(true)
+        .build(ui, || {
+            let passed = goals
+                .0
+                .iter()
+                .filter(|goal| matches!(goal.status, GoalStatus::Passed(_)))
+                .count();
+            ui.text(format!("{}/{} passed", passed, goals.0.len()));
+            ui.separator();
+
+            goals.0.iter().for_each(|goal| {
+                match goal.status {
+                    GoalStatus::Pending => ui.text_disabled("[ ]"),
+                    GoalStatus::Passed(_) => ui.text_colored([0.4, 1.0, 0.4, 1.0], "[x]"),
+                    GoalStatus::Failed(_) => ui.text_colored([1.0, 0.4, 0.4, 1.0], "[-]"),
+                }
+                ui.same_line(0.0);
+                ui.text(&goal.description);
+                if let Some(time) = goal.status.time() {
+                    ui.same_line(0.0);
+                    ui.text_disabled(format!("{} at {:.2} s", goal.status.name(), time));
+                } else if let Some(deadline) = goal.deadline {
+                    ui.same_line(0.0);
+                    ui.text_disabled(format!("by {:.2} s", deadline));
+                }
+            });
+
+            ui.separator();
+            let mut export = world.fetch_mut::<GoalResultsExport>();
+            let mut student = ImString::new(export.student.clone());
+            ui.input_text(im_str!("Name"), &mut student).build();
+            export.student = student.to_string();
+            let mut filename = ImString::new(export.filename.clone());
+            ui.input_text(im_str!("Filename"), &mut filename).build();
+            export.filename = filename.to_string();
+            if ui.is_item_hovered() {
+                ui.tooltip_text("Saved as JSON if it ends in .json, otherwise CSV");
+            }
+
+            signal_button!("Export Results", UiSignal::ExportGoalResults, ui, signals);
+        });
+}
+
+/// The current scene's title, author, description, and instructions. The instructions
+/// can have # headings and - or * bullet points, and blank lines separate paragraphs.
+pub fn make_scene_info(ui: &mut imgui::Ui, world: &World) -> bool {
+    let scene_info = world.fetch::<CurrentSceneInfo>();
+    let info = match &scene_info.0 {
+        Some(info) => info,
+        None => return false,
+    };
+    let mut opened = true;
+    let resolution = world.fetch::<Resolution>().0;
+
+    imgui::Window::new(im_str!("Instructions"))
+        .position(
+            [resolution.x * 0.3, resolution.y * 0.15],
+            imgui::Condition::FirstUseEver,
+        )
+        .size(
+            [resolution.x * 0.4, resolution.y * 0.5],
+            imgui::Condition::FirstUseEver,
+        )
+        .opened(&mut opened)
+        .build(ui, || {
+            if let Some(title) = &info.title {
+                ui.text_colored([1.0, 0.85, 0.4, 1.0], title);
+            }
+            if let Some(author) = &info.author {
+                ui.text_disabled(format!("by {}", author));
+            }
+            if let Some(description) = &info.description {
+                ui.text_wrapped(&ImString::new(description.clone()));
+            }
+
+            if let Some(instructions) = &info.instructions {
+                ui.separator();
+                instructions.lines().map(str::trim).for_each(|line| {
+                    if line.is_empty() {
+                        ui.spacing();
+                    } else if line.starts_with('#') {
+                        ui.spacing();
+                        ui.text_colored([0.6, 0.8, 1.0, 1.0], line.trim_start_matches('#').trim());
+                    } else if line.starts_with("- ") || line.starts_with("* ") {
+                        // bullet_text() doesn't wrap
+                        ui.bullet();
+                        ui.text_wrapped(&ImString::new(&line[2..]));
+                    } else {
+                        ui.text_wrapped(&ImString::new(line));
+                    }
+                });
+            }
+        });
+
+    opened
+}
+
+pub fn make_lua_console(ui: &mut imgui::Ui, world: &World, signals: &mut Vec<UiSignal>) -> bool {
+    let mut opened = true;
+    let mut console = world.fetch_mut::<LuaConsole>();
+    let mut repl = world.fetch_mut::<LuaRepl>();
+    let resolution = world.fetch::<Resolution>().0;
+
+    imgui::Window::new(im_str!("Lua Console"))
+        .position(
+            [resolution.x * 0.25, resolution.y * 0.65],
+            imgui::Condition::FirstUseEver,
+        )
+        .size(
+            [resolution.x * 0.5, resolution.y * 0.3],
+            imgui::Condition::FirstUseEver,
+        )
+        .opened(&mut opened)
+        .build(ui, || {
+            ui.checkbox(
+                im_str!("Pause On Error"),
+                &mut world.fetch_mut::<PauseOnLuaError>().0,
+            );
+            ui.same_line(0.0);
+            if ui.small_button(im_str!("Clear")) {
+                console.clear();
+            }
+            ui.separator();
+
+            // leaves room for the input line
+            imgui::ChildWindow::new(im_str!("Lua Output"))
+                .size([0.0, -ui.frame_height_with_spacing()])
+                .build(ui, || {
+                    // stay scrolled to the bottom unless the user scrolled up
+                    let at_bottom = ui.scroll_y() >= ui.scroll_max_y();
+
+                    console.entries.iter().for_each(|entry| {
+                        let source = entry.source();
+                        if !source.is_empty() {
+                            ui.text_disabled(source);
+                            ui.same_line(0.0);
+                        }
+
+                        let count = if entry.count > 1 {
+                            format!(" (x{})", entry.count)
+                        } else {
+                            String::new()
+                        };
+                        let text = format!("{}{}", entry.message, count);
+                        match entry.kind {
+                            ConsoleEntryKind::Error => ui.text_colored([1.0, 0.4, 0.4, 1.0], text),
+                            ConsoleEntryKind::Input => ui.text_colored([0.6, 0.8, 1.0, 1.0], text),
+                            ConsoleEntryKind::Print | ConsoleEntryKind::Result => ui.text(text),
+                        }
+                    });
+
+                    if at_bottom {
+                        ui.set_scroll_here_y();
+                    }
+                });
+
+            // >> means the last line didn't finish the chunk, e.g. it started a function
+            ui.text(if repl.pending.is_empty() { ">" } else { ">>" });
+            ui.same_line(0.0);
+            ui.set_next_item_width(-1.0);
+            let lua = world.fetch::<LuaRes>();
+            let (entered, candidates) = lua_repl_input(ui, &mut repl, &lua);
+            if entered {
+                signals.push(UiSignal::RunLuaRepl(repl.submit()));
+                // enter unfocuses the input, but it should stay focused to type the next line
+                ui.set_keyboard_focus_here(FocusedWidget::Previous);
+            }
+            if !candidates.is_empty() {
+                console.log(ConsoleEntry::new(
+                    ConsoleEntryKind::Print,
+                    candidates.join("  "),
+                ));
+            }
+        });
+
+    opened
+}
+
+/// what the REPL input's callback needs, passed to it through imgui as user data
+struct ReplCallbackData<'a> {
+    repl: &'a mut LuaRepl,
+    lua: &'a LuaRes,
+    candidates: Vec<String>,
+}
+
+/// The REPL input line, returns whether enter was pressed and the matches if tab completion
+/// found more than one. imgui-rs doesn't wrap input text callbacks, which are needed to
+/// change the text while it's being edited, so this calls into imgui-sys directly.
+fn lua_repl_input(_ui: &imgui::Ui, repl: &mut LuaRepl, lua: &LuaRes) -> (bool, Vec<String>) {
+    // the callback doesn't resize the buffer so it has room for a long line
+    let mut buf = vec![0u8; 1024];
+    let len = repl.input.len().min(buf.len() - 1);
+    buf[..len].copy_from_slice(&repl.input.as_bytes()[..len]);
+
+    let mut data = ReplCallbackData {
+        repl,
+        lua,
+        candidates: Vec::new(),
+    };
+    let flags = ImGuiInputTextFlags::EnterReturnsTrue
+        | ImGuiInputTextFlags::CallbackCompletion
+        | ImGuiInputTextFlags::CallbackHistory;
+
+    let entered = unsafe {
+        imgui::sys::igInputText(
+            im_str!("##lua_repl").as_ptr(),
+            buf.as_mut_ptr() as *mut std::os::raw::c_char,
+            buf.len(),
+            flags.bits(),
+            Some(lua_repl_callback),
+            &mut data as *mut ReplCallbackData as *mut std::os::raw::c_void,
+        )
+    };
+
+    let len = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    data.repl.input = String::from_utf8_lossy(&buf[..len]).into_owned();
+    (entered, data.candidates)
+}
+
+/// tab completes globals and the up and down arrows go through the history
+extern "C" fn lua_repl_callback(
+    data: *mut imgui::sys::ImGuiInputTextCallbackData,
+) -> std::os::raw::c_int {
+    // imgui only calls this from igInputText() in lua_repl_input(),
+    // where the ReplCallbackData is still alive
+    let data = unsafe { &mut *data };
+    let user_data = unsafe { &mut *(data.UserData as *mut ReplCallbackData) };
+    let text =
+        unsafe { std::slice::from_raw_parts(data.Buf as *const u8, data.BufTextLen as usize) };
+    let text = String::from_utf8_lossy(text).into_owned();
+
+    let insert_chars = |data: &mut imgui::sys::ImGuiInputTextCallbackData, pos, s: &str| unsafe {
+        let s = s.as_bytes();
+        imgui::sys::ImGuiInputTextCallbackData_InsertChars(
+            data,
+            pos,
+            s.as_ptr() as *const std::os::raw::c_char,
+            s.as_ptr().add(s.len()) as *const std::os::raw::c_char,
+        );
+    };
+
+    if data.EventFlag == ImGuiInputTextFlags::CallbackCompletion.bits() {
+        let before_cursor = text.get(..data.CursorPos as usize).unwrap_or(&text);
+        let completion = user_data
+            .lua
+            .lock()
+            .unwrap()
+            .context(|lua_ctx| complete(lua_ctx, before_cursor));
+        insert_chars(data, data.CursorPos, &completion.insert);
+        user_data.candidates = completion.candidates;
+    } else if data.EventFlag == ImGuiInputTextFlags::CallbackHistory.bits() {
+        let entry = if data.EventKey == imgui::sys::ImGuiKey_UpArrow as i32 {
+            user_data.repl.history_prev()
+        } else {
+            user_data.repl.history_next()
+        };
+        if let Some(entry) = entry.map(str::to_string) {
+            unsafe { imgui::sys::ImGuiInputTextCallbackData_DeleteChars(data, 0, data.BufTextLen) };
+            insert_chars(data, 0, &entry);
+        }
+    }
+
+    0
+}
+
 pub fn make_default_ui(ui: &mut imgui::Ui) {
     // Window
     imgui::Window::new(im_str!("Hello world"))
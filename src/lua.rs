@@ -1,12 +1,35 @@
-use crate::main_state::{body_builder::BodyBuilder, MainState};
-use crate::resources::LuaRes;
+use crate::main_state::{
+    body_builder::BodyBuilder,
+    force_field::{FieldShape, ForceField, ForceFieldKind},
+    joint::{threshold, JointBuilder, JointKind},
+    keyframe_path::{Keyframe, KeyframePath, PathInterpolation, PathMode},
+    pulley::{PulleyBuilder, PulleyConstraint},
+    soft_body::{LatticeSprings, SoftBodyBuilder},
+    MainState,
+};
+use crate::resources::{
+    CurrentSceneInfo, HotReload, LuaBridgeRes, LuaInstructionsLeft, LuaLimits, LuaRes, LuaRngRes,
+    SceneInfo,
+};
+use console::{ConsoleEntry, ConsoleEntryKind, LuaConsole};
+use drawing::{Drawing, LuaDrawings};
+use goals::{Goal, GoalStatus, Goals};
+use lab_controls::{LabControl, LabControlKind, LabControls};
+use lua_entity::{get_lua_data, set_lua_data, BodySnapshot, LuaEntity};
+use repl::{pretty_print, LuaRepl};
 
-use crate::components::{Collider, Color, Name, PhysicsBody};
-use crate::resources::{self, Paused, ShapeInfo};
+use crate::components::{
+    Collider, Color, FollowPath, ForceGen, Joint, Name, PhysicsBody, Pulley, SoftBody,
+    SoftBodyParticle,
+};
+use crate::resources::{self, BrokenJoints, PauseOnLuaError, Paused, ShapeInfo};
 
-use crate::{BodySet, ColliderSet, MechanicalWorld, RigidBody, Vector};
+use crate::gui::graphs::{LuaGraph, LuaGraphs};
+use crate::gui::imgui_wrapper::UiChoice;
+
+use crate::{BodySet, ColliderSet, ForceGeneratorSet, MechanicalWorld, RigidBody, Vector};
 use np::material::BasicMaterial;
-use np::object::Body;
+use np::object::{Body, DefaultBodyHandle};
 use nphysics2d as np;
 
 use nc::shape::{Ball, Cuboid};
@@ -17,6 +40,20 @@ use specs::prelude::*;
 
 use rlua::prelude::*;
 
+use std::sync::atomic::Ordering;
+
+use ggez::input::{keyboard::KeyCode, mouse::MouseButton};
+
+pub mod body_proxy;
+pub mod console;
+pub mod drawing;
+pub mod goals;
+pub mod lab_controls;
+pub mod lua_entity;
+pub mod queries;
+pub mod repl;
+pub mod rng;
+pub mod schema;
 pub mod update_fn_sys;
 
 // TODO:
@@ -33,12 +70,44 @@ pub mod update_fn_sys;
 // Using this pure sort of function extra Lua glue can be written so that users
 // can easily construct functions which handle integration and collision math separately.
 
+/// global functions that need the world, so they only work in run_with_world_fns()
+const WORLD_FNS: [&str; 6] = [
+    "spawn",
+    "destroy",
+    "raycast",
+    "query_point",
+    "query_rect",
+    "nearest",
+];
+
+/// global functions that the simulation calls when something happens
+const LUA_HANDLERS: [&str; 5] = [
+    "on_joint_break",
+    "on_key_down",
+    "on_key_up",
+    "on_mouse_down",
+    "on_mouse_up",
+];
+
 pub trait LuaResExt {
-    fn run_lua_code(&mut self, code: String);
-    fn run_lua_file(&self, filename: impl AsRef<std::path::Path> + std::clone::Clone);
+    fn run_lua_file(
+        &self,
+        filename: impl AsRef<std::path::Path> + std::clone::Clone,
+        limits: &LuaLimits,
+        instructions_left: &LuaInstructionsLeft,
+    ) -> rlua::Result<()>;
 }
 
-pub fn new_lua_res() -> LuaRes {
+/// how often the instruction hook counts down LuaInstructionsLeft
+pub const HOOK_INTERVAL: u32 = 1000;
+
+pub fn new_lua_res(instructions_left: LuaInstructionsLeft, rng: LuaRngRes) -> LuaRes {
+    std::sync::Arc::new(std::sync::Mutex::new(new_lua(instructions_left, rng)))
+}
+
+/// A Lua state with the globals and functions that scenes expect, also used to
+/// start over when a scene is hot reloaded
+pub fn new_lua(instructions_left: LuaInstructionsLeft, rng: LuaRngRes) -> Lua {
     let mut lua_stdlib = rlua::StdLib::empty();
     lua_stdlib.insert(rlua::StdLib::BASE);
     lua_stdlib.insert(rlua::StdLib::TABLE);
@@ -46,35 +115,367 @@ pub fn new_lua_res() -> LuaRes {
     lua_stdlib.insert(rlua::StdLib::STRING);
 
     let lua = Lua::new_with(lua_stdlib);
-    lua.set_memory_limit(Some(262_144));
-    // lua.set_hook(
-    //     rlua::HookTriggers {
-    //         every_nth_instruction: Some(75_000),
-    //         ..Default::default()
-    //     },
-    //     |_, _| panic!("Lua script exceeded instruction limit"),
-    // );
+    let budget_left = instructions_left.clone();
+
+    // errors once the scripts have used up the frame's budget, see MainState::reset_lua_budget()
+    lua.set_hook(
+        rlua::HookTriggers {
+            every_nth_instruction: Some(HOOK_INTERVAL),
+            ..Default::default()
+        },
+        move |_, _| {
+            let left = instructions_left.fetch_sub(i64::from(HOOK_INTERVAL), Ordering::Relaxed);
+            if left > 0 {
+                Ok(())
+            } else {
+                Err(rlua::Error::RuntimeError(
+                    "ran out of instructions for this frame, is there an infinite loop? \
+                     The limit can be raised in the Settings menu or with set_limits{}"
+                        .to_string(),
+                ))
+            }
+        },
+    );
 
     lua.context(|lua_ctx| {
         let globals = lua_ctx.globals();
         let shapes: Vec<rlua::Table> = Vec::new();
         globals.set("shapes", shapes).unwrap();
+        let force_fields: Vec<rlua::Table> = Vec::new();
+        globals.set("force_fields", force_fields).unwrap();
+        let soft_bodies: Vec<rlua::Table> = Vec::new();
+        globals.set("soft_bodies", soft_bodies).unwrap();
+        let pulleys: Vec<rlua::Table> = Vec::new();
+        globals.set("pulleys", pulleys).unwrap();
+        let joints: Vec<rlua::Table> = Vec::new();
+        globals.set("joints", joints).unwrap();
+        let graphs: Vec<rlua::Table> = Vec::new();
+        globals.set("graphs", graphs).unwrap();
+        let lab_controls: Vec<rlua::Table> = Vec::new();
+        globals.set("lab_controls", lab_controls).unwrap();
+        let drawings: Vec<rlua::Table> = Vec::new();
+        globals.set("drawings", drawings).unwrap();
+        let goals: Vec<rlua::Table> = Vec::new();
+        globals.set("goals", goals).unwrap();
+        globals
+            .set("timers", lua_ctx.create_table().unwrap())
+            .unwrap();
+        globals
+            .set("PARAMS", lua_ctx.create_table().unwrap())
+            .unwrap();
+        globals
+            .set("PULLEYS", lua_ctx.create_table().unwrap())
+            .unwrap();
+        globals
+            .set("KEYS_DOWN", lua_ctx.create_table().unwrap())
+            .unwrap();
+        globals
+            .set("console_output", lua_ctx.create_table().unwrap())
+            .unwrap();
         globals.set("PAUSED", false).unwrap();
+        globals.set("TIME", 0.0).unwrap();
         globals.set("GRAVITY", 9.81).unwrap();
         globals.set("PI", std::f32::consts::PI).unwrap();
         globals.set("SCREEN_X", crate::SCREEN_X).unwrap();
         globals.set("SCREEN_Y", crate::SCREEN_Y).unwrap();
+        globals
+            .set("rng", rng::rng_table(lua_ctx, &rng).unwrap())
+            .unwrap();
+        globals
+            .set(
+                "out_of_instructions",
+                lua_ctx
+                    .create_function(move |_, ()| Ok(budget_left.load(Ordering::Relaxed) <= 0))
+                    .unwrap(),
+            )
+            .unwrap();
 
         lua_ctx
             .load(
                 r#"
-                    function add_shape(shape)
+                    -- pcall() and xpcall() can't catch running out of instructions, otherwise
+                    -- while true do pcall(f) end would never stop
+                    local instructions_used_up = out_of_instructions
+                    out_of_instructions = nil
+                    local builtin_pcall, builtin_xpcall = pcall, xpcall
+
+                    local function rethrow_if_out_of_instructions(ok, ...)
+                        if not ok and instructions_used_up() then
+                            error((...), 0)
+                        end
+                        return ok, ...
+                    end
+
+                    function pcall(...)
+                        return rethrow_if_out_of_instructions(builtin_pcall(...))
+                    end
+
+                    function xpcall(...)
+                        return rethrow_if_out_of_instructions(builtin_xpcall(...))
+                    end
+
+                    -- math.random() and math.randomseed() use rng once rng.replace_math_random(true)
+                    -- is called or it's turned on in the Settings menu
+                    local rng = rng
+                    local builtin_random, builtin_randomseed = math.random, math.randomseed
+
+                    function math.random(...)
+                        if rng.replace_math_random() then
+                            return rng.random(...)
+                        end
+                        return builtin_random(...)
+                    end
+
+                    function math.randomseed(...)
+                        if rng.replace_math_random() then
+                            return rng.seed(...)
+                        end
+                        return builtin_randomseed(...)
+                    end
+
+                    -- where each shape was added from, so that errors in it can point there
+                    shape_locations = setmetatable({}, {__mode = "k"})
+
+                    local function add_shape_from_caller(shape)
+                        if type(shape) == "table" then
+                            local _, location = pcall(error, "", 4)
+                            shape_locations[shape] = location
+                        end
                         shapes[#shapes+1] = shape
                     end
 
+                    function add_shape(shape)
+                        add_shape_from_caller(shape)
+                    end
+
                     function add_shapes(...)
                         for _, shape in ipairs{...} do
-                            add_shape(shape)
+                            add_shape_from_caller(shape)
+                        end
+                    end
+
+                    function add_force_field(field)
+                        force_fields[#force_fields+1] = field
+                    end
+
+                    function add_force_fields(...)
+                        for _, field in ipairs{...} do
+                            add_force_field(field)
+                        end
+                    end
+
+                    function add_soft_body(soft_body)
+                        soft_bodies[#soft_bodies+1] = soft_body
+                    end
+
+                    function add_soft_bodies(...)
+                        for _, soft_body in ipairs{...} do
+                            add_soft_body(soft_body)
+                        end
+                    end
+
+                    function add_pulley(pulley)
+                        pulleys[#pulleys+1] = pulley
+                    end
+
+                    function add_pulleys(...)
+                        for _, pulley in ipairs{...} do
+                            add_pulley(pulley)
+                        end
+                    end
+
+                    function add_joint(joint)
+                        joints[#joints+1] = joint
+                    end
+
+                    function add_joints(...)
+                        for _, joint in ipairs{...} do
+                            add_joint(joint)
+                        end
+                    end
+
+                    function add_graph(graph)
+                        graphs[#graphs+1] = graph
+                    end
+
+                    -- param(), checkbox(), and button() add to the Lab Controls window,
+                    -- param() and checkbox() return the value, which is also in PARAMS
+                    local function check_lab_control(fn_name, control)
+                        if type(control) ~= "table" or type(control.name) ~= "string" then
+                            error(fn_name .. "() needs a table with a name", 3)
+                        end
+                    end
+
+                    local function add_lab_control(kind, control, default)
+                        control.kind = kind
+                        lab_controls[#lab_controls+1] = control
+                        if default ~= nil then
+                            -- the value from the last time the scene was run is kept
+                            if type(PARAMS[control.name]) ~= type(default) then
+                                PARAMS[control.name] = default
+                            end
+                            return PARAMS[control.name]
+                        end
+                    end
+
+                    function param(control)
+                        check_lab_control("param", control)
+                        local value = add_lab_control("slider", control, control.default or control.min or 0)
+                        if type(control.min) == "number" and type(control.max) == "number" then
+                            value = math.max(control.min, math.min(value, control.max))
+                            PARAMS[control.name] = value
+                        end
+                        return value
+                    end
+
+                    function checkbox(control)
+                        check_lab_control("checkbox", control)
+                        return add_lab_control("checkbox", control, control.default == true)
+                    end
+
+                    function button(control)
+                        check_lab_control("button", control)
+                        add_lab_control("button", control, nil)
+                    end
+
+                    -- goal() adds to the Goals window, check() is called every step until it returns true
+                    function goal(goal)
+                        if type(goal) ~= "table" or type(goal.description) ~= "string" then
+                            error("goal() needs a table with a description", 2)
+                        end
+                        if type(goal.check) ~= "function" then
+                            error("goal() needs a check function", 2)
+                        end
+                        goals[#goals+1] = goal
+                    end
+
+                    -- drawings only last until the next update(), so they're made every frame
+                    local function add_drawing(kind, drawing)
+                        if type(drawing) ~= "table" then
+                            error("draw_" .. kind .. "() needs a table", 3)
+                        end
+                        drawing.kind = kind
+                        drawings[#drawings+1] = drawing
+                    end
+
+                    function draw_line(line)
+                        add_drawing("line", line)
+                    end
+
+                    function draw_arrow(arrow)
+                        add_drawing("arrow", arrow)
+                    end
+
+                    function draw_circle(circle)
+                        add_drawing("circle", circle)
+                    end
+
+                    function draw_polygon(polygon)
+                        add_drawing("polygon", polygon)
+                    end
+
+                    function draw_text(text)
+                        add_drawing("text", text)
+                    end
+
+                    function set_limits(limits)
+                        error("set_limits() only works while the scene file is loading, "
+                            .. "the limits can also be changed in the Settings menu", 2)
+                    end
+
+                    function spawn(shape)
+                        error("spawn() only works while the scene is running, e.g. in update()", 2)
+                    end
+
+                    function destroy(obj)
+                        error("destroy() only works while the scene is running, e.g. in update()", 2)
+                    end
+
+                    function raycast(x1, y1, x2, y2, ignore)
+                        error("raycast() only works while the scene is running, e.g. in update()", 2)
+                    end
+
+                    function query_point(x, y)
+                        error("query_point() only works while the scene is running, e.g. in update()", 2)
+                    end
+
+                    function query_rect(x1, y1, x2, y2)
+                        error("query_rect() only works while the scene is running, e.g. in update()", 2)
+                    end
+
+                    function nearest(x, y, ignore)
+                        error("nearest() only works while the scene is running, e.g. in update()", 2)
+                    end
+
+                    -- print() goes to the Lua console along with where it was called from
+                    function print(...)
+                        local parts = {}
+                        for i = 1, select('#', ...) do
+                            parts[i] = tostring((select(i, ...)))
+                        end
+                        local _, location = pcall(error, "", 3)
+                        console_output[#console_output+1] = {
+                            message = table.concat(parts, "\t"),
+                            location = location,
+                        }
+                    end
+
+                    function is_key_down(key)
+                        return KEYS_DOWN[string.lower(key)] == true
+                    end
+
+                    -- timers go off after an amount of simulated time, so they wait while paused
+                    local next_timer_id = 1
+
+                    local function add_timer(seconds, fn, interval)
+                        if type(seconds) ~= "number" or type(fn) ~= "function" then
+                            error("expected a number of seconds and a function", 3)
+                        end
+                        local id = next_timer_id
+                        next_timer_id = next_timer_id + 1
+                        timers[id] = {time = TIME + seconds, fn = fn, interval = interval}
+                        return id
+                    end
+
+                    function after(seconds, fn)
+                        return add_timer(seconds, fn, nil)
+                    end
+
+                    function every(seconds, fn)
+                        if type(seconds) == "number" and seconds <= 0 then
+                            error("every() needs a positive number of seconds", 2)
+                        end
+                        return add_timer(seconds, fn, seconds)
+                    end
+
+                    function cancel(id)
+                        timers[id] = nil
+                    end
+
+                    -- Returns a function that gives the id and function of the earliest timer
+                    -- that's due until there aren't any left. Timers added while they're
+                    -- being run wait until the next frame.
+                    function due_timers()
+                        local last_id = next_timer_id - 1
+                        return function()
+                            local next_id, next_timer
+                            for id, timer in pairs(timers) do
+                                if id <= last_id and timer.time <= TIME and (next_timer == nil
+                                    or timer.time < next_timer.time
+                                    or (timer.time == next_timer.time and id < next_id)) then
+                                    next_id, next_timer = id, timer
+                                end
+                            end
+                            if next_timer == nil then
+                                return nil
+                            end
+
+                            if next_timer.interval then
+                                next_timer.time = next_timer.time + next_timer.interval
+                            else
+                                timers[next_id] = nil
+                            end
+                            return next_id, next_timer.fn
                         end
                     end
 
@@ -84,124 +485,1043 @@ pub fn new_lua_res() -> LuaRes {
             )
             .exec()
             .unwrap();
+
+        // dofile() and loadfile() could read any file, require() only reads from lua/
+        globals.set("dofile", rlua::Value::Nil).unwrap();
+        globals.set("loadfile", rlua::Value::Nil).unwrap();
+        reset_required_modules(lua_ctx);
+        lua_ctx
+            .set_named_registry_value(lua_entity::OBJECT_DATA, lua_ctx.create_table().unwrap())
+            .unwrap();
+        globals
+            .set("require", lua_ctx.create_function(require).unwrap())
+            .unwrap();
     });
 
-    std::sync::Arc::new(std::sync::Mutex::new(lua))
+    lua
 }
 
-impl LuaResExt for LuaRes {
-    fn run_lua_code(&mut self, code: String) {
-        self.lock().unwrap().context(|lua_ctx| {
-            lua_ctx.load(&code).exec().unwrap();
-        });
+/// modules that come with the program instead of being in lua/
+const BUILTIN_MODULES: [(&str, &str); 1] = [("physics", include_str!("lua/physics.lua"))];
+
+/// Like Lua's require() but modules only come from BUILTIN_MODULES and the lua/ directory,
+/// e.g. require("shapes.car") runs lua/shapes/car.lua once and returns what it returned
+fn require<'lua>(lua_ctx: rlua::Context<'lua>, name: String) -> rlua::Result<rlua::Value<'lua>> {
+    let loaded: rlua::Table = lua_ctx.named_registry_value("loaded_modules")?;
+    match loaded.get::<_, rlua::Value>(name.as_str())? {
+        rlua::Value::Nil => {}
+        rlua::Value::Boolean(false) => {
+            return Err(lua_error(format!(
+                "module \"{}\" requires itself, maybe through another module",
+                name
+            )))
+        }
+        module => return Ok(module),
     }
 
-    fn run_lua_file(&self, filename: impl AsRef<std::path::Path> + std::clone::Clone) {
-        self.lock().unwrap().context(|lua_ctx| {
-            let lua_code = std::fs::read_to_string(filename.clone()).unwrap();
-            if let Err(e) = lua_ctx
-                .load(&lua_code)
-                .set_name(&filename.as_ref().file_name().unwrap().to_str().unwrap())
-                .unwrap()
-                .exec()
-            {
-                println!("Lua {}", e.to_string());
-            };
-        });
+    let (code, chunk_name) = match BUILTIN_MODULES.iter().find(|(builtin, _)| *builtin == name) {
+        Some((_, code)) => (code.to_string(), format!("={}", name)),
+        None => {
+            let path = module_path(&name)?;
+            let code = std::fs::read_to_string(&path).map_err(|e| {
+                lua_error(format!("couldn't load module {}: {}", path.display(), e))
+            })?;
+            // so that the scene reloads when the module changes
+            let required_files: rlua::Table = lua_ctx.named_registry_value("required_files")?;
+            required_files.set(
+                required_files.len()? + 1,
+                path.to_string_lossy().into_owned(),
+            )?;
+            (code, format!("@{}", path.display()))
+        }
+    };
+
+    // false marks it as loading to catch modules that require each other
+    loaded.set(name.as_str(), false)?;
+    let module = lua_ctx
+        .load(&code)
+        .set_name(&chunk_name)?
+        .call::<_, rlua::Value>(name.as_str());
+    let module = match module {
+        Ok(rlua::Value::Nil) => rlua::Value::Boolean(true),
+        Ok(module) => module,
+        Err(e) => {
+            loaded.set(name.as_str(), rlua::Value::Nil)?;
+            return Err(e);
+        }
+    };
+    loaded.set(name.as_str(), module.clone())?;
+    Ok(module)
+}
+
+/// "shapes.car" is lua/shapes/car.lua, names that could point outside of lua/ are errors
+fn module_path(name: &str) -> rlua::Result<std::path::PathBuf> {
+    let valid = name.split('.').all(|part| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    });
+    if !valid {
+        return Err(lua_error(format!(
+            "invalid module name \"{}\", modules are named like \"folder.file\" for lua/folder/file.lua",
+            name
+        )));
+    }
+
+    let mut path = std::path::PathBuf::from("lua");
+    name.split('.').for_each(|part| path.push(part));
+    path.set_extension("lua");
+    Ok(path)
+}
+
+/// Modules are run again for each scene so that changes to them show up
+fn reset_required_modules(lua_ctx: rlua::Context) {
+    lua_ctx
+        .set_named_registry_value("loaded_modules", lua_ctx.create_table().unwrap())
+        .unwrap();
+    lua_ctx
+        .set_named_registry_value("required_files", lua_ctx.create_table().unwrap())
+        .unwrap();
+}
+
+impl LuaResExt for LuaRes {
+    fn run_lua_file(
+        &self,
+        filename: impl AsRef<std::path::Path> + std::clone::Clone,
+        limits: &LuaLimits,
+        instructions_left: &LuaInstructionsLeft,
+    ) -> rlua::Result<()> {
+        let path = filename.as_ref();
+        let lua_code = std::fs::read_to_string(path).map_err(|e| {
+            rlua::Error::RuntimeError(format!("couldn't read {}: {}", path.display(), e))
+        })?;
+
+        let lua = self.lock().unwrap();
+        with_memory_limit(&lua, limits, || {
+            let current_limits = std::cell::Cell::new(*limits);
+            lua.context(|lua_ctx| {
+                lua_ctx.scope(|scope| {
+                    let globals = lua_ctx.globals();
+
+                    // set_limits{} applies right away so that the rest of the file can use
+                    // them, and sets the globals that are read once the file is done
+                    let set_limits =
+                        scope.create_function(|lua_ctx, new_limits: rlua::Table| {
+                            schema::check_fields(&new_limits, schema::LIMITS_FIELDS)?;
+                            let globals = lua_ctx.globals();
+                            let mut limits = current_limits.get();
+                            if let Some(instructions) = new_limits.get("instructions")? {
+                                instructions_left.fetch_add(
+                                    instructions - limits.instructions_per_frame,
+                                    Ordering::Relaxed,
+                                );
+                                limits.instructions_per_frame = instructions;
+                                globals.set("INSTRUCTION_LIMIT", instructions)?;
+                            }
+                            if let Some(memory_kb) = new_limits.get("memory_kb")? {
+                                limits.memory_kb = memory_kb;
+                                lua.set_memory_limit(Some(memory_kb * 1024));
+                                globals.set("MEMORY_LIMIT_KB", memory_kb)?;
+                            }
+                            current_limits.set(limits);
+                            Ok(())
+                        })?;
+                    let prelude_set_limits: rlua::Value = globals.get("set_limits")?;
+                    globals.set("set_limits", set_limits)?;
+
+                    // the @ makes Lua put the path in error messages, e.g. "lua/init.lua:12: ..."
+                    let result = lua_ctx
+                        .load(&lua_code)
+                        .set_name(&format!("@{}", path.display()))
+                        .and_then(|chunk| chunk.exec());
+                    globals.set("set_limits", prelude_set_limits)?;
+                    result
+                })
+            })
+        })
     }
 }
 
+/// Scripts are only held to the memory limit while they run so that the simulation
+/// can still make the tables it passes to Lua after a script has used up its memory.
+pub fn with_memory_limit<R>(lua: &Lua, limits: &LuaLimits, f: impl FnOnce() -> R) -> R {
+    lua.set_memory_limit(Some(limits.memory_kb * 1024));
+    let result = f();
+    lua.set_memory_limit(None);
+    result
+}
+
 impl<'a, 'b> MainState<'a, 'b> {
+    /// Lazily creates the body for add_shape() and spawn(), world.maintain() has to be
+    /// called before its components show up
     #[allow(clippy::many_single_char_names)]
-    /// must call world.maintain() after calling this for shape to actually get added
-    /// in practice is only used in process_lua_shapes() so it should be fine
-    pub fn process_lua_shape(&mut self, shape: &rlua::Table) {
-        let ty: String = shape.get("shape").expect("invalid shape");
-        let mass = shape.get("mass").unwrap_or(1.0);
-        let x = shape.get("x").unwrap();
-        let y = shape.get("y").unwrap();
-        let x_vel = shape.get("x_vel").unwrap_or(0.0);
-        let y_vel = shape.get("y_vel").unwrap_or(0.0);
-        let rotvel = shape.get("rotvel").unwrap_or(0.0);
-        let rotation = shape.get("rotation").unwrap_or(0.0);
-        let elasticity = shape.get("elasticity").unwrap_or(0.2);
-        let friction = shape.get("friction").unwrap_or(0.5);
-        let name = shape.get("name").ok();
+    pub fn process_lua_shape(
+        &self,
+        shape: &rlua::Table,
+    ) -> rlua::Result<(Entity, DefaultBodyHandle)> {
+        schema::check_fields(shape, schema::SHAPE_FIELDS)?;
+
+        let ty: String = get_required(shape, "shape")?;
+        let mass = shape.get::<_, Option<f32>>("mass")?.unwrap_or(1.0);
+        let x = get_required(shape, "x")?;
+        let y = get_required(shape, "y")?;
+        let x_vel = shape.get::<_, Option<f32>>("x_vel")?.unwrap_or(0.0);
+        let y_vel = shape.get::<_, Option<f32>>("y_vel")?.unwrap_or(0.0);
+        let rotvel = shape.get::<_, Option<f32>>("rotvel")?.unwrap_or(0.0);
+        let rotation = match shape.get::<_, Option<f32>>("rot")? {
+            Some(rot) => rot,
+            None => shape.get::<_, Option<f32>>("rotation")?.unwrap_or(0.0),
+        };
+        let elasticity = shape.get::<_, Option<f32>>("elasticity")?.unwrap_or(0.2);
+        let friction = shape.get::<_, Option<f32>>("friction")?.unwrap_or(0.5);
+        let name: Option<String> = shape.get("name")?;
         let status = shape
-            .get("status")
-            .unwrap_or_else(|_| "dynamic".to_string());
-        let color = shape
-            .get("color")
-            .map_or(ggez::graphics::WHITE, |color: rlua::Table| {
-                let r = color.get("r").unwrap();
-                let g = color.get("g").unwrap();
-                let b = color.get("b").unwrap();
-                let a = color.get("a").unwrap_or(255);
-                ggez::graphics::Color::from_rgba(r, g, b, a)
-            });
-        let update_fn: Option<String> = shape.get("update_function").ok();
-        let collisions_enabled: bool = shape
-            .get("collision")
-            .map(|s: String| s.as_str() == "true")
-            .unwrap_or(true);
+            .get::<_, Option<String>>("status")?
+            .unwrap_or_else(|| "dynamic".to_string());
+        let color = color_from_lua(shape)?;
+        let update_fn: Option<String> = shape.get("update_function")?;
+        let batch_fn: Option<String> = shape.get("batch_function")?;
+        // older scenes use the strings "true" and "false"
+        let collisions_enabled = match shape.get::<_, rlua::Value>("collision")? {
+            rlua::Value::Boolean(enabled) => enabled,
+            rlua::Value::String(enabled) => enabled.to_str()? == "true",
+            _ => true,
+        };
+
+        let path = shape
+            .get::<_, Option<rlua::Table>>("path")?
+            .map(|path| process_lua_path(&path))
+            .transpose()?;
+
+        #[allow(clippy::wildcard_in_or_patterns)]
+        let status = match status.to_lowercase().as_str() {
+            // bodies following a path are always kinematic
+            _ if path.is_some() => np::object::BodyStatus::Kinematic,
+            "static" => np::object::BodyStatus::Static,
+            "kinematic" => np::object::BodyStatus::Kinematic,
+            "dynamic" | _ => np::object::BodyStatus::Dynamic,
+        };
+
+        let shape_info = match ty.to_lowercase().as_str() {
+            "rectangle" | "rect" => {
+                let w = get_required(shape, "w")?;
+                let h = get_required(shape, "h")?;
+                ShapeInfo::Rectangle(Some(Vector::new(w, h)))
+            }
+            "circle" => {
+                let rad = get_required(shape, "r")?;
+                ShapeInfo::Circle(Some(rad))
+            }
+            _ => return Err(lua_error(format!("invalid shape \"{}\"", ty))),
+        };
+
+        let (entity, body_handle) = BodyBuilder {
+            translation: Vector::new(x, y),
+            rotation,
+            velocity: Vector::new(x_vel, y_vel),
+            rotvel,
+            status,
+            restitution: elasticity,
+            friction,
+            color,
+            update_fn,
+            batch_fn,
+            name,
+            collisions_enabled,
+            ..BodyBuilder::from_world(&self.world, shape_info, mass)
+        }
+        .create_with_handle();
+
+        if let Some(path) = path {
+            self.world
+                .fetch::<LazyUpdate>()
+                .insert(entity, FollowPath { path, elapsed: 0.0 });
+        }
+
+        Ok((entity, body_handle))
+    }
+
+    /// shapes are processed after the scene file has run, so errors are given
+    /// the line that add_shape() was called from
+    pub fn process_lua_shapes<'lua>(
+        &mut self,
+        lua_ctx: rlua::Context<'lua>,
+        shapes: Vec<rlua::Table<'lua>>,
+    ) {
+        let locations = lua_ctx
+            .globals()
+            .get::<_, Option<rlua::Table>>("shape_locations")
+            .ok()
+            .flatten();
+        shapes.iter().for_each(|shape| {
+            let result = self
+                .process_lua_shape(shape)
+                .and_then(|(entity, _)| set_lua_data(lua_ctx, entity, shape.get("data")?));
+            if let Err(e) = result {
+                let location = locations
+                    .as_ref()
+                    .and_then(|locations| locations.get::<_, Option<String>>(shape.clone()).ok())
+                    .flatten();
+                let e = match (e, location) {
+                    (rlua::Error::RuntimeError(message), Some(location)) => {
+                        lua_error(format!("{}{}", location, message))
+                    }
+                    (e, _) => e,
+                };
+                self.log_lua_error(&e, shape.get("name").ok());
+            }
+        });
+        self.world.maintain();
+    }
+
+    /// Used by spawn(), the shape is snapshotted right away so that its handle
+    /// works before world.maintain() is called.
+    fn spawn_lua_shape<'lua>(
+        &self,
+        lua_ctx: rlua::Context<'lua>,
+        shape: &rlua::Table<'lua>,
+        bridge: &LuaBridgeRes,
+    ) -> rlua::Result<Entity> {
+        let (entity, body_handle) = self.process_lua_shape(shape)?;
+        set_lua_data(lua_ctx, entity, shape.get("data")?)?;
+        let body_set = self.world.fetch::<BodySet>();
+        bridge.lock().unwrap().snapshots.insert(
+            entity,
+            BodySnapshot::new(
+                body_set.rigid_body(body_handle).unwrap(),
+                color_from_lua(shape)?,
+            ),
+        );
+        Ok(entity)
+    }
+
+    /// objects can be passed to Lua functions as handles or by name
+    fn entity_from_lua(&self, target: rlua::Value) -> rlua::Result<Entity> {
+        match target {
+            rlua::Value::UserData(user_data) => Ok(user_data.borrow::<LuaEntity>()?.0),
+            rlua::Value::String(name) => {
+                let name = name.to_str()?;
+                self.find_body_by_name(name)
+                    .ok_or_else(|| rlua::Error::RuntimeError(format!("no object named {}", name)))
+            }
+            _ => Err(rlua::Error::RuntimeError(
+                "expected an object or its name".to_string(),
+            )),
+        }
+    }
+
+    /// Runs Lua code with the functions in WORLD_FNS available, like spawn() and raycast().
+    /// They need the world, so outside of this they're the versions from the prelude
+    /// which raise an error.
+    fn run_with_world_fns<'lua, R>(
+        &self,
+        lua_ctx: rlua::Context<'lua>,
+        f: impl FnOnce() -> R,
+    ) -> R {
+        let globals = lua_ctx.globals();
+        let prelude_fns: Vec<rlua::Value> = WORLD_FNS
+            .iter()
+            .map(|name| globals.get(*name).unwrap())
+            .collect();
+        let bridge = LuaBridgeRes::clone(&self.world.fetch::<LuaBridgeRes>());
+
+        let result = lua_ctx.scope(|scope| {
+            let spawn = scope
+                .create_function(|lua_ctx, shape: rlua::Table| {
+                    let entity = self.spawn_lua_shape(lua_ctx, &shape, &bridge)?;
+                    Ok(LuaEntity(entity, bridge.clone()))
+                })
+                .unwrap();
+
+            // objects are deleted once the Lua code is done running
+            let destroy = scope
+                .create_function(|_, target: rlua::Value| {
+                    let entity = self.entity_from_lua(target)?;
+                    bridge.lock().unwrap().destroy(entity);
+                    Ok(())
+                })
+                .unwrap();
+
+            // raycast() returns a table with the object that was hit and where, or nil
+            let raycast = scope
+                .create_function(
+                    |lua_ctx, (x1, y1, x2, y2, ignore): (f32, f32, f32, f32, rlua::Value)| {
+                        let ignored = match ignore {
+                            rlua::Value::Nil => None,
+                            ignore => Some(self.entity_from_lua(ignore)?),
+                        };
+                        check_finite(&[x1, y1, x2, y2])?;
+                        let start = Vector::new(x1, y1);
+                        let end = Vector::new(x2, y2);
+                        match queries::raycast(&self.world, start, end, ignored) {
+                            Some(hit) => {
+                                let hit_table = lua_ctx.create_table()?;
+                                hit_table.set("object", LuaEntity(hit.entity, bridge.clone()))?;
+                                hit_table.set("x", hit.point.x)?;
+                                hit_table.set("y", hit.point.y)?;
+                                hit_table.set("normal_x", hit.normal.x)?;
+                                hit_table.set("normal_y", hit.normal.y)?;
+                                hit_table.set("distance", hit.distance)?;
+                                Ok(rlua::Value::Table(hit_table))
+                            }
+                            None => Ok(rlua::Value::Nil),
+                        }
+                    },
+                )
+                .unwrap();
+
+            let query_point = scope
+                .create_function(|_, (x, y): (f32, f32)| {
+                    check_finite(&[x, y])?;
+                    Ok(queries::query_point(&self.world, Vector::new(x, y))
+                        .into_iter()
+                        .map(|entity| LuaEntity(entity, bridge.clone()))
+                        .collect::<Vec<_>>())
+                })
+                .unwrap();
+
+            let query_rect = scope
+                .create_function(|_, (x1, y1, x2, y2): (f32, f32, f32, f32)| {
+                    check_finite(&[x1, y1, x2, y2])?;
+                    let corner1 = Vector::new(x1, y1);
+                    let corner2 = Vector::new(x2, y2);
+                    Ok(queries::query_rect(&self.world, corner1, corner2)
+                        .into_iter()
+                        .map(|entity| LuaEntity(entity, bridge.clone()))
+                        .collect::<Vec<_>>())
+                })
+                .unwrap();
+
+            // nearest() returns the object and how far away it is
+            let nearest = scope
+                .create_function(|_, (x, y, ignore): (f32, f32, rlua::Value)| {
+                    let ignored = match ignore {
+                        rlua::Value::Nil => None,
+                        ignore => Some(self.entity_from_lua(ignore)?),
+                    };
+                    check_finite(&[x, y])?;
+                    Ok(
+                        match queries::nearest(&self.world, Vector::new(x, y), ignored) {
+                            Some((entity, distance)) => {
+                                (Some(LuaEntity(entity, bridge.clone())), Some(distance))
+                            }
+                            None => (None, None),
+                        },
+                    )
+                })
+                .unwrap();
+
+            globals.set("spawn", spawn).unwrap();
+            globals.set("destroy", destroy).unwrap();
+            globals.set("raycast", raycast).unwrap();
+            globals.set("query_point", query_point).unwrap();
+            globals.set("query_rect", query_rect).unwrap();
+            globals.set("nearest", nearest).unwrap();
+            f()
+        });
+
+        WORLD_FNS
+            .iter()
+            .zip(prelude_fns)
+            .for_each(|(name, prelude_fn)| globals.set(*name, prelude_fn).unwrap());
+        result
+    }
+
+    /// like process_lua_shape(), world.maintain() has to be called afterwards
+    pub fn process_lua_force_field(&mut self, field: &rlua::Table) -> rlua::Result<()> {
+        schema::check_fields(field, schema::FORCE_FIELD_FIELDS)?;
+
+        let kind = field
+            .get::<_, Option<String>>("kind")?
+            .unwrap_or_else(|| "wind".to_string());
+        let kind = ForceFieldKind::from_name(&kind)
+            .ok_or_else(|| lua_error(format!("invalid force field kind \"{}\"", kind)))?;
+        let x = get_required(field, "x")?;
+        let y = get_required(field, "y")?;
+
+        // fields with a radius are circles, otherwise they're rectangles
+        // with the same half extents as rectangle shapes
+        let shape = match field.get::<_, Option<f32>>("r")? {
+            Some(r) => FieldShape::Circle(r),
+            None => {
+                let w = get_required(field, "w")?;
+                let h = get_required(field, "h")?;
+                FieldShape::Rectangle(Vector::new(w, h))
+            }
+        };
+
+        let entity = ForceField {
+            strength: field.get::<_, Option<f32>>("strength")?.unwrap_or(5.0),
+            angle: field.get::<_, Option<f32>>("angle")?.unwrap_or(0.0),
+            ..ForceField::new(kind, shape, Vector::new(x, y))
+        }
+        .create(&self.world);
+        if let Some(name) = field.get::<_, Option<String>>("name")? {
+            self.world.fetch::<LazyUpdate>().insert(entity, Name(name));
+        }
+        Ok(())
+    }
+
+    pub fn process_lua_force_fields(&mut self, fields: Vec<rlua::Table>) {
+        fields.iter().for_each(|field| {
+            if let Err(e) = self.process_lua_force_field(field) {
+                self.log_lua_error(&e, field.get("name").ok());
+            }
+        });
+        self.world.maintain();
+    }
+
+    /// like process_lua_shape(), world.maintain() has to be called afterwards
+    pub fn process_lua_soft_body(&mut self, soft_body: &rlua::Table) -> rlua::Result<()> {
+        schema::check_fields(soft_body, schema::SOFT_BODY_FIELDS)?;
+
+        let ty: String = get_required(soft_body, "shape")?;
+        let x = get_required(soft_body, "x")?;
+        let y = get_required(soft_body, "y")?;
+        let color = color_from_lua(soft_body)?;
+
+        let shape_info = match ty.to_lowercase().as_str() {
+            "rectangle" | "rect" => {
+                let w = get_required(soft_body, "w")?;
+                let h = get_required(soft_body, "h")?;
+                ShapeInfo::Rectangle(Some(Vector::new(w, h)))
+            }
+            "circle" => {
+                let rad = get_required(soft_body, "r")?;
+                ShapeInfo::Circle(Some(rad))
+            }
+            _ => return Err(lua_error(format!("invalid soft body shape \"{}\"", ty))),
+        };
+
+        SoftBodyBuilder {
+            resolution: soft_body
+                .get::<_, Option<f32>>("resolution")?
+                .map_or(6, |resolution| resolution.round() as usize),
+            stiffness: soft_body
+                .get::<_, Option<f32>>("stiffness")?
+                .unwrap_or(100.0),
+            damping: soft_body.get::<_, Option<f32>>("damping")?.unwrap_or(0.5),
+            mass: soft_body.get::<_, Option<f32>>("mass")?.unwrap_or(5.0),
+            restitution: soft_body
+                .get::<_, Option<f32>>("elasticity")?
+                .unwrap_or(0.2),
+            friction: soft_body.get::<_, Option<f32>>("friction")?.unwrap_or(0.5),
+            color,
+            name: soft_body.get("name")?,
+            ..SoftBodyBuilder::new(shape_info, Vector::new(x, y))
+        }
+        .create(&self.world);
+        Ok(())
+    }
+
+    pub fn process_lua_soft_bodies(&mut self, soft_bodies: Vec<rlua::Table>) {
+        soft_bodies.iter().for_each(|soft_body| {
+            if let Err(e) = self.process_lua_soft_body(soft_body) {
+                self.log_lua_error(&e, soft_body.get("name").ok());
+            }
+        });
+        self.world.maintain();
+    }
+
+    /// like process_lua_shape(), world.maintain() has to be called afterwards.
+    /// The bodies are found by name so they have to already be in the world.
+    pub fn process_lua_pulley(&mut self, pulley: &rlua::Table) -> rlua::Result<()> {
+        schema::check_fields(pulley, schema::PULLEY_FIELDS)?;
+
+        let find_body = |key: &str| {
+            let body_name: String = get_required(pulley, key)?;
+            self.find_body_by_name(&body_name)
+                .ok_or_else(|| lua_error(format!("no body named {} for pulley", body_name)))
+        };
+        let body1 = find_body("body1")?;
+        let body2 = find_body("body2")?;
+        if body1 == body2 {
+            return Err(lua_error("a pulley can't connect a body to itself"));
+        }
+
+        let wheels = get_required::<Vec<rlua::Table>>(pulley, "wheels")?
+            .iter()
+            .map(|wheel| {
+                Ok(Vector::new(
+                    get_required(wheel, "x")?,
+                    get_required(wheel, "y")?,
+                ))
+            })
+            .collect::<rlua::Result<Vec<Vector>>>()?;
+        if wheels.is_empty() {
+            return Err(lua_error("pulleys need at least one wheel"));
+        }
+
+        PulleyBuilder {
+            ratio: pulley.get::<_, Option<f32>>("ratio")?.unwrap_or(1.0),
+            length: pulley.get("length")?,
+            name: pulley.get("name")?,
+            ..PulleyBuilder::new(body1, body2, wheels)
+        }
+        .create(&self.world);
+        Ok(())
+    }
+
+    pub fn process_lua_pulleys(&mut self, pulleys: Vec<rlua::Table>) {
+        pulleys.iter().for_each(|pulley| {
+            if let Err(e) = self.process_lua_pulley(pulley) {
+                self.log_lua_error(&e, pulley.get("name").ok());
+            }
+        });
+        self.world.maintain();
+    }
+
+    /// like process_lua_pulley(), the bodies have to already be in the world.
+    /// The anchor defaults to halfway between the two bodies.
+    pub fn process_lua_joint(&mut self, joint: &rlua::Table) -> rlua::Result<()> {
+        schema::check_fields(joint, schema::JOINT_FIELDS)?;
+
+        let kind_name = joint
+            .get::<_, Option<String>>("kind")?
+            .unwrap_or_else(|| "pin".to_string());
+        let kind = JointKind::from_name(&kind_name)
+            .ok_or_else(|| lua_error(format!("invalid joint kind \"{}\"", kind_name)))?;
+
+        let find_body = |key: &str| {
+            let body_name: String = get_required(joint, key)?;
+            self.find_body_by_name(&body_name)
+                .ok_or_else(|| lua_error(format!("no body named {} for joint", body_name)))
+        };
+        let body1 = find_body("body1")?;
+        let body2 = find_body("body2")?;
+        if body1 == body2 {
+            return Err(lua_error("a joint can't join a body to itself"));
+        }
+
+        let anchor = match (joint.get("x")?, joint.get("y")?) {
+            (Some(x), Some(y)) => Vector::new(x, y),
+            (Some(_), None) | (None, Some(_)) => {
+                return Err(lua_error(
+                    "joints need both x and y for the anchor, or neither for the midpoint",
+                ))
+            }
+            (None, None) => {
+                let physics_bodies = self.world.read_storage::<PhysicsBody>();
+                let body_set = self.world.fetch::<BodySet>();
+                let pos = |entity| {
+                    body_set
+                        .rigid_body(physics_bodies.get(entity).unwrap().body_handle)
+                        .unwrap()
+                        .position()
+                        .translation
+                        .vector
+                };
+                (pos(body1) + pos(body2)) / 2.0
+            }
+        };
+
+        JointBuilder {
+            break_force: joint
+                .get::<_, Option<f32>>("break_force")?
+                .and_then(threshold),
+            break_torque: joint
+                .get::<_, Option<f32>>("break_torque")?
+                .and_then(threshold),
+            name: joint.get("name")?,
+            ..JointBuilder::new(kind, body1, body2, anchor)
+        }
+        .create(&self.world);
+        Ok(())
+    }
+
+    pub fn process_lua_joints(&mut self, joints: Vec<rlua::Table>) {
+        joints.iter().for_each(|joint| {
+            if let Err(e) = self.process_lua_joint(joint) {
+                self.log_lua_error(&e, joint.get("name").ok());
+            }
+        });
+        self.world.maintain();
+    }
+
+    /// graphs are tables like {object = "ball", label = "Energy", fn = function(obj) ... end},
+    /// where the object can also be a handle like OBJECTS.ball
+    pub fn process_lua_graph<'lua>(
+        &mut self,
+        lua_ctx: rlua::Context<'lua>,
+        graph: &rlua::Table<'lua>,
+    ) -> rlua::Result<()> {
+        let entity = match graph.get::<_, rlua::Value>("object")? {
+            rlua::Value::UserData(handle) => handle.borrow::<LuaEntity>()?.0,
+            _ => {
+                let body_name: String = get_required(graph, "object")?;
+                self.find_body_by_name(&body_name)
+                    .ok_or_else(|| lua_error(format!("no body named {} for graph", body_name)))?
+            }
+        };
+        let label: String = get_required(graph, "label")?;
+        let function: rlua::Function = get_required(graph, "fn")?;
+
+        let graph = LuaGraph::new(label, lua_ctx.create_registry_value(function)?, None);
+        self.add_lua_graph(entity, graph);
+        Ok(())
+    }
+
+    pub fn process_lua_graphs<'lua>(
+        &mut self,
+        lua_ctx: rlua::Context<'lua>,
+        graphs: Vec<rlua::Table<'lua>>,
+    ) {
+        graphs.iter().for_each(|graph| {
+            if let Err(e) = self.process_lua_graph(lua_ctx, graph) {
+                self.log_lua_error(&e, graph.get("object").ok());
+            }
+        });
+    }
+
+    /// Graphs a Lua expression typed into the side menu, obj is the object's handle
+    pub fn add_expression_graph(&mut self, entity: Entity, expression: String) {
+        let lua = LuaRes::clone(&self.world.fetch::<LuaRes>());
+        let function = lua.lock().unwrap().context(|lua_ctx| {
+            lua_ctx
+                .load(&format!("return function(obj) return {} end", expression))
+                .set_name(&format!("=graph {}", expression))?
+                .eval::<rlua::Function>()
+                .and_then(|function| lua_ctx.create_registry_value(function))
+        });
+
+        match function {
+            Ok(function) => {
+                let graph = LuaGraph::new(expression.clone(), function, Some(expression));
+                self.add_lua_graph(entity, graph);
+            }
+            Err(e) => {
+                let name = self
+                    .world
+                    .read_storage::<Name>()
+                    .get(entity)
+                    .map(|name| name.0.clone());
+                self.log_lua_error(&e, name);
+            }
+        }
+    }
+
+    fn add_lua_graph(&mut self, entity: Entity, graph: LuaGraph) {
+        let mut lua_graphs = self.world.write_storage::<LuaGraphs>();
+        match lua_graphs.get_mut(entity) {
+            Some(LuaGraphs(graphs)) => graphs.push(graph),
+            None => {
+                lua_graphs.insert(entity, LuaGraphs(vec![graph])).unwrap();
+            }
+        }
+    }
+
+    /// Lab controls are tables like {name = "Launch angle", min = 0, max = 90, default = 45,
+    /// on_change = function(value) ... end}, made by param(), checkbox(), and button()
+    pub fn process_lua_lab_control<'lua>(
+        &mut self,
+        lua_ctx: rlua::Context<'lua>,
+        control: &rlua::Table<'lua>,
+    ) -> rlua::Result<()> {
+        let name: String = get_required(control, "name")?;
+        let kind: String = get_required(control, "kind")?;
+        let params: rlua::Table = lua_ctx.globals().get("PARAMS")?;
+
+        let (kind, callback_name) = match kind.as_str() {
+            "slider" => {
+                let min: f32 = get_required(control, "min")?;
+                let max: f32 = get_required(control, "max")?;
+                if min >= max {
+                    return Err(lua_error(format!(
+                        "min has to be less than max for {}",
+                        name
+                    )));
+                }
+                let step: Option<f32> = control.get("step")?;
+                if matches!(step, Some(step) if step <= 0.0) {
+                    return Err(lua_error(format!("step has to be positive for {}", name)));
+                }
+                let value: f32 = params.get(name.as_str())?;
+                let kind = LabControlKind::Slider {
+                    min,
+                    max,
+                    step,
+                    value,
+                };
+                (kind, "on_change")
+            }
+            "checkbox" => {
+                let value: bool = params.get(name.as_str())?;
+                (LabControlKind::Checkbox { value }, "on_change")
+            }
+            _ => (LabControlKind::Button, "on_click"),
+        };
+        let callback = match control.get::<_, Option<rlua::Function>>(callback_name)? {
+            Some(callback) => Some(lua_ctx.create_registry_value(callback)?),
+            None => None,
+        };
+        let rerun = control.get::<_, Option<bool>>("rerun")?.unwrap_or(false);
+
+        self.world.fetch_mut::<LabControls>().add(LabControl {
+            name,
+            kind,
+            callback,
+            rerun,
+        });
+        Ok(())
+    }
+
+    pub fn process_lua_lab_controls<'lua>(
+        &mut self,
+        lua_ctx: rlua::Context<'lua>,
+        controls: Vec<rlua::Table<'lua>>,
+    ) {
+        controls.iter().for_each(|control| {
+            if let Err(e) = self.process_lua_lab_control(lua_ctx, control) {
+                self.log_lua_error(&e, control.get("name").ok());
+            }
+        });
+    }
+
+    /// Goals are tables like {description = "Land on the platform", check = function() ... end},
+    /// with an optional fail function and a deadline in seconds
+    pub fn process_lua_goal<'lua>(
+        &mut self,
+        lua_ctx: rlua::Context<'lua>,
+        goal: &rlua::Table<'lua>,
+    ) -> rlua::Result<()> {
+        let description: String = get_required(goal, "description")?;
+        let check: rlua::Function = get_required(goal, "check")?;
+        let fail = match goal.get::<_, Option<rlua::Function>>("fail")? {
+            Some(fail) => Some(lua_ctx.create_registry_value(fail)?),
+            None => None,
+        };
+        let deadline: Option<f32> = goal.get("deadline")?;
+        if matches!(deadline, Some(deadline) if deadline <= 0.0) {
+            return Err(lua_error(format!(
+                "deadline has to be positive for {}",
+                description
+            )));
+        }
+
+        self.world.fetch_mut::<Goals>().0.push(Goal {
+            description,
+            check: lua_ctx.create_registry_value(check)?,
+            fail,
+            deadline,
+            status: GoalStatus::Pending,
+        });
+        Ok(())
+    }
+
+    pub fn process_lua_goals<'lua>(
+        &mut self,
+        lua_ctx: rlua::Context<'lua>,
+        goals: Vec<rlua::Table<'lua>>,
+    ) {
+        goals.iter().for_each(|goal| {
+            if let Err(e) = self.process_lua_goal(lua_ctx, goal) {
+                self.log_lua_error(&e, goal.get("description").ok());
+            }
+        });
+    }
+
+    /// replaces the last frame's drawings with the ones made since then
+    pub fn process_lua_drawings(&mut self, drawings: Vec<rlua::Table>) {
+        let drawings = drawings
+            .iter()
+            .filter_map(|drawing| match Drawing::from_lua(drawing) {
+                Ok(drawing) => Some(drawing),
+                Err(e) => {
+                    self.log_lua_error(&e, None);
+                    None
+                }
+            })
+            .collect();
+        self.world.insert(LuaDrawings(drawings));
+    }
+
+    /// Updates PARAMS and calls on_change or on_click after a control
+    /// in the Lab Controls window is used
+    pub fn lab_control_changed(&mut self, index: usize) {
+        self.snapshot_lua_bodies();
 
-        #[allow(clippy::wildcard_in_or_patterns)]
-        let status = match status.to_lowercase().as_str() {
-            "static" => np::object::BodyStatus::Static,
-            "kinematic" => np::object::BodyStatus::Kinematic,
-            "dynamic" | _ => np::object::BodyStatus::Dynamic,
-        };
+        let lua = LuaRes::clone(&self.world.fetch::<LuaRes>());
+        let lua = lua.lock().unwrap();
+        let limits = *self.world.fetch::<LuaLimits>();
+        lua.context(|lua_ctx| {
+            let (name, value, is_button, callback) = {
+                let lab_controls = self.world.fetch::<LabControls>();
+                let control = match lab_controls.controls.get(index) {
+                    Some(control) => control,
+                    None => return,
+                };
+                let callback = control
+                    .callback
+                    .as_ref()
+                    .map(|key| lua_ctx.registry_value::<rlua::Function>(key));
+                let is_button = control.kind == LabControlKind::Button;
+                (
+                    control.name.clone(),
+                    control.kind.lua_value(),
+                    is_button,
+                    callback,
+                )
+            };
 
-        let shape_info = match ty.to_lowercase().as_str() {
-            "rectangle" | "rect" => {
-                let w = shape.get("w").unwrap();
-                let h = shape.get("h").unwrap();
-                ShapeInfo::Rectangle(Some(Vector::new(w, h)))
+            if !is_button {
+                let result = lua_ctx
+                    .globals()
+                    .get::<_, rlua::Table>("PARAMS")
+                    .and_then(|params| params.set(name.as_str(), value.clone()));
+                if let Err(e) = result {
+                    self.log_lua_error(&e, None);
+                }
             }
-            "circle" => {
-                let rad = shape.get("r").unwrap();
-                ShapeInfo::Circle(Some(rad))
+
+            let result = match callback {
+                Some(Ok(callback)) => with_memory_limit(&lua, &limits, || {
+                    self.run_with_world_fns(lua_ctx, || callback.call::<_, ()>(value))
+                }),
+                Some(Err(e)) => Err(e),
+                None => Ok(()),
+            };
+            if let Err(e) = result {
+                self.log_lua_error(&e, None);
             }
-            _ => panic!("invalid shape"),
-        };
+        });
+        std::mem::drop(lua);
 
-        BodyBuilder {
-            translation: Vector::new(x, y),
-            rotation,
-            velocity: Vector::new(x_vel, y_vel),
-            rotvel,
-            status,
-            restitution: elasticity,
-            friction,
-            color,
-            update_fn,
-            name,
-            collisions_enabled,
-            ..BodyBuilder::from_world(&self.world, shape_info, mass)
-        }
-        .create();
+        self.apply_lua_writes();
     }
 
-    pub fn process_lua_shapes(&mut self, shapes: Vec<rlua::Table>) {
-        shapes
-            .iter()
-            .for_each(|shape| self.process_lua_shape(shape));
-        self.world.maintain();
+    pub fn find_body_by_name(&self, body_name: &str) -> Option<Entity> {
+        let names = self.world.read_storage::<Name>();
+        let physics_bodies = self.world.read_storage::<PhysicsBody>();
+        let entities = self.world.entities();
+        (&names, &physics_bodies, &entities)
+            .join()
+            .find(|(name, _, _)| name.0 == body_name)
+            .map(|(_, _, entity)| entity)
     }
 
     pub fn add_shapes_from_lua_file(
         &mut self,
         filename: impl AsRef<std::path::Path> + std::clone::Clone,
     ) {
+        self.world.fetch_mut::<HotReload>().watch_scene(&filename);
+
         let lua = self.world.fetch_mut::<LuaRes>().clone();
-        lua.run_lua_file(filename);
+
+        // the limits are per scene, so the last scene's can't be left over
+        lua.lock().unwrap().context(|lua_ctx| {
+            let globals = lua_ctx.globals();
+            globals.set("INSTRUCTION_LIMIT", rlua::Value::Nil).unwrap();
+            globals.set("MEMORY_LIMIT_KB", rlua::Value::Nil).unwrap();
+            globals.set("SCENE", rlua::Value::Nil).unwrap();
+            reset_required_modules(lua_ctx);
+
+            // the controls' values are kept if the same scene is being run again
+            let kept_values = self
+                .world
+                .fetch_mut::<LabControls>()
+                .start_scene(filename.as_ref());
+            let params = lua_ctx.create_table().unwrap();
+            kept_values.iter().for_each(|(name, kind)| {
+                params.set(name.as_str(), kind.lua_value()).unwrap();
+            });
+            globals.set("PARAMS", params).unwrap();
+            let lab_controls: Vec<rlua::Table> = Vec::new();
+            globals.set("lab_controls", lab_controls).unwrap();
+            let goals: Vec<rlua::Table> = Vec::new();
+            globals.set("goals", goals).unwrap();
+        });
+        self.world.insert(Goals::default());
+        // every run of a scene gets the same random numbers unless the seed is changed,
+        // and a seed set by the last scene doesn't carry over
+        self.world
+            .fetch::<LuaRngRes>()
+            .lock()
+            .unwrap()
+            .start_scene();
+        let default_limits = LuaLimits::default();
+        self.world
+            .fetch::<LuaInstructionsLeft>()
+            .store(default_limits.instructions_per_frame, Ordering::Relaxed);
+        let instructions_left =
+            LuaInstructionsLeft::clone(&self.world.fetch::<LuaInstructionsLeft>());
+        if let Err(e) = lua.run_lua_file(filename, &default_limits, &instructions_left) {
+            self.log_lua_error(&e, None);
+        }
+
         lua.lock().unwrap().context(|lua_ctx| {
             let globals = lua_ctx.globals();
+            let required_files: Vec<String> = lua_ctx
+                .named_registry_value::<_, rlua::Table>("required_files")
+                .and_then(|files| files.sequence_values().collect())
+                .unwrap_or_default();
+            let mut hot_reload = self.world.fetch_mut::<HotReload>();
+            required_files
+                .iter()
+                .for_each(|file| hot_reload.watch(file));
+            std::mem::drop(hot_reload);
+
+            let scene_info = match globals.get::<_, Option<rlua::Table>>("SCENE") {
+                Ok(Some(scene)) => scene_info_from_lua(&scene)
+                    .map_err(|e| self.log_lua_error(&e, None))
+                    .ok(),
+                Ok(None) => None,
+                Err(e) => {
+                    self.log_lua_error(&e, None);
+                    None
+                }
+            };
+            self.world.insert(CurrentSceneInfo(scene_info));
+
+            self.world.insert(LuaLimits {
+                instructions_per_frame: globals
+                    .get("INSTRUCTION_LIMIT")
+                    .unwrap_or(default_limits.instructions_per_frame),
+                memory_kb: globals
+                    .get("MEMORY_LIMIT_KB")
+                    .unwrap_or(default_limits.memory_kb),
+            });
             let shapes = globals.get::<_, Vec<rlua::Table>>("shapes").unwrap();
-            self.process_lua_shapes(shapes);
+            self.process_lua_shapes(lua_ctx, shapes);
+
+            let fields = globals.get::<_, Vec<rlua::Table>>("force_fields").unwrap();
+            self.process_lua_force_fields(fields);
+
+            let soft_bodies = globals.get::<_, Vec<rlua::Table>>("soft_bodies").unwrap();
+            self.process_lua_soft_bodies(soft_bodies);
+
+            // pulleys and joints go last since they need the bodies to exist
+            let pulleys = globals.get::<_, Vec<rlua::Table>>("pulleys").unwrap();
+            self.process_lua_pulleys(pulleys);
+
+            let joints = globals.get::<_, Vec<rlua::Table>>("joints").unwrap();
+            self.process_lua_joints(joints);
+
+            let graphs = globals.get::<_, Vec<rlua::Table>>("graphs").unwrap();
+            self.process_lua_graphs(lua_ctx, graphs);
+
+            let lab_controls = globals.get::<_, Vec<rlua::Table>>("lab_controls").unwrap();
+            self.process_lua_lab_controls(lua_ctx, lab_controls);
+
+            let goals = globals.get::<_, Vec<rlua::Table>>("goals").unwrap();
+            self.process_lua_goals(lua_ctx, goals);
 
             let shapes: Vec<rlua::Table> = Vec::new();
             globals.set("shapes", shapes).unwrap();
+            let fields: Vec<rlua::Table> = Vec::new();
+            globals.set("force_fields", fields).unwrap();
+            let soft_bodies: Vec<rlua::Table> = Vec::new();
+            globals.set("soft_bodies", soft_bodies).unwrap();
+            let pulleys: Vec<rlua::Table> = Vec::new();
+            globals.set("pulleys", pulleys).unwrap();
+            let joints: Vec<rlua::Table> = Vec::new();
+            globals.set("joints", joints).unwrap();
+            let graphs: Vec<rlua::Table> = Vec::new();
+            globals.set("graphs", graphs).unwrap();
+            let lab_controls: Vec<rlua::Table> = Vec::new();
+            globals.set("lab_controls", lab_controls).unwrap();
+            let goals: Vec<rlua::Table> = Vec::new();
+            globals.set("goals", goals).unwrap();
         });
     }
 
@@ -215,10 +1535,58 @@ impl<'a, 'b> MainState<'a, 'b> {
         let collider_set = self.world.fetch::<ColliderSet>();
 
         let colors = self.world.read_storage::<Color>();
+        let soft_body_particles = self.world.read_storage::<SoftBodyParticle>();
+        let names = self.world.read_storage::<Name>();
+        let pulleys = self.world.read_storage::<Pulley>();
+        let joints = self.world.read_storage::<Joint>();
+        let follow_paths = self.world.read_storage::<FollowPath>();
+        let entities = self.world.entities();
+
+        // pulleys and joints refer to their bodies by name, so bodies without one get
+        // a name made from their id. Particles aren't exported on their own
+        // so pulleys and joints attached to them are skipped.
+        let export_name = |entity: Entity| {
+            names
+                .get(entity)
+                .map(|name| name.0.clone())
+                .unwrap_or_else(|| format!("body{}", entity.id()))
+        };
+        // joints, pulleys, soft bodies, and force fields only get a name if they have one
+        let optional_name = |entity: Entity| {
+            names
+                .get(entity)
+                .map(|name| format!(", name = \"{}\"", name.0))
+                .unwrap_or_default()
+        };
+        let exportable = |body1: Entity, body2: Entity| {
+            soft_body_particles.get(body1).is_none() && soft_body_particles.get(body2).is_none()
+        };
+        // joints that just broke are still around while they flash
+        let exportable_joint =
+            |joint: &Joint| joint.broken_flash.is_none() && exportable(joint.body1, joint.body2);
+
+        // obj.data is kept in Lua, so it's turned into Lua source ahead of time
+        let data_strings: std::collections::HashMap<Entity, String> = {
+            let lua = LuaRes::clone(&self.world.fetch::<LuaRes>());
+            let lua = lua.lock().unwrap();
+            lua.context(|lua_ctx| {
+                (&physics_bodies, &entities)
+                    .join()
+                    .filter_map(|(_, entity)| {
+                        let data = get_lua_data(lua_ctx, entity).ok()??;
+                        match lua_source(&rlua::Value::Table(data), 0)?.as_str() {
+                            "{}" => None,
+                            data_str => Some((entity, format!(", data = {}", data_str))),
+                        }
+                    })
+                    .collect()
+            })
+        };
 
         let mut first = true;
 
-        (&physics_bodies, &colliders, &colors).join().for_each(|(physics_body_handle, collider_handle, color)|{
+        // soft body particles get exported with their soft body instead
+        (&physics_bodies, &colliders, &colors, &entities, !&soft_body_particles).join().for_each(|(physics_body_handle, collider_handle, color, entity, ())|{
             if !first {
                 body_string.push_str(",\n\t");
             } else {
@@ -249,15 +1617,48 @@ impl<'a, 'b> MainState<'a, 'b> {
 
             let material = collider.material().downcast_ref::<BasicMaterial<f32>>().unwrap();
 
+            let attached = pulleys.join().any(|pulley| {
+                exportable(pulley.body1, pulley.body2) && (pulley.body1 == entity || pulley.body2 == entity)
+            }) || joints.join().any(|joint| {
+                exportable_joint(joint) && (joint.body1 == entity || joint.body2 == entity)
+            });
+            let name_str = if names.get(entity).is_some() || attached {
+                format!(", name = \"{}\"", export_name(entity))
+            } else {
+                String::new()
+            };
+
             let status_str = match rigid_body.status() {
                 np::object::BodyStatus::Static => "static",
                 np::object::BodyStatus::Dynamic => "dynamic",
+                np::object::BodyStatus::Kinematic => "kinematic",
                 _ => panic!("Invalid body status for serialization"),
             };
 
+            let path_str = follow_paths.get(entity).map_or_else(String::new, |follow_path| {
+                let path = &follow_path.path;
+                let waypoints_str = path
+                    .keyframes
+                    .iter()
+                    .map(|keyframe| {
+                        format!(
+                            "{{x = {:.3}, y = {:.3}, t = {:.3}}}",
+                            keyframe.pos.x, keyframe.pos.y, keyframe.time
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                format!(
+                    ", path = {{interpolation = \"{}\", mode = \"{}\", {}}}",
+                    path.interpolation.name(),
+                    path.mode.name(),
+                    waypoints_str
+                )
+            });
+
             body_string.push_str(
                 format!(
-                    "{{shape = \"{shape_str}\", x = {x:.prec$}, y = {y:.prec$}, rotation = {rotation:.prec$}, x_vel = {x_vel:.prec$}, y_vel = {y_vel:.prec$}, rotvel = {rotvel:.prec$}, {shape_info_str}, mass = {mass:.prec$}, friction = {friction:.prec$}, elasticity = {elasticity:.prec$}, color = {{r = {red}, g = {green}, b = {blue}}}, status = \"{status}\"}}",
+                    "{{shape = \"{shape_str}\", x = {x:.prec$}, y = {y:.prec$}, rotation = {rotation:.prec$}, x_vel = {x_vel:.prec$}, y_vel = {y_vel:.prec$}, rotvel = {rotvel:.prec$}, {shape_info_str}, mass = {mass:.prec$}, friction = {friction:.prec$}, elasticity = {elasticity:.prec$}, color = {{r = {red}, g = {green}, b = {blue}}}, status = \"{status}\"{name_str}{path_str}{data_str}}}",
                     shape_str = shape_str,
                     x = position.translation.x,
                     y = position.translation.y,
@@ -273,12 +1674,201 @@ impl<'a, 'b> MainState<'a, 'b> {
                     red = (color.0.r * 255.0).round() as usize,
                     green = (color.0.g * 255.0).round() as usize,
                     blue = (color.0.b * 255.0).round() as usize,
+                    name_str = name_str,
+                    path_str = path_str,
+                    data_str = data_strings.get(&entity).map_or("", String::as_str),
                     prec = 3,
                 ).as_str())
         });
 
         let body_string = format!("add_shapes(\n\t{}\n)\n", body_string);
 
+        let force_gens = self.world.read_storage::<ForceGen>();
+        let force_gen_set = self.world.fetch::<ForceGeneratorSet>();
+        let field_strings = (&force_gens, &entities)
+            .join()
+            .filter_map(|(force_gen, entity)| {
+                force_gen_set
+                    .get(force_gen.gen_handle)
+                    .and_then(|gen| gen.downcast_ref::<ForceField>())
+                    .map(|field| (field, entity))
+            })
+            .map(|(field, entity)| {
+                let shape_info_str = match field.shape {
+                    FieldShape::Rectangle(half_extents) => {
+                        format!("w = {:.3}, h = {:.3}", half_extents.x, half_extents.y)
+                    }
+                    FieldShape::Circle(r) => format!("r = {:.3}", r),
+                };
+                format!(
+                    "{{kind = \"{kind}\", x = {x:.prec$}, y = {y:.prec$}, {shape_info_str}, strength = {strength:.prec$}, angle = {angle:.prec$}{name_str}}}",
+                    kind = field.kind.name(),
+                    x = field.center.x,
+                    y = field.center.y,
+                    shape_info_str = shape_info_str,
+                    strength = field.strength,
+                    angle = field.angle,
+                    name_str = optional_name(entity),
+                    prec = 3,
+                )
+            })
+            .collect::<Vec<String>>();
+
+        let body_string = if field_strings.is_empty() {
+            body_string
+        } else {
+            format!(
+                "{}\nadd_force_fields(\n\t{}\n)\n",
+                body_string,
+                field_strings.join(",\n\t")
+            )
+        };
+
+        let soft_bodies = self.world.read_storage::<SoftBody>();
+        let soft_body_strings = (&soft_bodies, &force_gens, &colors, &entities)
+            .join()
+            .filter_map(|(soft_body, force_gen, color, entity)| {
+                let springs = force_gen_set
+                    .get(force_gen.gen_handle)
+                    .and_then(|gen| gen.downcast_ref::<LatticeSprings>())?;
+                let first_collider = colliders
+                    .get(*soft_body.particles.first()?)
+                    .and_then(|collider| collider_set.get(collider.coll_handle))?;
+                let material = first_collider
+                    .material()
+                    .downcast_ref::<BasicMaterial<f32>>()
+                    .unwrap();
+
+                // the soft body doesn't have a position of its own,
+                // so it gets recreated around where its particles are
+                let center = soft_body
+                    .particles
+                    .iter()
+                    .filter_map(|particle| colliders.get(*particle))
+                    .filter_map(|collider| collider_set.get(collider.coll_handle))
+                    .map(|collider| collider.position().translation.vector)
+                    .sum::<Vector>()
+                    / soft_body.particles.len() as f32;
+
+                let (shape_str, shape_info_str) = match soft_body.shape {
+                    ShapeInfo::Rectangle(Some(half_extents)) => (
+                        "Rect",
+                        format!("w = {:.3}, h = {:.3}", half_extents.x, half_extents.y),
+                    ),
+                    ShapeInfo::Circle(Some(r)) => ("Circle", format!("r = {:.3}", r)),
+                    _ => panic!("Serialize invalid soft body shape"),
+                };
+
+                Some(format!(
+                    "{{shape = \"{shape_str}\", x = {x:.prec$}, y = {y:.prec$}, {shape_info_str}, resolution = {resolution}, stiffness = {stiffness:.prec$}, damping = {damping:.prec$}, mass = {mass:.prec$}, friction = {friction:.prec$}, elasticity = {elasticity:.prec$}, color = {{r = {red}, g = {green}, b = {blue}}}{name_str}}}",
+                    shape_str = shape_str,
+                    x = center.x,
+                    y = center.y,
+                    shape_info_str = shape_info_str,
+                    resolution = soft_body.resolution,
+                    stiffness = springs.stiffness,
+                    damping = springs.damping,
+                    mass = soft_body.mass,
+                    friction = material.friction,
+                    elasticity = material.restitution,
+                    red = (color.0.r * 255.0).round() as usize,
+                    green = (color.0.g * 255.0).round() as usize,
+                    blue = (color.0.b * 255.0).round() as usize,
+                    name_str = optional_name(entity),
+                    prec = 3,
+                ))
+            })
+            .collect::<Vec<String>>();
+
+        let body_string = if soft_body_strings.is_empty() {
+            body_string
+        } else {
+            format!(
+                "{}\nadd_soft_bodies(\n\t{}\n)\n",
+                body_string,
+                soft_body_strings.join(",\n\t")
+            )
+        };
+
+        let pulley_strings = (&pulleys, &force_gens, &entities)
+            .join()
+            .filter(|(pulley, _, _)| exportable(pulley.body1, pulley.body2))
+            .filter_map(|(pulley, force_gen, entity)| {
+                force_gen_set
+                    .get(force_gen.gen_handle)
+                    .and_then(|gen| gen.downcast_ref::<PulleyConstraint>())
+                    .map(|constraint| (pulley, constraint, entity))
+            })
+            .map(|(pulley, constraint, entity)| {
+                let wheels_str = constraint
+                    .wheels
+                    .iter()
+                    .map(|wheel| format!("{{x = {:.3}, y = {:.3}}}", wheel.x, wheel.y))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                format!(
+                    "{{body1 = \"{body1}\", body2 = \"{body2}\", wheels = {{{wheels_str}}}, ratio = {ratio:.prec$}, length = {length:.prec$}{name_str}}}",
+                    body1 = export_name(pulley.body1),
+                    body2 = export_name(pulley.body2),
+                    wheels_str = wheels_str,
+                    ratio = constraint.ratio,
+                    length = constraint.length,
+                    name_str = optional_name(entity),
+                    prec = 3,
+                )
+            })
+            .collect::<Vec<String>>();
+
+        let body_string = if pulley_strings.is_empty() {
+            body_string
+        } else {
+            format!(
+                "{}\nadd_pulleys(\n\t{}\n)\n",
+                body_string,
+                pulley_strings.join(",\n\t")
+            )
+        };
+
+        let joint_strings = (&joints, &entities)
+            .join()
+            .filter(|(joint, _)| exportable_joint(joint))
+            .map(|(joint, entity)| {
+                // the anchor is saved in world space
+                let anchor = body_set
+                    .rigid_body(physics_bodies.get(joint.body1).unwrap().body_handle)
+                    .unwrap()
+                    .position()
+                    * joint.anchor1;
+                let break_str = |key: &str, threshold: Option<f32>| {
+                    threshold
+                        .map(|threshold| format!(", {} = {:.3}", key, threshold))
+                        .unwrap_or_default()
+                };
+                format!(
+                    "{{kind = \"{kind}\", body1 = \"{body1}\", body2 = \"{body2}\", x = {x:.prec$}, y = {y:.prec$}{break_force}{break_torque}{name_str}}}",
+                    kind = joint.kind.name(),
+                    body1 = export_name(joint.body1),
+                    body2 = export_name(joint.body2),
+                    x = anchor.x,
+                    y = anchor.y,
+                    break_force = break_str("break_force", joint.break_force),
+                    break_torque = break_str("break_torque", joint.break_torque),
+                    name_str = optional_name(entity),
+                    prec = 3,
+                )
+            })
+            .collect::<Vec<String>>();
+
+        let body_string = if joint_strings.is_empty() {
+            body_string
+        } else {
+            format!(
+                "{}\nadd_joints(\n\t{}\n)\n",
+                body_string,
+                joint_strings.join(",\n\t")
+            )
+        };
+
         let mut consts_str = String::with_capacity(48);
         let gravity = {
             let mech_world = self.world.fetch::<MechanicalWorld>();
@@ -286,22 +1876,296 @@ impl<'a, 'b> MainState<'a, 'b> {
         };
         consts_str.push_str(format!("GRAVITY = {}\n", gravity).as_str());
 
-        let lua_string = format!("{}\n{}", body_string, consts_str);
+        // the seed goes first so that it's set before anything random happens
+        let rng_str = {
+            let rng = self.world.fetch::<LuaRngRes>();
+            let rng = rng.lock().unwrap();
+            if rng.replace_math_random() {
+                format!(
+                    "rng.seed({})\nrng.replace_math_random(true)\n\n",
+                    rng.seed()
+                )
+            } else {
+                format!("rng.seed({})\n\n", rng.seed())
+            }
+        };
+
+        let lua_string = format!("{}{}\n{}", rng_str, body_string, consts_str);
 
         std::fs::write(filename, lua_string).unwrap();
     }
 
-    pub fn lua_update(&mut self) {
-        let lua = self.world.fetch_mut::<crate::resources::LuaRes>().clone();
-        lua.lock().unwrap().context(|lua_ctx| {
-            if let Err(e) = lua_ctx.load("update()").exec() {
-                panic!("Lua Error in update(): {}", e.to_string())
+    /// refreshes what LuaEntity methods read, has to be done before running Lua code
+    pub fn snapshot_lua_bodies(&self) {
+        let bridge = LuaBridgeRes::clone(&self.world.fetch::<LuaBridgeRes>());
+        bridge.lock().unwrap().snapshot_bodies(
+            &self.world.fetch::<BodySet>(),
+            &self.world.read_storage::<PhysicsBody>(),
+            &self.world.read_storage::<Color>(),
+            &self.world.entities(),
+        );
+    }
+
+    /// applies the writes LuaEntity methods queued up and deletes destroyed objects,
+    /// has to be done after running Lua code
+    pub fn apply_lua_writes(&mut self) {
+        // spawned objects need their components before anything can be done to them
+        self.world.maintain();
+
+        let bridge = LuaBridgeRes::clone(&self.world.fetch::<LuaBridgeRes>());
+        let destroyed = {
+            let mut bridge = bridge.lock().unwrap();
+            bridge.apply_commands(
+                &mut self.world.fetch_mut::<BodySet>(),
+                &self.world.read_storage::<PhysicsBody>(),
+                &mut self.world.write_storage::<Color>(),
+            );
+            std::mem::take(&mut bridge.destroyed)
+        };
+        destroyed
+            .into_iter()
+            .for_each(|entity| self.delete_entity(entity));
+    }
+
+    /// Calls a global Lua event handler like on_key_down() if the script defines one.
+    /// Returns true if the handler returned true, which means it handled the event.
+    pub fn call_lua_handler<A>(&mut self, name: &str, args: A) -> bool
+    where
+        A: for<'lua> rlua::ToLuaMulti<'lua>,
+    {
+        self.snapshot_lua_bodies();
+
+        let lua = LuaRes::clone(&self.world.fetch::<LuaRes>());
+        let lua = lua.lock().unwrap();
+        let limits = *self.world.fetch::<LuaLimits>();
+        let handled = lua.context(|lua_ctx| {
+            let handler = match lua_ctx.globals().get::<_, rlua::Function>(name) {
+                Ok(handler) => handler,
+                Err(_) => return false,
+            };
+            with_memory_limit(&lua, &limits, || {
+                self.run_with_world_fns(lua_ctx, || match handler.call::<_, bool>(args) {
+                    Ok(handled) => handled,
+                    Err(e) => {
+                        self.log_lua_error(&e, None);
+                        false
+                    }
+                })
+            })
+        });
+        std::mem::drop(lua);
+
+        self.apply_lua_writes();
+        handled
+    }
+
+    /// calls the functions given to after() and every() that are due, in the order they were due
+    fn run_lua_timers(&self, lua_ctx: rlua::Context) {
+        let next_timer = lua_ctx
+            .globals()
+            .get::<_, rlua::Function>("due_timers")
+            .and_then(|due_timers| due_timers.call::<_, rlua::Function>(()));
+        let next_timer = match next_timer {
+            Ok(next_timer) => next_timer,
+            Err(e) => {
+                self.log_lua_error(&e, None);
+                return;
+            }
+        };
+
+        loop {
+            match next_timer.call::<_, (Option<i64>, Option<rlua::Function>)>(()) {
+                Ok((Some(id), Some(timer_fn))) => {
+                    if let Err(e) = timer_fn.call::<_, ()>(id) {
+                        self.log_lua_error(&e, None);
+                    }
+                }
+                Ok(_) => break,
+                Err(e) => {
+                    self.log_lua_error(&e, None);
+                    break;
+                }
             }
+        }
+    }
+
+    /// Calls check() and fail() for the goals that are still pending. A goal whose
+    /// functions error is marked as failed so that the error isn't logged every frame.
+    fn check_lua_goals(&self, lua_ctx: rlua::Context) {
+        let time = self.world.fetch::<resources::SimTime>().0;
+        // the goals are copied out first since checking them can add more
+        let pending = self
+            .world
+            .fetch::<Goals>()
+            .0
+            .iter()
+            .enumerate()
+            .filter(|(_, goal)| goal.status == GoalStatus::Pending)
+            .map(|(i, goal)| {
+                let check = lua_ctx.registry_value::<rlua::Function>(&goal.check);
+                let fail = goal
+                    .fail
+                    .as_ref()
+                    .map(|key| lua_ctx.registry_value::<rlua::Function>(key));
+                (i, goal.description.clone(), check, fail, goal.deadline)
+            })
+            .collect::<Vec<_>>();
+
+        let statuses = pending
+            .into_iter()
+            .map(|(i, description, check, fail, deadline)| {
+                let result = check.and_then(|check| {
+                    if check.call::<_, bool>(())? {
+                        return Ok(GoalStatus::Passed(time));
+                    }
+                    let failed = match fail {
+                        Some(fail) => fail?.call::<_, bool>(())?,
+                        None => false,
+                    };
+                    if failed || matches!(deadline, Some(deadline) if time >= deadline) {
+                        Ok(GoalStatus::Failed(time))
+                    } else {
+                        Ok(GoalStatus::Pending)
+                    }
+                });
+                let status = result.unwrap_or_else(|e| {
+                    self.log_lua_error(&e, Some(description));
+                    GoalStatus::Failed(time)
+                });
+                (i, status)
+            })
+            .collect::<Vec<_>>();
+
+        let mut goals = self.world.fetch_mut::<Goals>();
+        statuses.into_iter().for_each(|(i, status)| {
+            goals.0[i].status = status;
+        });
+    }
+
+    /// The Lua state is shared between scenes, so this is done before loading
+    /// a new one so that the last scene's handlers aren't called
+    pub fn clear_lua_handlers(&self) {
+        let lua = LuaRes::clone(&self.world.fetch::<LuaRes>());
+        lua.lock().unwrap().context(|lua_ctx| {
+            let globals = lua_ctx.globals();
+            LUA_HANDLERS.iter().for_each(|handler| {
+                globals.set(*handler, rlua::Value::Nil).unwrap();
+            });
+            globals
+                .set("timers", lua_ctx.create_table().unwrap())
+                .unwrap();
+        });
+    }
+
+    /// Updates KEYS_DOWN and calls on_key_down() or on_key_up(),
+    /// returns true if the script handled the key
+    pub fn lua_key_event(&mut self, key: KeyCode, down: bool, repeat: bool) -> bool {
+        let key_name = lua_key_name(key);
+
+        let lua = LuaRes::clone(&self.world.fetch::<LuaRes>());
+        lua.lock().unwrap().context(|lua_ctx| {
+            let keys_down: rlua::Table = lua_ctx.globals().get("KEYS_DOWN").unwrap();
+            keys_down
+                .set(key_name.as_str(), if down { Some(true) } else { None })
+                .unwrap();
+        });
+
+        if down {
+            self.call_lua_handler("on_key_down", (key_name, repeat))
+        } else {
+            self.call_lua_handler("on_key_up", key_name)
+        }
+    }
+
+    /// Calls on_mouse_down() or on_mouse_up() with the world position of the mouse,
+    /// returns true if the script handled the click
+    pub fn lua_mouse_event(&mut self, btn: MouseButton, down: bool) -> bool {
+        let btn_name = match btn {
+            MouseButton::Left => "left",
+            MouseButton::Right => "right",
+            MouseButton::Middle => "middle",
+            MouseButton::Other(_) => "other",
+        };
+        let mouse_pos = self.world.fetch::<resources::MousePos>().0;
+        let handler = if down { "on_mouse_down" } else { "on_mouse_up" };
+        self.call_lua_handler(handler, (btn_name, mouse_pos.x, mouse_pos.y))
+    }
+
+    pub fn lua_update(&mut self) {
+        self.snapshot_lua_bodies();
 
+        let lua = self.world.fetch_mut::<crate::resources::LuaRes>().clone();
+        let lua = lua.lock().unwrap();
+        let limits = *self.world.fetch::<LuaLimits>();
+        let paused = self.world.fetch::<Paused>().0;
+        lua.context(|lua_ctx| {
             let globals = lua_ctx.globals();
+            globals
+                .set("TIME", self.world.fetch::<resources::SimTime>().0)
+                .unwrap();
+
+            with_memory_limit(&lua, &limits, || {
+                self.run_with_world_fns(lua_ctx, || {
+                    if !paused {
+                        self.run_lua_timers(lua_ctx);
+                    }
+
+                    if let Err(e) = lua_ctx.load("update()").exec() {
+                        self.log_lua_error(&e, None);
+                    }
+
+                    // on_joint_break() is called with the names of the joint and its bodies
+                    let broken_joints =
+                        std::mem::take(&mut self.world.fetch_mut::<BrokenJoints>().0);
+                    if let Ok(on_joint_break) = globals.get::<_, rlua::Function>("on_joint_break") {
+                        broken_joints.into_iter().for_each(|joint_break| {
+                            // the memory limit is on, so making the table can fail too
+                            let result = lua_ctx.create_table().and_then(|event| {
+                                event.set("name", joint_break.name)?;
+                                event.set("body1", joint_break.body1)?;
+                                event.set("body2", joint_break.body2)?;
+                                on_joint_break.call::<_, ()>(event)
+                            });
+                            if let Err(e) = result {
+                                self.log_lua_error(&e, None);
+                            }
+                        });
+                    }
+
+                    if !paused {
+                        self.check_lua_goals(lua_ctx);
+                    }
+                })
+            });
+
             if let Ok(true) = globals.get("ADD_SHAPES") {
-                self.process_lua_shapes(globals.get::<_, Vec<rlua::Table>>("shapes").unwrap());
+                self.process_lua_shapes(
+                    lua_ctx,
+                    globals.get::<_, Vec<rlua::Table>>("shapes").unwrap(),
+                );
+                self.process_lua_force_fields(
+                    globals.get::<_, Vec<rlua::Table>>("force_fields").unwrap(),
+                );
+                self.process_lua_soft_bodies(
+                    globals.get::<_, Vec<rlua::Table>>("soft_bodies").unwrap(),
+                );
+                self.process_lua_pulleys(globals.get::<_, Vec<rlua::Table>>("pulleys").unwrap());
+                self.process_lua_joints(globals.get::<_, Vec<rlua::Table>>("joints").unwrap());
             }
+            // unlike shapes, graphs, lab controls, and goals can be added at any time
+            self.process_lua_graphs(
+                lua_ctx,
+                globals.get::<_, Vec<rlua::Table>>("graphs").unwrap(),
+            );
+            self.process_lua_lab_controls(
+                lua_ctx,
+                globals.get::<_, Vec<rlua::Table>>("lab_controls").unwrap(),
+            );
+            self.process_lua_goals(
+                lua_ctx,
+                globals.get::<_, Vec<rlua::Table>>("goals").unwrap(),
+            );
+            self.process_lua_drawings(globals.get::<_, Vec<rlua::Table>>("drawings").unwrap());
 
             if let Ok(paused) = globals.get::<_, bool>("PAUSED") {
                 self.world.insert::<Paused>(Paused(paused));
@@ -330,22 +2194,55 @@ impl<'a, 'b> MainState<'a, 'b> {
             }
 
             {
-                pub struct LuaEntity(pub Entity);
-                impl rlua::UserData for LuaEntity {
-                    fn add_methods<'lua, M: rlua::UserDataMethods<'lua, Self>>(methods: &mut M) {
-                        methods.add_method("id", |_, this, _: ()| Ok(this.0.id()));
-                    }
-                }
-
+                let bridge = LuaBridgeRes::clone(&self.world.fetch::<LuaBridgeRes>());
                 let entities = self.world.entities();
                 let names = self.world.read_storage::<Name>();
+                let physics_bodies = self.world.read_storage::<PhysicsBody>();
                 let lua_objects = lua_ctx.create_table().unwrap();
-                (&entities, &names).join().for_each(|(entity, name)| {
-                    lua_objects.set(name.0.as_str(), LuaEntity(entity)).unwrap();
-                });
+                (&entities, &names, &physics_bodies)
+                    .join()
+                    .for_each(|(entity, name, _)| {
+                        lua_objects
+                            .set(name.0.as_str(), LuaEntity(entity, bridge.clone()))
+                            .unwrap();
+                    });
                 globals.set("OBJECTS", lua_objects).unwrap();
             }
 
+            {
+                // pulleys can be looked up by name or by the order they were created in
+                let pulleys = self.world.read_storage::<Pulley>();
+                let force_gens = self.world.read_storage::<ForceGen>();
+                let names = self.world.read_storage::<Name>();
+                let entities = self.world.entities();
+                let force_gen_set = self.world.fetch::<ForceGeneratorSet>();
+
+                let lua_pulleys = lua_ctx.create_table().unwrap();
+                (&pulleys, &force_gens, &entities)
+                    .join()
+                    .filter_map(|(_, force_gen, entity)| {
+                        force_gen_set
+                            .get(force_gen.gen_handle)
+                            .and_then(|gen| gen.downcast_ref::<PulleyConstraint>())
+                            .map(|constraint| (constraint, entity))
+                    })
+                    .enumerate()
+                    .for_each(|(i, (constraint, entity))| {
+                        let pulley_table = lua_ctx.create_table().unwrap();
+                        pulley_table.set("tension", constraint.tension).unwrap();
+                        pulley_table.set("ratio", constraint.ratio).unwrap();
+                        pulley_table.set("length", constraint.length).unwrap();
+
+                        if let Some(name) = names.get(entity) {
+                            lua_pulleys
+                                .set(name.0.as_str(), pulley_table.clone())
+                                .unwrap();
+                        }
+                        lua_pulleys.set(i + 1, pulley_table).unwrap();
+                    });
+                globals.set("PULLEYS", lua_pulleys).unwrap();
+            }
+
             {
                 let mouse_pos = self.world.fetch::<resources::MousePos>().0;
                 globals.set("MOUSE_X", mouse_pos.x).unwrap();
@@ -354,6 +2251,322 @@ impl<'a, 'b> MainState<'a, 'b> {
 
             let shapes: Vec<rlua::Table> = Vec::new();
             globals.set("shapes", shapes).unwrap();
+            let fields: Vec<rlua::Table> = Vec::new();
+            globals.set("force_fields", fields).unwrap();
+            let soft_bodies: Vec<rlua::Table> = Vec::new();
+            globals.set("soft_bodies", soft_bodies).unwrap();
+            let pulleys: Vec<rlua::Table> = Vec::new();
+            globals.set("pulleys", pulleys).unwrap();
+            let joints: Vec<rlua::Table> = Vec::new();
+            globals.set("joints", joints).unwrap();
+            let graphs: Vec<rlua::Table> = Vec::new();
+            globals.set("graphs", graphs).unwrap();
+            let lab_controls: Vec<rlua::Table> = Vec::new();
+            globals.set("lab_controls", lab_controls).unwrap();
+            let drawings: Vec<rlua::Table> = Vec::new();
+            globals.set("drawings", drawings).unwrap();
+            let goals: Vec<rlua::Table> = Vec::new();
+            globals.set("goals", goals).unwrap();
+
+            self.drain_lua_prints(lua_ctx);
+        });
+        std::mem::drop(lua);
+
+        self.apply_lua_writes();
+    }
+
+    /// Runs a line from the REPL. Lines are saved up until they make a complete chunk,
+    /// and what expressions like `GRAVITY` or `OBJECTS.ball:get_pos()` return is printed.
+    pub fn run_lua_repl(&mut self, line: String) {
+        let chunk = {
+            let mut repl = self.world.fetch_mut::<LuaRepl>();
+            if line.trim().is_empty() && repl.pending.is_empty() {
+                return;
+            }
+            let prompt = if repl.pending.is_empty() { ">" } else { ">>" };
+            self.world.fetch_mut::<LuaConsole>().log(ConsoleEntry::new(
+                ConsoleEntryKind::Input,
+                format!("{} {}", prompt, line),
+            ));
+            repl.pending.push_str(&line);
+            std::mem::take(&mut repl.pending)
+        };
+
+        self.snapshot_lua_bodies();
+
+        let lua = LuaRes::clone(&self.world.fetch::<LuaRes>());
+        let lua = lua.lock().unwrap();
+        let limits = *self.world.fetch::<LuaLimits>();
+        lua.context(|lua_ctx| {
+            // like the standalone Lua REPL, it's tried as an expression first
+            let expression = format!("return {}", chunk);
+            let function = lua_ctx
+                .load(&expression)
+                .set_name("=repl")
+                .and_then(rlua::Chunk::into_function)
+                .or_else(|_| {
+                    lua_ctx
+                        .load(&chunk)
+                        .set_name("=repl")
+                        .and_then(rlua::Chunk::into_function)
+                });
+
+            match function {
+                Ok(function) => {
+                    let result = with_memory_limit(&lua, &limits, || {
+                        self.run_with_world_fns(lua_ctx, || {
+                            function.call::<_, rlua::MultiValue>(())
+                        })
+                    });
+                    match result {
+                        Ok(values) if !values.is_empty() => {
+                            let message = values
+                                .iter()
+                                .map(pretty_print)
+                                .collect::<Vec<String>>()
+                                .join(", ");
+                            self.world
+                                .fetch_mut::<LuaConsole>()
+                                .log(ConsoleEntry::new(ConsoleEntryKind::Result, message));
+                        }
+                        Ok(_) => {}
+                        Err(e) => self.log_lua_error(&e, None),
+                    }
+                }
+                Err(rlua::Error::SyntaxError {
+                    incomplete_input: true,
+                    ..
+                }) => {
+                    self.world.fetch_mut::<LuaRepl>().pending = chunk + "\n";
+                }
+                Err(e) => self.log_lua_error(&e, None),
+            }
+
+            self.drain_lua_prints(lua_ctx);
+        });
+        std::mem::drop(lua);
+
+        self.apply_lua_writes();
+    }
+
+    /// Replaces the Lua state with a new one so nothing from the last run of a scene is left over
+    pub fn reset_lua_state(&self) {
+        let instructions_left =
+            LuaInstructionsLeft::clone(&self.world.fetch::<LuaInstructionsLeft>());
+        let rng = LuaRngRes::clone(&self.world.fetch::<LuaRngRes>());
+        *self.world.fetch::<LuaRes>().lock().unwrap() = new_lua(instructions_left, rng);
+    }
+
+    /// lua_update() takes the pause state from PAUSED, so this keeps it from being overwritten
+    pub fn set_lua_paused(&self, paused: bool) {
+        let lua = LuaRes::clone(&self.world.fetch::<LuaRes>());
+        lua.lock().unwrap().context(|lua_ctx| {
+            lua_ctx.globals().set("PAUSED", paused).unwrap();
+        });
+    }
+
+    /// Gives the scripts a new instruction budget, done at the start of every frame
+    pub fn reset_lua_budget(&self) {
+        let limits = self.world.fetch::<LuaLimits>();
+        self.world
+            .fetch::<LuaInstructionsLeft>()
+            .store(limits.instructions_per_frame, Ordering::Relaxed);
+    }
+
+    pub fn log_lua_error(&self, error: &rlua::Error, object: Option<String>) {
+        self.world
+            .fetch_mut::<LuaConsole>()
+            .log_error(error, object);
+    }
+
+    /// print() adds to console_output instead of writing to stdout
+    fn drain_lua_prints(&self, lua_ctx: rlua::Context) {
+        let globals = lua_ctx.globals();
+        let output: Vec<rlua::Table> = globals.get("console_output").unwrap_or_default();
+        let mut console = self.world.fetch_mut::<LuaConsole>();
+        output.iter().for_each(|print| {
+            let message = print.get("message").unwrap_or_default();
+            let location: String = print.get("location").unwrap_or_default();
+            console.log(ConsoleEntry::print(message, &location));
         });
+        globals
+            .set("console_output", lua_ctx.create_table().unwrap())
+            .unwrap();
+    }
+
+    /// Opens the console when there are new errors and pauses if PauseOnLuaError is set
+    pub fn show_lua_errors(&mut self) {
+        let new_error = std::mem::replace(
+            &mut self.world.fetch_mut::<LuaConsole>().unseen_error,
+            false,
+        );
+        if new_error {
+            self.imgui_wrapper.shown_menus.insert(UiChoice::LuaConsole);
+            if self.world.fetch::<PauseOnLuaError>().0 {
+                self.world.insert(Paused(true));
+            }
+        }
     }
 }
+
+/// Keys are named like their KeyCode in lowercase, e.g. "space", "a", "key1", "left", "lshift"
+fn lua_key_name(key: KeyCode) -> String {
+    format!("{:?}", key).to_lowercase()
+}
+
+fn lua_error(message: impl Into<String>) -> rlua::Error {
+    rlua::Error::RuntimeError(message.into())
+}
+
+/// NaN and infinite coordinates, e.g. from dividing by 0, can't be queried
+fn check_finite(coords: &[f32]) -> rlua::Result<()> {
+    if coords.iter().all(|coord| coord.is_finite()) {
+        Ok(())
+    } else {
+        Err(lua_error("coordinates have to be finite numbers"))
+    }
+}
+
+/// table.get() for keys that can't be left out, the error says which key it was
+fn get_required<'lua, T: rlua::FromLua<'lua>>(
+    table: &rlua::Table<'lua>,
+    key: &str,
+) -> rlua::Result<T> {
+    table
+        .get(key)
+        .map_err(|_| lua_error(format!("\"{}\" is missing or has the wrong type", key)))
+}
+
+/// Colors are tables like {r = 255, g = 0, b = 0, a = 255}, white if there isn't one
+fn color_from_lua(table: &rlua::Table) -> rlua::Result<ggez::graphics::Color> {
+    match table.get::<_, rlua::Table>("color") {
+        Ok(color) => {
+            schema::check_fields(&color, schema::COLOR_FIELDS)?;
+            let channel =
+                |key| -> rlua::Result<u8> { Ok(get_required::<f32>(&color, key)?.round() as u8) };
+            let a = match color.get::<_, Option<f32>>("a")? {
+                Some(a) => a.round() as u8,
+                None => 255,
+            };
+            Ok(ggez::graphics::Color::from_rgba(
+                channel("r")?,
+                channel("g")?,
+                channel("b")?,
+                a,
+            ))
+        }
+        Err(_) => Ok(ggez::graphics::WHITE),
+    }
+}
+
+/// SCENE is a table like {title = "Pendulum Lab", author = "...", description = "...",
+/// instructions = [[...]]}, where everything is optional
+fn scene_info_from_lua(scene: &rlua::Table) -> rlua::Result<SceneInfo> {
+    Ok(SceneInfo {
+        title: scene.get("title")?,
+        author: scene.get("author")?,
+        description: scene.get("description")?,
+        instructions: scene.get("instructions")?,
+    })
+}
+
+/// Runs a scene in a Lua state of its own to get its SCENE table without loading it.
+/// Errors are left for when it's loaded.
+pub fn read_scene_info(path: &std::path::Path) -> Option<SceneInfo> {
+    let code = std::fs::read_to_string(path).ok()?;
+    let limits = LuaLimits::default();
+    let instructions_left = LuaInstructionsLeft::new(limits.instructions_per_frame.into());
+    let lua = new_lua(instructions_left, LuaRngRes::default());
+    lua.context(|lua_ctx| {
+        // the whole file runs, so it gets the same memory limit as a scene that's loaded
+        let _ = with_memory_limit(&lua, &limits, || lua_ctx.load(&code).exec());
+        let scene: rlua::Table = lua_ctx.globals().get("SCENE").ok()?;
+        scene_info_from_lua(&scene).ok()
+    })
+}
+
+/// how deep tables in obj.data are exported, which also stops tables that contain themselves
+const MAX_EXPORT_DEPTH: usize = 16;
+
+const LUA_KEYWORDS: [&str; 22] = [
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+/// Writes a value as Lua source for export_lua(). Functions, handles, and the like
+/// can't be written out, so table entries with them are left out, and None is
+/// returned for the values themselves.
+fn lua_source(value: &rlua::Value, depth: usize) -> Option<String> {
+    match value {
+        rlua::Value::Boolean(b) => Some(b.to_string()),
+        rlua::Value::Integer(i) => Some(i.to_string()),
+        // {:?} keeps the decimal point so that floats stay floats
+        rlua::Value::Number(n) if n.is_finite() => Some(format!("{:?}", n)),
+        rlua::Value::String(s) => Some(format!("{:?}", s.to_str().ok()?)),
+        rlua::Value::Table(table) if depth < MAX_EXPORT_DEPTH => {
+            let mut entries = table
+                .clone()
+                .pairs::<rlua::Value, rlua::Value>()
+                .filter_map(Result::ok)
+                .filter_map(|(key, value)| {
+                    let key = match &key {
+                        rlua::Value::String(s) if is_lua_name(s.to_str().ok()?) => {
+                            s.to_str().ok()?.to_string()
+                        }
+                        _ => format!("[{}]", lua_source(&key, depth + 1)?),
+                    };
+                    Some(format!("{} = {}", key, lua_source(&value, depth + 1)?))
+                })
+                .collect::<Vec<String>>();
+            // pairs() doesn't have an order, so they're sorted to keep exports the same
+            entries.sort();
+            Some(format!("{{{}}}", entries.join(", ")))
+        }
+        _ => None,
+    }
+}
+
+/// whether a key can be written as {key = value} instead of {["key"] = value}
+fn is_lua_name(s: &str) -> bool {
+    s.is_ascii() && repl::is_identifier(s) && !LUA_KEYWORDS.contains(&s)
+}
+
+/// Paths are a list of waypoints like {x = 1, y = 2, t = 0.5}
+/// along with optional interpolation and mode keys
+fn process_lua_path(path: &rlua::Table) -> rlua::Result<KeyframePath> {
+    let keyframes = path
+        .clone()
+        .sequence_values::<rlua::Table>()
+        .map(|waypoint| {
+            let waypoint = waypoint?;
+            let time: f32 = get_required(&waypoint, "t")?;
+            if !time.is_finite() {
+                return Err(lua_error(format!(
+                    "waypoint times have to be finite numbers, not {}",
+                    time
+                )));
+            }
+            Ok(Keyframe {
+                time,
+                pos: Vector::new(get_required(&waypoint, "x")?, get_required(&waypoint, "y")?),
+            })
+        })
+        .collect::<rlua::Result<_>>()?;
+
+    let interpolation = match path.get::<_, String>("interpolation") {
+        Ok(name) => PathInterpolation::from_name(&name)
+            .ok_or_else(|| lua_error(format!("invalid path interpolation \"{}\"", name)))?,
+        Err(_) => PathInterpolation::Linear,
+    };
+    let mode = match path.get::<_, String>("mode") {
+        Ok(name) => PathMode::from_name(&name)
+            .ok_or_else(|| lua_error(format!("invalid path mode \"{}\"", name)))?,
+        Err(_) => PathMode::Loop,
+    };
+
+    Ok(KeyframePath {
+        interpolation,
+        mode,
+        ..KeyframePath::new(keyframes)
+    })
+}
@@ -0,0 +1,404 @@
+use specs::prelude::*;
+
+use super::lua_entity::{lua_data, set_lua_data};
+use super::schema::{find_field, SHAPE_FIELDS};
+use crate::{RigidBody, ShapeHandle, Vector};
+
+use nc::pipeline::object::GeometricQueryType;
+use nc::shape::{Ball, Cuboid};
+use ncollide2d as nc;
+use np::material::BasicMaterial;
+use np::object::{Body, BodyStatus};
+use nphysics2d as np;
+
+/// nphysics' defaults, used when collisions are turned back on
+const LINEAR_PREDICTION: f32 = 0.001;
+const ANGULAR_PREDICTION: f32 = std::f32::consts::PI / 180.0 * 5.0;
+
+pub type NpCollider = np::object::Collider<f32, np::object::DefaultBodyHandle>;
+
+/// The number fields of obj, in the order they're stored in BodyFields.numbers.
+/// Like in add_shape(), w and h are half of the width and height.
+const NUMBER_FIELDS: [&str; 12] = [
+    "x",
+    "y",
+    "rot",
+    "x_vel",
+    "y_vel",
+    "rotvel",
+    "mass",
+    "friction",
+    "elasticity",
+    "r",
+    "w",
+    "h",
+];
+const X: usize = 0;
+const Y: usize = 1;
+const ROT: usize = 2;
+const X_VEL: usize = 3;
+const Y_VEL: usize = 4;
+const ROTVEL: usize = 5;
+const MASS: usize = 6;
+const FRICTION: usize = 7;
+const ELASTICITY: usize = 8;
+const R: usize = 9;
+const W: usize = 10;
+const H: usize = 11;
+
+/// bits in BodyProxy.written after the ones for the number fields
+const STATUS_BIT: u32 = 1 << NUMBER_FIELDS.len();
+const COLLISION_BIT: u32 = STATUS_BIT << 1;
+
+/// What update functions can read and change about a body. The size fields
+/// are None for shapes that don't have them, e.g. w and h for circles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BodyFields {
+    numbers: [Option<f32>; 12],
+    status: BodyStatus,
+    collision: bool,
+}
+
+impl BodyFields {
+    fn from_body(rigid_body: &RigidBody, collider: &NpCollider) -> Self {
+        let isometry = rigid_body.position();
+        let vel = rigid_body.velocity();
+        let material = collider
+            .material()
+            .downcast_ref::<BasicMaterial<f32>>()
+            .unwrap();
+
+        let mut numbers = [None; 12];
+        numbers[X] = Some(isometry.translation.x);
+        numbers[Y] = Some(isometry.translation.y);
+        numbers[ROT] = Some(isometry.rotation.angle());
+        numbers[X_VEL] = Some(vel.linear.x);
+        numbers[Y_VEL] = Some(vel.linear.y);
+        numbers[ROTVEL] = Some(vel.angular);
+        numbers[MASS] = Some(rigid_body.augmented_mass().mass());
+        numbers[FRICTION] = Some(material.friction);
+        numbers[ELASTICITY] = Some(material.restitution);
+
+        let shape = collider.shape();
+        if let Some(ball) = shape.downcast_ref::<Ball<f32>>() {
+            numbers[R] = Some(ball.radius());
+        } else if let Some(cuboid) = shape.downcast_ref::<Cuboid<f32>>() {
+            numbers[W] = Some(cuboid.half_extents().x);
+            numbers[H] = Some(cuboid.half_extents().y);
+        }
+
+        BodyFields {
+            numbers,
+            status: rigid_body.status(),
+            collision: !collider.is_sensor(),
+        }
+    }
+
+    /// For update functions that return a table of their own instead of obj. The fields
+    /// that aren't in the table keep the body's current values, returns them with the
+    /// bits for the ones that were there.
+    fn from_table(table: &rlua::Table, current: Self) -> rlua::Result<(Self, u32)> {
+        let mut fields = current;
+        let mut written = 0;
+        for (i, name) in NUMBER_FIELDS.iter().enumerate() {
+            let value: rlua::Value = table.get(*name)?;
+            if let rlua::Value::Nil = value {
+                continue;
+            }
+            fields.numbers[i] = Some(checked_number(name, value)?);
+            written |= 1 << i;
+        }
+
+        if let Some(status) = table.get::<_, Option<String>>("status")? {
+            fields.status = status_from_name(&status)?;
+            written |= STATUS_BIT;
+        }
+        if let Some(collision) = table.get::<_, Option<bool>>("collision")? {
+            fields.collision = collision;
+            written |= COLLISION_BIT;
+        }
+        Ok((fields, written))
+    }
+
+    /// Changes the body to match the fields in written. Only the ones that are
+    /// different are set so that bodies that weren't touched can stay asleep.
+    pub fn apply(
+        &self,
+        written: u32,
+        rigid_body: &mut RigidBody,
+        collider: &mut NpCollider,
+    ) -> rlua::Result<()> {
+        let current = BodyFields::from_body(rigid_body, collider);
+        let changed = |i: usize| {
+            written & (1 << i) != 0
+                && self.numbers[i].is_some()
+                && self.numbers[i] != current.numbers[i]
+        };
+        let number = |i: usize| self.numbers[i].unwrap_or(0.0);
+        let mut wake_up = false;
+
+        if changed(X) || changed(Y) || changed(ROT) {
+            let [x, y, rot] = [X, Y, ROT].map(|i| self.numbers[i].or(current.numbers[i]).unwrap());
+            rigid_body.set_position(np::math::Isometry::new(Vector::new(x, y), rot));
+        }
+        if changed(X_VEL) || changed(Y_VEL) {
+            let [x_vel, y_vel] =
+                [X_VEL, Y_VEL].map(|i| self.numbers[i].or(current.numbers[i]).unwrap());
+            rigid_body.set_linear_velocity(Vector::new(x_vel, y_vel));
+        }
+        if changed(ROTVEL) {
+            rigid_body.set_angular_velocity(number(ROTVEL));
+        }
+
+        if changed(MASS) {
+            rigid_body.set_mass(number(MASS));
+            wake_up = true;
+        }
+
+        if written & STATUS_BIT != 0 && self.status != current.status {
+            rigid_body.set_status(self.status);
+            wake_up = true;
+        }
+
+        if changed(FRICTION) || changed(ELASTICITY) {
+            let material = collider
+                .material_mut()
+                .downcast_mut::<BasicMaterial<f32>>()
+                .unwrap();
+            if changed(FRICTION) {
+                material.friction = number(FRICTION);
+            }
+            if changed(ELASTICITY) {
+                material.restitution = number(ELASTICITY);
+            }
+            wake_up = true;
+        }
+
+        if written & COLLISION_BIT != 0 && self.collision != current.collision {
+            collider.set_query_type(if self.collision {
+                GeometricQueryType::Contacts(
+                    collider.margin() + LINEAR_PREDICTION,
+                    ANGULAR_PREDICTION,
+                )
+            } else {
+                GeometricQueryType::Proximity(LINEAR_PREDICTION)
+            });
+            wake_up = true;
+        }
+
+        let new_shape = if current.numbers[R].is_some() && changed(R) {
+            Some(ShapeHandle::new(Ball::new(number(R))))
+        } else if current.numbers[W].is_some() && (changed(W) || changed(H)) {
+            let half_extents = Vector::new(
+                self.numbers[W].or(current.numbers[W]).unwrap(),
+                self.numbers[H].or(current.numbers[H]).unwrap(),
+            );
+            Some(ShapeHandle::new(Cuboid::new(half_extents)))
+        } else {
+            None
+        };
+        if let Some(new_shape) = new_shape {
+            collider.set_shape(new_shape);
+            wake_up = true;
+        }
+
+        if wake_up {
+            rigid_body.activate();
+        }
+        Ok(())
+    }
+}
+
+/// The obj passed to update functions. It's made once for each body and reused every frame,
+/// so reading and writing fields doesn't make any tables. Anything else that's stored on it,
+/// like obj.color, is kept in a table in its user value.
+pub struct BodyProxy {
+    pub entity: Entity,
+    pub name: Option<String>,
+    fields: BodyFields,
+    /// a bit for each field that was written since the fields were last applied to the body
+    written: u32,
+}
+
+impl BodyProxy {
+    pub fn new(entity: Entity, rigid_body: &RigidBody, collider: &NpCollider) -> Self {
+        BodyProxy {
+            entity,
+            name: None,
+            fields: BodyFields::from_body(rigid_body, collider),
+            written: 0,
+        }
+    }
+
+    /// Reads the body into the fields that haven't been written. Fields that were
+    /// written outside of the update function, e.g. in update(), are applied next.
+    pub fn refresh(&mut self, rigid_body: &RigidBody, collider: &NpCollider) {
+        let current = BodyFields::from_body(rigid_body, collider);
+        (0..NUMBER_FIELDS.len()).for_each(|i| {
+            if self.written & (1 << i) == 0 {
+                self.fields.numbers[i] = current.numbers[i];
+            }
+        });
+        if self.written & STATUS_BIT == 0 {
+            self.fields.status = current.status;
+        }
+        if self.written & COLLISION_BIT == 0 {
+            self.fields.collision = current.collision;
+        }
+    }
+
+    /// applies the fields that were written to the body
+    pub fn apply(
+        &mut self,
+        rigid_body: &mut RigidBody,
+        collider: &mut NpCollider,
+    ) -> rlua::Result<()> {
+        let written = std::mem::take(&mut self.written);
+        self.fields.apply(written, rigid_body, collider)
+    }
+
+    fn get<'lua>(
+        &self,
+        lua_ctx: rlua::Context<'lua>,
+        key: &str,
+    ) -> rlua::Result<Option<rlua::Value<'lua>>> {
+        if let Some(i) = NUMBER_FIELDS.iter().position(|name| *name == key) {
+            return Ok(Some(match self.fields.numbers[i] {
+                Some(number) => rlua::Value::Number(f64::from(number)),
+                None => rlua::Value::Nil,
+            }));
+        }
+        let value = match key {
+            "status" => {
+                rlua::Value::String(lua_ctx.create_string(status_name(self.fields.status))?)
+            }
+            "collision" => rlua::Value::Boolean(self.fields.collision),
+            "name" => match &self.name {
+                Some(name) => rlua::Value::String(lua_ctx.create_string(name)?),
+                None => rlua::Value::Nil,
+            },
+            "data" => rlua::Value::Table(lua_data(lua_ctx, self.entity)?),
+            _ => return Ok(None),
+        };
+        Ok(Some(value))
+    }
+
+    /// returns false if it isn't one of the body's fields
+    fn set<'lua>(
+        &mut self,
+        lua_ctx: rlua::Context<'lua>,
+        key: &str,
+        value: rlua::Value<'lua>,
+    ) -> rlua::Result<bool> {
+        let error = |message: String| Err(rlua::Error::RuntimeError(message));
+        if let Some(i) = NUMBER_FIELDS.iter().position(|name| *name == key) {
+            self.fields.numbers[i] = Some(checked_number(key, value)?);
+            self.written |= 1 << i;
+            return Ok(true);
+        }
+
+        match (key, value) {
+            ("status", rlua::Value::String(status)) => {
+                self.fields.status = status_from_name(status.to_str()?)?;
+                self.written |= STATUS_BIT;
+            }
+            ("collision", rlua::Value::Boolean(collision)) => {
+                self.fields.collision = collision;
+                self.written |= COLLISION_BIT;
+            }
+            ("status", _) => return error("obj.status has to be a string".to_string()),
+            ("collision", _) => return error("obj.collision has to be true or false".to_string()),
+            ("name", _) => return error("obj.name can't be changed".to_string()),
+            ("data", rlua::Value::Table(data)) => set_lua_data(lua_ctx, self.entity, Some(data))?,
+            ("data", rlua::Value::Nil) => set_lua_data(lua_ctx, self.entity, None)?,
+            ("data", _) => return error("obj.data has to be a table".to_string()),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+impl rlua::UserData for BodyProxy {
+    fn add_methods<'lua, M: rlua::UserDataMethods<'lua, Self>>(methods: &mut M) {
+        methods.add_meta_function(
+            rlua::MetaMethod::Index,
+            |lua_ctx, (proxy, key): (rlua::AnyUserData, rlua::String)| {
+                if let Some(value) = proxy.borrow::<BodyProxy>()?.get(lua_ctx, key.to_str()?)? {
+                    return Ok(value);
+                }
+                proxy.get_user_value::<rlua::Table>()?.raw_get(key)
+            },
+        );
+        methods.add_meta_function(
+            rlua::MetaMethod::NewIndex,
+            |lua_ctx, (proxy, key, value): (rlua::AnyUserData, rlua::String, rlua::Value)| {
+                let is_field =
+                    proxy
+                        .borrow_mut::<BodyProxy>()?
+                        .set(lua_ctx, key.to_str()?, value.clone())?;
+                if !is_field {
+                    proxy.get_user_value::<rlua::Table>()?.raw_set(key, value)?;
+                }
+                Ok(())
+            },
+        );
+    }
+}
+
+/// for update functions that return a table of their own, only the fields in it are changed
+pub fn apply_table(
+    table: &rlua::Table,
+    rigid_body: &mut RigidBody,
+    collider: &mut NpCollider,
+) -> rlua::Result<()> {
+    let current = BodyFields::from_body(rigid_body, collider);
+    let (fields, written) = BodyFields::from_table(table, current)?;
+    fields.apply(written, rigid_body, collider)
+}
+
+/// A number field's value after checking it against the same ranges as add_shape(),
+/// so that e.g. obj.elasticity = 5 or a NaN doesn't make it to nphysics
+fn checked_number(name: &str, value: rlua::Value) -> rlua::Result<f32> {
+    if let Some(field) = find_field(SHAPE_FIELDS, name) {
+        field.check(&value)?;
+    }
+    let number = match value {
+        rlua::Value::Number(n) => n as f32,
+        rlua::Value::Integer(i) => i as f32,
+        _ => {
+            return Err(rlua::Error::RuntimeError(format!(
+                "obj.{} has to be a number",
+                name
+            )))
+        }
+    };
+    // numbers that fit in Lua can still be too big for f32
+    if number.is_finite() {
+        Ok(number)
+    } else {
+        Err(rlua::Error::RuntimeError(format!(
+            "obj.{} is too big, it's {}",
+            name, number
+        )))
+    }
+}
+
+fn status_name(status: BodyStatus) -> &'static str {
+    match status {
+        BodyStatus::Static => "static",
+        BodyStatus::Kinematic => "kinematic",
+        _ => "dynamic",
+    }
+}
+
+fn status_from_name(name: &str) -> rlua::Result<BodyStatus> {
+    match name.to_lowercase().as_str() {
+        "static" => Ok(BodyStatus::Static),
+        "kinematic" => Ok(BodyStatus::Kinematic),
+        "dynamic" => Ok(BodyStatus::Dynamic),
+        _ => Err(rlua::Error::RuntimeError(format!(
+            "invalid status \"{}\"",
+            name
+        ))),
+    }
+}
@@ -0,0 +1,174 @@
+const MAX_ENTRIES: usize = 500;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ConsoleEntryKind {
+    Print,
+    Error,
+    /// code entered in the REPL
+    Input,
+    /// what the code entered in the REPL returned
+    Result,
+}
+
+#[derive(Clone, Debug)]
+pub struct ConsoleEntry {
+    pub kind: ConsoleEntryKind,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    /// the name of the object whose update function or shape table caused the error
+    pub object: Option<String>,
+    /// how many times in a row the same thing was logged
+    pub count: usize,
+}
+
+impl ConsoleEntry {
+    pub fn new(kind: ConsoleEntryKind, message: String) -> Self {
+        ConsoleEntry {
+            kind,
+            message,
+            file: None,
+            line: None,
+            object: None,
+            count: 1,
+        }
+    }
+
+    pub fn print(message: String, location: &str) -> Self {
+        let (file, line, _) = split_location(location);
+        ConsoleEntry {
+            kind: ConsoleEntryKind::Print,
+            message,
+            file,
+            line,
+            object: None,
+            count: 1,
+        }
+    }
+
+    pub fn error(error: &rlua::Error, object: Option<String>) -> Self {
+        let (file, line, message) = error_location(error);
+        ConsoleEntry {
+            kind: ConsoleEntryKind::Error,
+            message,
+            file,
+            line,
+            object,
+            count: 1,
+        }
+    }
+
+    fn same_as(&self, other: &ConsoleEntry) -> bool {
+        self.kind == other.kind
+            && self.message == other.message
+            && self.file == other.file
+            && self.line == other.line
+            && self.object == other.object
+    }
+
+    /// e.g. "lua/init.lua:12 (ball)"
+    pub fn source(&self) -> String {
+        let location = match (&self.file, self.line) {
+            (Some(file), Some(line)) => format!("{}:{}", file, line),
+            (Some(file), None) => file.clone(),
+            _ => String::new(),
+        };
+        match &self.object {
+            Some(object) if location.is_empty() => format!("({})", object),
+            Some(object) => format!("{} ({})", location, object),
+            None => location,
+        }
+    }
+}
+
+/// Lua errors and print() output, shown in the Lua console window
+#[derive(Clone, Default)]
+pub struct LuaConsole {
+    pub entries: Vec<ConsoleEntry>,
+    /// set when an error is logged so that the window can be opened
+    pub unseen_error: bool,
+}
+
+impl LuaConsole {
+    pub fn log(&mut self, entry: ConsoleEntry) {
+        if entry.kind == ConsoleEntryKind::Error {
+            self.unseen_error = true;
+        }
+
+        // an error in an update function is logged every frame, so repeats are counted instead
+        if let Some(last) = self.entries.last_mut() {
+            if last.same_as(&entry) {
+                last.count += 1;
+                return;
+            }
+        }
+
+        self.entries.push(entry);
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+    }
+
+    pub fn log_error(&mut self, error: &rlua::Error, object: Option<String>) {
+        self.log(ConsoleEntry::error(error, object));
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+/// Returns the file, line, and message of an error. Errors from Rust callbacks
+/// like spawn() get their location from the traceback.
+fn error_location(error: &rlua::Error) -> (Option<String>, Option<u32>, String) {
+    match error {
+        rlua::Error::RuntimeError(message) | rlua::Error::SyntaxError { message, .. } => {
+            let (file, line, message) = split_location(message);
+            (file, line, message.to_string())
+        }
+        rlua::Error::CallbackError { traceback, cause } => {
+            let (file, line, message) = error_location(cause);
+            if file.is_some() {
+                return (file, line, message);
+            }
+
+            // the first frame that isn't a Rust function is the Lua code that called it
+            traceback
+                .lines()
+                .map(split_location)
+                .find(|(file, _, _)| file.is_some())
+                .map_or((None, None, message.clone()), |(file, line, _)| {
+                    (file, line, message)
+                })
+        }
+        rlua::Error::MemoryError(_) => (
+            None,
+            None,
+            "ran out of memory, the limit can be raised in the Settings menu or with set_limits{}"
+                .to_string(),
+        ),
+        _ => (None, None, error.to_string()),
+    }
+}
+
+/// Lua puts where things happened at the start of messages,
+/// like "lua/init.lua:12: attempt to call a nil value"
+fn split_location(message: &str) -> (Option<String>, Option<u32>, &str) {
+    let first_line = message.lines().next().unwrap_or("");
+
+    // file names can have colons in them, so look for the first :<line>: instead
+    first_line
+        .match_indices(':')
+        .find_map(|(i, _)| {
+            let rest = &first_line[i + 1..];
+            let end = rest.find(':')?;
+            let line = rest[..end].parse::<u32>().ok()?;
+            let file = first_line[..i].trim();
+            Some((
+                Some(file.to_string()),
+                Some(line),
+                message[i + 1 + end + 1..].trim(),
+            ))
+        })
+        .unwrap_or((None, None, message.trim()))
+}
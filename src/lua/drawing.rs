@@ -0,0 +1,119 @@
+use ggez::graphics;
+
+use super::{get_required, lua_error};
+use crate::Vector;
+
+const DEFAULT_LINE_WIDTH: f32 = 0.05;
+const DEFAULT_TEXT_SIZE: f32 = 0.5;
+
+#[derive(Clone, Debug)]
+pub enum DrawingShape {
+    Line(Vec<Vector>),
+    Arrow(Vector, Vector),
+    Circle(Vector, f32),
+    Polygon(Vec<Vector>),
+    /// the position is the top left corner and the size is the height of a line
+    Text(Vector, String, f32),
+}
+
+/// Something drawn with draw_line(), draw_arrow(), draw_circle(), draw_polygon(),
+/// or draw_text(). They only last until the next update().
+#[derive(Clone, Debug)]
+pub struct Drawing {
+    pub shape: DrawingShape,
+    pub color: graphics::Color,
+    /// how wide the lines are, or 0 if it's filled in
+    pub width: f32,
+    /// whether it's in screen coordinates, which stay put when the camera moves
+    pub screen: bool,
+}
+
+#[derive(Default)]
+pub struct LuaDrawings(pub Vec<Drawing>);
+
+impl Drawing {
+    /// drawings are tables like {x1 = 0, y1 = 0, x2 = 5, y2 = 5, color = {r = 255, g = 0, b = 0}},
+    /// with their kind set by the prelude function that made them
+    pub fn from_lua(drawing: &rlua::Table) -> rlua::Result<Self> {
+        let kind: String = get_required(drawing, "kind")?;
+        let point = |x: &str, y: &str| -> rlua::Result<Vector> {
+            Ok(Vector::new(
+                get_required(drawing, x)?,
+                get_required(drawing, y)?,
+            ))
+        };
+
+        let shape = match kind.as_str() {
+            "line" => match drawing.get::<_, Option<rlua::Table>>("points")? {
+                Some(points) => DrawingShape::Line(points_from_lua(&points, 2)?),
+                None => DrawingShape::Line(vec![point("x1", "y1")?, point("x2", "y2")?]),
+            },
+            "arrow" => DrawingShape::Arrow(point("x1", "y1")?, point("x2", "y2")?),
+            "circle" => {
+                let r: f32 = get_required(drawing, "r")?;
+                if r <= 0.0 {
+                    return Err(lua_error("r has to be positive"));
+                }
+                DrawingShape::Circle(point("x", "y")?, r)
+            }
+            "polygon" => {
+                let points: rlua::Table = get_required(drawing, "points")?;
+                DrawingShape::Polygon(points_from_lua(&points, 3)?)
+            }
+            "text" => {
+                let text: String = get_required(drawing, "text")?;
+                let size = drawing
+                    .get::<_, Option<f32>>("size")?
+                    .unwrap_or(DEFAULT_TEXT_SIZE);
+                if size <= 0.0 {
+                    return Err(lua_error("size has to be positive"));
+                }
+                DrawingShape::Text(point("x", "y")?, text, size)
+            }
+            _ => return Err(lua_error(format!("can't draw a {}", kind))),
+        };
+
+        let fill = drawing.get::<_, Option<bool>>("fill")?.unwrap_or(false);
+        let width = match shape {
+            DrawingShape::Circle(..) | DrawingShape::Polygon(_) if fill => 0.0,
+            _ => {
+                let width = drawing
+                    .get::<_, Option<f32>>("width")?
+                    .unwrap_or(DEFAULT_LINE_WIDTH);
+                if width <= 0.0 {
+                    return Err(lua_error("width has to be positive"));
+                }
+                width
+            }
+        };
+
+        Ok(Drawing {
+            shape,
+            color: super::color_from_lua(drawing)?,
+            width,
+            screen: drawing.get::<_, Option<bool>>("screen")?.unwrap_or(false),
+        })
+    }
+}
+
+/// points are a list like {{x = 0, y = 0}, {x = 1, y = 2}}, which vectors from
+/// the physics module also work as
+fn points_from_lua(points: &rlua::Table, min_len: usize) -> rlua::Result<Vec<Vector>> {
+    let points = points
+        .clone()
+        .sequence_values::<rlua::Table>()
+        .map(|point| {
+            let point = point?;
+            Ok(Vector::new(
+                get_required(&point, "x")?,
+                get_required(&point, "y")?,
+            ))
+        })
+        .collect::<rlua::Result<Vec<Vector>>>()?;
+
+    if points.len() < min_len {
+        Err(lua_error(format!("needs at least {} points", min_len)))
+    } else {
+        Ok(points)
+    }
+}
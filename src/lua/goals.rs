@@ -0,0 +1,182 @@
+use std::io::Write;
+
+use crate::main_state::MainState;
+use crate::resources::{CurrentScene, CurrentSceneInfo, LuaRngRes, SimTime};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GoalStatus {
+    Pending,
+    /// when it passed or failed in simulated seconds
+    Passed(f32),
+    Failed(f32),
+}
+
+impl GoalStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            GoalStatus::Pending => "pending",
+            GoalStatus::Passed(_) => "passed",
+            GoalStatus::Failed(_) => "failed",
+        }
+    }
+
+    pub fn time(&self) -> Option<f32> {
+        match *self {
+            GoalStatus::Pending => None,
+            GoalStatus::Passed(time) | GoalStatus::Failed(time) => Some(time),
+        }
+    }
+}
+
+/// Made with goal{} in a scene. It passes once check() returns true and fails if fail()
+/// returns true or the deadline goes by first, after that it isn't checked anymore.
+pub struct Goal {
+    pub description: String,
+    pub check: rlua::RegistryKey,
+    pub fail: Option<rlua::RegistryKey>,
+    /// in simulated seconds since the scene was loaded
+    pub deadline: Option<f32>,
+    pub status: GoalStatus,
+}
+
+#[derive(Default)]
+pub struct Goals(pub Vec<Goal>);
+
+/// where the Goals window exports results to and the name that goes in them
+#[derive(Clone)]
+pub struct GoalResultsExport {
+    pub filename: String,
+    pub student: String,
+}
+
+impl Default for GoalResultsExport {
+    fn default() -> Self {
+        GoalResultsExport {
+            filename: "results.csv".to_string(),
+            student: String::new(),
+        }
+    }
+}
+
+impl<'a, 'b> MainState<'a, 'b> {
+    /// Saves the goals and whether they passed as JSON if the filename ends in .json,
+    /// and CSV otherwise
+    pub fn export_goal_results(&mut self) {
+        let export = GoalResultsExport::clone(&self.world.fetch::<GoalResultsExport>());
+        let result = if export.filename.to_lowercase().ends_with(".json") {
+            self.goal_results_json(&export.student).and_then(|json| {
+                std::fs::File::create(&export.filename)?.write_all(json.as_bytes())
+            })
+        } else {
+            self.write_goal_results_csv(&export)
+        };
+
+        match result {
+            Ok(()) => self.notify(format!("Saved results to {}", export.filename), false),
+            Err(e) => self.notify(
+                format!("Couldn't save results to {}: {}", export.filename, e),
+                true,
+            ),
+        }
+    }
+
+    fn write_goal_results_csv(&self, export: &GoalResultsExport) -> std::io::Result<()> {
+        let scene = self
+            .world
+            .fetch::<CurrentScene>()
+            .0
+            .clone()
+            .unwrap_or_default();
+        let seed = self
+            .world
+            .fetch::<LuaRngRes>()
+            .lock()
+            .unwrap()
+            .seed()
+            .to_string();
+        let goals = self.world.fetch::<Goals>();
+
+        let mut writer = csv::Writer::from_path(&export.filename)?;
+        writer.write_record(["scene", "student", "seed", "goal", "status", "time"])?;
+        goals.0.iter().try_for_each(|goal| {
+            let time = goal
+                .status
+                .time()
+                .map(|time| format!("{:.3}", time))
+                .unwrap_or_default();
+            writer.write_record([
+                scene.as_str(),
+                export.student.as_str(),
+                seed.as_str(),
+                goal.description.as_str(),
+                goal.status.name(),
+                time.as_str(),
+            ])
+        })?;
+        writer.flush()
+    }
+
+    fn goal_results_json(&self, student: &str) -> std::io::Result<String> {
+        let scene = self
+            .world
+            .fetch::<CurrentScene>()
+            .0
+            .clone()
+            .unwrap_or_default();
+        let title = self
+            .world
+            .fetch::<CurrentSceneInfo>()
+            .0
+            .as_ref()
+            .and_then(|info| info.title.clone())
+            .unwrap_or_default();
+        let sim_time = self.world.fetch::<SimTime>().0;
+        let seed = self.world.fetch::<LuaRngRes>().lock().unwrap().seed();
+        let goals = self.world.fetch::<Goals>();
+
+        let goal_strings = goals
+            .0
+            .iter()
+            .map(|goal| {
+                let time = goal
+                    .status
+                    .time()
+                    .map(|time| format!("{:.3}", time))
+                    .unwrap_or_else(|| "null".to_string());
+                format!(
+                    "\t\t{{\"description\": {}, \"status\": \"{}\", \"time\": {}}}",
+                    json_string(&goal.description),
+                    goal.status.name(),
+                    time
+                )
+            })
+            .collect::<Vec<String>>();
+
+        Ok(format!(
+            "{{\n\t\"scene\": {},\n\t\"title\": {},\n\t\"student\": {},\n\t\"seed\": {},\n\t\"time\": {:.3},\n\t\"goals\": [\n{}\n\t]\n}}\n",
+            json_string(&scene),
+            json_string(&title),
+            json_string(student),
+            // a string, since JSON readers often turn numbers into doubles
+            json_string(&seed.to_string()),
+            sim_time,
+            goal_strings.join(",\n")
+        ))
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    s.chars().for_each(|c| match c {
+        '"' => escaped.push_str("\\\""),
+        '\\' => escaped.push_str("\\\\"),
+        '\n' => escaped.push_str("\\n"),
+        '\r' => escaped.push_str("\\r"),
+        '\t' => escaped.push_str("\\t"),
+        c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+        c => escaped.push(c),
+    });
+    escaped.push('"');
+    escaped
+}
@@ -0,0 +1,70 @@
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LabControlKind {
+    Slider {
+        min: f32,
+        max: f32,
+        /// values are rounded to a multiple of this if it's set
+        step: Option<f32>,
+        value: f32,
+    },
+    Checkbox {
+        value: bool,
+    },
+    Button,
+}
+
+/// a slider, checkbox, or button in the Lab Controls window, made with param(),
+/// checkbox(), or button() in a scene
+pub struct LabControl {
+    pub name: String,
+    pub kind: LabControlKind,
+    /// on_change for sliders and checkboxes, on_click for buttons
+    pub callback: Option<rlua::RegistryKey>,
+    /// whether the scene is run again when it's changed
+    pub rerun: bool,
+}
+
+impl LabControlKind {
+    /// the value that's in PARAMS and passed to on_change, nothing for buttons
+    pub fn lua_value<'lua>(&self) -> rlua::Value<'lua> {
+        match *self {
+            LabControlKind::Slider { value, .. } => rlua::Value::Number(f64::from(value)),
+            LabControlKind::Checkbox { value } => rlua::Value::Boolean(value),
+            LabControlKind::Button => rlua::Value::Nil,
+        }
+    }
+}
+
+/// The controls of the current scene. Their values are kept when the same
+/// scene is loaded again, e.g. when a control reruns it.
+#[derive(Default)]
+pub struct LabControls {
+    pub controls: Vec<LabControl>,
+    scene: Option<PathBuf>,
+}
+
+impl LabControls {
+    /// Clears the controls before a scene is run, returning the values that it should start with
+    pub fn start_scene(&mut self, scene: &Path) -> Vec<(String, LabControlKind)> {
+        let controls = std::mem::take(&mut self.controls);
+        if self.scene.as_deref() == Some(scene) {
+            controls
+                .into_iter()
+                .map(|control| (control.name, control.kind))
+                .collect()
+        } else {
+            self.scene = Some(scene.to_path_buf());
+            Vec::new()
+        }
+    }
+
+    /// adds a control, replacing any with the same name
+    pub fn add(&mut self, control: LabControl) {
+        match self.controls.iter_mut().find(|c| c.name == control.name) {
+            Some(existing) => *existing = control,
+            None => self.controls.push(control),
+        }
+    }
+}
@@ -0,0 +1,294 @@
+use specs::prelude::*;
+use specs::storage::GenericReadStorage;
+
+use std::collections::HashMap;
+
+use crate::components::{Color, PhysicsBody};
+use crate::resources::LuaBridgeRes;
+use crate::{BodySet, RigidBody, Vector};
+
+use np::math::{Force, ForceType, Isometry};
+use np::object::Body;
+use nphysics2d as np;
+
+/// What Lua sees of a body. It's refreshed before Lua code runs
+/// and changed right away by setters so scripts read their own writes.
+#[derive(Copy, Clone, Debug)]
+pub struct BodySnapshot {
+    pub pos: Vector,
+    pub vel: Vector,
+    pub mass: f32,
+    /// 0-255 like the color tables passed to update functions
+    pub color: (f32, f32, f32),
+}
+
+impl BodySnapshot {
+    pub fn new(rigid_body: &RigidBody, color: ggez::graphics::Color) -> Self {
+        BodySnapshot {
+            pos: rigid_body.position().translation.vector,
+            vel: rigid_body.velocity().linear,
+            mass: rigid_body.augmented_mass().linear,
+            color: (color.r * 255.0, color.g * 255.0, color.b * 255.0),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub enum EntityCommand {
+    SetPos(Entity, Vector),
+    SetVel(Entity, Vector),
+    ApplyForce(Entity, Vector),
+    ApplyImpulse(Entity, Vector),
+    SetColor(Entity, (f32, f32, f32)),
+    SetMass(Entity, f32),
+}
+
+/// Lua can't borrow the specs world, so LuaEntity methods read from
+/// snapshots of the bodies and queue up writes which are applied
+/// after the Lua code is done running.
+#[derive(Default)]
+pub struct LuaBridge {
+    pub snapshots: HashMap<Entity, BodySnapshot>,
+    pub commands: Vec<EntityCommand>,
+    /// deleted with MainState::delete_entity() after lua_update()
+    pub destroyed: Vec<Entity>,
+}
+
+impl LuaBridge {
+    /// handles to the entity stop working right away
+    pub fn destroy(&mut self, entity: Entity) {
+        self.snapshots.remove(&entity);
+        self.destroyed.push(entity);
+    }
+
+    pub fn snapshot_bodies(
+        &mut self,
+        body_set: &BodySet,
+        physics_bodies: &ReadStorage<PhysicsBody>,
+        colors: &impl GenericReadStorage<Component = Color>,
+        entities: &Entities,
+    ) {
+        self.snapshots.clear();
+        (physics_bodies, entities)
+            .join()
+            .for_each(|(physics_body, entity)| {
+                if let Some(rigid_body) = body_set.rigid_body(physics_body.body_handle) {
+                    let color = colors
+                        .get(entity)
+                        .map_or(ggez::graphics::WHITE, |Color(color)| *color);
+                    self.snapshots
+                        .insert(entity, BodySnapshot::new(rigid_body, color));
+                }
+            });
+    }
+
+    /// applies every queued write, writes to bodies that were deleted in the meantime are dropped
+    pub fn apply_commands(
+        &mut self,
+        body_set: &mut BodySet,
+        physics_bodies: &ReadStorage<PhysicsBody>,
+        colors: &mut WriteStorage<Color>,
+    ) {
+        self.commands.drain(..).for_each(|command| match command {
+            EntityCommand::SetColor(entity, (r, g, b)) => {
+                if let Some(Color(color)) = colors.get_mut(entity) {
+                    color.r = r / 255.0;
+                    color.g = g / 255.0;
+                    color.b = b / 255.0;
+                }
+            }
+            EntityCommand::SetPos(entity, _)
+            | EntityCommand::SetVel(entity, _)
+            | EntityCommand::ApplyForce(entity, _)
+            | EntityCommand::ApplyImpulse(entity, _)
+            | EntityCommand::SetMass(entity, _) => {
+                let rigid_body = match physics_bodies
+                    .get(entity)
+                    .and_then(|physics_body| body_set.rigid_body_mut(physics_body.body_handle))
+                {
+                    Some(rigid_body) => rigid_body,
+                    None => return,
+                };
+
+                match command {
+                    EntityCommand::SetPos(_, pos) => {
+                        let rotation = rigid_body.position().rotation.angle();
+                        rigid_body.set_position(Isometry::new(pos, rotation));
+                    }
+                    EntityCommand::SetVel(_, vel) => rigid_body.set_linear_velocity(vel),
+                    EntityCommand::ApplyForce(_, force) => {
+                        rigid_body.apply_force(0, &Force::linear(force), ForceType::Force, true)
+                    }
+                    EntityCommand::ApplyImpulse(_, impulse) => {
+                        rigid_body.apply_force(0, &Force::linear(impulse), ForceType::Impulse, true)
+                    }
+                    EntityCommand::SetMass(_, mass) => rigid_body.set_mass(mass),
+                    EntityCommand::SetColor(..) => unreachable!(),
+                }
+            }
+        });
+    }
+}
+
+/// the named registry value that obj.data tables are kept in
+pub const OBJECT_DATA: &str = "object_data";
+
+/// The data tables are keyed by the entity's generation as well as its id
+/// so that an id that's reused doesn't get the deleted object's data.
+fn data_key(entity: Entity) -> i64 {
+    (i64::from(entity.gen().id()) << 32) | i64::from(entity.id())
+}
+
+/// an object's obj.data, or None if it doesn't have one yet
+pub fn get_lua_data(lua_ctx: rlua::Context, entity: Entity) -> rlua::Result<Option<rlua::Table>> {
+    let object_data: rlua::Table = lua_ctx.named_registry_value(OBJECT_DATA)?;
+    object_data.raw_get(data_key(entity))
+}
+
+/// an object's obj.data, made the first time it's used
+pub fn lua_data(lua_ctx: rlua::Context, entity: Entity) -> rlua::Result<rlua::Table> {
+    match get_lua_data(lua_ctx, entity)? {
+        Some(data) => Ok(data),
+        None => {
+            let data = lua_ctx.create_table()?;
+            set_lua_data(lua_ctx, entity, Some(data.clone()))?;
+            Ok(data)
+        }
+    }
+}
+
+/// replaces an object's obj.data, None removes it
+pub fn set_lua_data<'lua>(
+    lua_ctx: rlua::Context<'lua>,
+    entity: Entity,
+    data: Option<rlua::Table<'lua>>,
+) -> rlua::Result<()> {
+    let object_data: rlua::Table = lua_ctx.named_registry_value(OBJECT_DATA)?;
+    object_data.raw_set(data_key(entity), data)
+}
+
+/// A handle to a body that Lua code can hold on to, e.g. the entries of OBJECTS
+#[derive(Clone)]
+pub struct LuaEntity(pub Entity, pub LuaBridgeRes);
+
+impl LuaEntity {
+    fn snapshot(&self) -> rlua::Result<BodySnapshot> {
+        self.1
+            .lock()
+            .unwrap()
+            .snapshots
+            .get(&self.0)
+            .copied()
+            .ok_or_else(|| rlua::Error::RuntimeError("object no longer exists".to_string()))
+    }
+
+    /// queues the command and updates the snapshot so that getters see the change
+    fn write(
+        &self,
+        command: EntityCommand,
+        update: impl FnOnce(&mut BodySnapshot),
+    ) -> rlua::Result<()> {
+        let mut bridge = self.1.lock().unwrap();
+        match bridge.snapshots.get_mut(&self.0) {
+            Some(snapshot) => update(snapshot),
+            None => {
+                return Err(rlua::Error::RuntimeError(
+                    "object no longer exists".to_string(),
+                ))
+            }
+        }
+        bridge.commands.push(command);
+        Ok(())
+    }
+}
+
+impl rlua::UserData for LuaEntity {
+    fn add_methods<'lua, M: rlua::UserDataMethods<'lua, Self>>(methods: &mut M) {
+        // obj.data is the only field, everything else is a method
+        methods.add_meta_method(
+            rlua::MetaMethod::Index,
+            |lua_ctx, this, key: String| match key.as_str() {
+                "data" => {
+                    this.snapshot()?;
+                    Ok(rlua::Value::Table(lua_data(lua_ctx, this.0)?))
+                }
+                _ => Ok(rlua::Value::Nil),
+            },
+        );
+        methods.add_meta_method(
+            rlua::MetaMethod::NewIndex,
+            |lua_ctx, this, (key, value): (String, rlua::Value)| match key.as_str() {
+                "data" => {
+                    this.snapshot()?;
+                    match value {
+                        rlua::Value::Table(data) => set_lua_data(lua_ctx, this.0, Some(data)),
+                        rlua::Value::Nil => set_lua_data(lua_ctx, this.0, None),
+                        _ => Err(rlua::Error::RuntimeError(
+                            "obj.data has to be a table".to_string(),
+                        )),
+                    }
+                }
+                _ => Err(rlua::Error::RuntimeError(format!(
+                    "objects can't have a field named {}, put it in obj.data instead",
+                    key
+                ))),
+            },
+        );
+
+        methods.add_method("id", |_, this, _: ()| Ok(this.0.id()));
+
+        methods.add_method("get_pos", |_, this, _: ()| {
+            let pos = this.snapshot()?.pos;
+            Ok((pos.x, pos.y))
+        });
+        methods.add_method("set_pos", |_, this, (x, y): (f32, f32)| {
+            let pos = Vector::new(x, y);
+            this.write(EntityCommand::SetPos(this.0, pos), |snapshot| {
+                snapshot.pos = pos
+            })
+        });
+
+        methods.add_method("get_vel", |_, this, _: ()| {
+            let vel = this.snapshot()?.vel;
+            Ok((vel.x, vel.y))
+        });
+        methods.add_method("set_vel", |_, this, (x, y): (f32, f32)| {
+            let vel = Vector::new(x, y);
+            this.write(EntityCommand::SetVel(this.0, vel), |snapshot| {
+                snapshot.vel = vel
+            })
+        });
+
+        // forces last for the next physics step, impulses change the velocity right away
+        methods.add_method("apply_force", |_, this, (x, y): (f32, f32)| {
+            this.write(EntityCommand::ApplyForce(this.0, Vector::new(x, y)), |_| {})
+        });
+        methods.add_method("apply_impulse", |_, this, (x, y): (f32, f32)| {
+            let impulse = Vector::new(x, y);
+            this.write(EntityCommand::ApplyImpulse(this.0, impulse), |snapshot| {
+                if snapshot.mass > 0.0 {
+                    snapshot.vel += impulse / snapshot.mass;
+                }
+            })
+        });
+
+        methods.add_method("get_color", |_, this, _: ()| Ok(this.snapshot()?.color));
+        methods.add_method("set_color", |_, this, color: (f32, f32, f32)| {
+            this.write(EntityCommand::SetColor(this.0, color), |snapshot| {
+                snapshot.color = color
+            })
+        });
+
+        methods.add_method("get_mass", |_, this, _: ()| Ok(this.snapshot()?.mass));
+        methods.add_method("set_mass", |_, this, mass: f32| {
+            if mass < 0.0 {
+                return Err(rlua::Error::RuntimeError(
+                    "mass can't be negative".to_string(),
+                ));
+            }
+            this.write(EntityCommand::SetMass(this.0, mass), |snapshot| {
+                snapshot.mass = mass
+            })
+        });
+    }
+}
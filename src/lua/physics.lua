@@ -0,0 +1,281 @@
+-- The built in physics module, loaded with local physics = require("physics")
+-- Remember that y goes down in the simulation, so GRAVITY is positive.
+local physics = {}
+
+----------------------------------------------------------------
+-- 2D vectors
+----------------------------------------------------------------
+
+local Vector = {}
+Vector.__index = Vector
+physics.Vector = Vector
+
+-- physics.vec(3, 4) or physics.vec{x = 3, y = 4}
+local function vec(x, y)
+    if type(x) == "table" then
+        x, y = x.x, x.y
+    end
+    return setmetatable({x = x or 0, y = y or 0}, Vector)
+end
+physics.vec = vec
+Vector.new = vec
+
+function physics.is_vec(v)
+    return getmetatable(v) == Vector
+end
+
+-- a vector pointing at an angle in radians
+function Vector.from_angle(angle, length)
+    length = length or 1
+    return vec(math.cos(angle) * length, math.sin(angle) * length)
+end
+
+function Vector.__add(a, b)
+    return vec(a.x + b.x, a.y + b.y)
+end
+
+function Vector.__sub(a, b)
+    return vec(a.x - b.x, a.y - b.y)
+end
+
+-- scales by a number on either side
+function Vector.__mul(a, b)
+    if type(a) == "number" then
+        return vec(b.x * a, b.y * a)
+    elseif type(b) == "number" then
+        return vec(a.x * b, a.y * b)
+    end
+    error("vectors can only be multiplied by numbers, use :dot() or :cross()", 2)
+end
+
+function Vector.__div(a, f)
+    return vec(a.x / f, a.y / f)
+end
+
+function Vector.__unm(a)
+    return vec(-a.x, -a.y)
+end
+
+function Vector.__eq(a, b)
+    return a.x == b.x and a.y == b.y
+end
+
+function Vector.__tostring(a)
+    return string.format("(%g, %g)", a.x, a.y)
+end
+
+function Vector:magnitude()
+    return math.sqrt(self.x * self.x + self.y * self.y)
+end
+Vector.length = Vector.magnitude
+
+function Vector:magnitude_squared()
+    return self.x * self.x + self.y * self.y
+end
+
+-- the same direction with a length of 1, or the zero vector
+function Vector:normalized()
+    local magnitude = self:magnitude()
+    if magnitude == 0 then
+        return vec(0, 0)
+    end
+    return self / magnitude
+end
+
+function Vector:dist(v)
+    return (self - v):magnitude()
+end
+
+function Vector:dot(v)
+    return self.x * v.x + self.y * v.y
+end
+
+-- the z part of the 3D cross product
+function Vector:cross(v)
+    return self.x * v.y - self.y * v.x
+end
+
+function Vector:cos_angle(v)
+    return self:dot(v) / (self:magnitude() * v:magnitude())
+end
+
+-- the angle from the x axis in radians
+function Vector:angle()
+    return math.atan(self.y, self.x)
+end
+
+function Vector:angle_to(v)
+    return math.atan(self:cross(v), self:dot(v))
+end
+
+function Vector:rotate(angle)
+    local cos, sin = math.cos(angle), math.sin(angle)
+    return vec(self.x * cos - self.y * sin, self.x * sin + self.y * cos)
+end
+
+-- rotated a quarter turn
+function Vector:perp()
+    return vec(-self.y, self.x)
+end
+
+-- the part of this vector that points along v
+function Vector:project(v)
+    return v * (self:dot(v) / v:dot(v))
+end
+
+function Vector:unpack()
+    return self.x, self.y
+end
+
+-- the position and velocity of object handles like OBJECTS.ball as vectors
+function physics.pos(obj)
+    return vec(obj:get_pos())
+end
+
+function physics.vel(obj)
+    return vec(obj:get_vel())
+end
+
+----------------------------------------------------------------
+-- Kinematics, these work with numbers or vectors
+----------------------------------------------------------------
+
+local function gravity(g)
+    return g or GRAVITY or 9.81
+end
+
+local function speed(v)
+    if type(v) == "number" then
+        return math.abs(v)
+    end
+    return v:magnitude()
+end
+
+-- x = x0 + v0 t + 1/2 a t^2
+function physics.position(x0, v0, a, t)
+    return x0 + v0 * t + a * (0.5 * t * t)
+end
+
+-- v = v0 + a t
+function physics.velocity(v0, a, t)
+    return v0 + a * t
+end
+
+-- v^2 = v0^2 + 2 a d, returns the final speed
+function physics.final_speed(v0, a, d)
+    return math.sqrt(math.max(v0 * v0 + 2 * a * d, 0))
+end
+
+function physics.kinetic_energy(mass, v)
+    local s = speed(v)
+    return 0.5 * mass * s * s
+end
+
+function physics.potential_energy(mass, height, g)
+    return mass * gravity(g) * height
+end
+
+function physics.momentum(mass, v)
+    return v * mass
+end
+
+-- how long something dropped from rest takes to fall a height
+function physics.fall_time(height, g)
+    return math.sqrt(2 * height / gravity(g))
+end
+
+-- projectiles launched at an angle in radians above flat ground
+function physics.projectile_time(launch_speed, angle, g)
+    return 2 * launch_speed * math.sin(angle) / gravity(g)
+end
+
+function physics.projectile_range(launch_speed, angle, g)
+    return launch_speed * launch_speed * math.sin(2 * angle) / gravity(g)
+end
+
+function physics.projectile_max_height(launch_speed, angle, g)
+    local vertical_speed = launch_speed * math.sin(angle)
+    return vertical_speed * vertical_speed / (2 * gravity(g))
+end
+
+-- returns the velocities after a 1D elastic collision
+function physics.elastic_collision_1d(m1, v1, m2, v2)
+    local total = m1 + m2
+    return (m1 - m2) / total * v1 + 2 * m2 / total * v2,
+        (m2 - m1) / total * v2 + 2 * m1 / total * v1
+end
+
+-- returns the velocity vectors after a 2D elastic collision between circles
+function physics.elastic_collision_2d(m1, p1, v1, m2, p2, v2)
+    local normal = p1 - p2
+    local dist_squared = normal:magnitude_squared()
+    local total = m1 + m2
+    local impulse = (v1 - v2):dot(normal) / dist_squared
+    return v1 - normal * (2 * m2 / total * impulse),
+        v2 + normal * (2 * m1 / total * impulse)
+end
+
+----------------------------------------------------------------
+-- Unit conversions, the simulation uses meters, kilograms, seconds, and radians
+----------------------------------------------------------------
+
+local units = {}
+physics.units = units
+
+function units.deg_to_rad(deg)
+    return deg * math.pi / 180
+end
+
+function units.rad_to_deg(rad)
+    return rad * 180 / math.pi
+end
+
+function units.kmh_to_ms(kmh)
+    return kmh / 3.6
+end
+
+function units.ms_to_kmh(ms)
+    return ms * 3.6
+end
+
+function units.mph_to_ms(mph)
+    return mph * 0.44704
+end
+
+function units.ms_to_mph(ms)
+    return ms / 0.44704
+end
+
+function units.ft_to_m(ft)
+    return ft * 0.3048
+end
+
+function units.m_to_ft(m)
+    return m / 0.3048
+end
+
+function units.cm_to_m(cm)
+    return cm / 100
+end
+
+function units.m_to_cm(m)
+    return m * 100
+end
+
+function units.lb_to_kg(lb)
+    return lb * 0.45359237
+end
+
+function units.kg_to_lb(kg)
+    return kg / 0.45359237
+end
+
+function units.rpm_to_rads(rpm)
+    return rpm * 2 * math.pi / 60
+end
+
+function units.rads_to_rpm(rads)
+    return rads * 60 / (2 * math.pi)
+end
+
+return physics
@@ -0,0 +1,112 @@
+use specs::prelude::*;
+
+use crate::{ColliderSet, GeometricalWorld, Point, Vector};
+
+use nc::bounding_volume::AABB;
+use nc::pipeline::CollisionGroups;
+use nc::query::{Proximity, Ray};
+use nc::shape::Cuboid;
+use ncollide2d as nc;
+use np::object::{Collider, DefaultBodyHandle};
+use nphysics2d as np;
+
+/// where raycast() hit something, the distance is from the start of the ray
+pub struct RayHit {
+    pub entity: Entity,
+    pub point: Vector,
+    pub normal: Vector,
+    pub distance: f32,
+}
+
+fn collider_entity(collider: &Collider<f32, DefaultBodyHandle>) -> Option<Entity> {
+    collider
+        .user_data()
+        .and_then(|data| data.downcast_ref::<Entity>())
+        .copied()
+}
+
+/// the first object that the line from start to end hits
+pub fn raycast(
+    world: &World,
+    start: Vector,
+    end: Vector,
+    ignored: Option<Entity>,
+) -> Option<RayHit> {
+    let geometrical_world = world.fetch::<GeometricalWorld>();
+    let colliders = world.fetch::<ColliderSet>();
+
+    let len = (end - start).norm();
+    if len < 1.0e-6 {
+        return None;
+    }
+    // with a direction of length 1 the time of impact is the distance
+    let ray = Ray::new(Point::from(start), (end - start) / len);
+
+    geometrical_world
+        .interferences_with_ray(&*colliders, &ray, len, &CollisionGroups::new())
+        .filter_map(|(_, collider, intersection)| {
+            collider_entity(collider).map(|entity| (entity, intersection))
+        })
+        .filter(|(entity, _)| Some(*entity) != ignored)
+        .min_by(|(_, a), (_, b)| a.toi.total_cmp(&b.toi))
+        .map(|(entity, intersection)| RayHit {
+            entity,
+            point: start + ray.dir * intersection.toi,
+            normal: intersection.normal,
+            distance: intersection.toi,
+        })
+}
+
+/// every object that a point is inside of
+pub fn query_point(world: &World, point: Vector) -> Vec<Entity> {
+    let geometrical_world = world.fetch::<GeometricalWorld>();
+    let colliders = world.fetch::<ColliderSet>();
+
+    geometrical_world
+        .interferences_with_point(&*colliders, &Point::from(point), &CollisionGroups::new())
+        .filter_map(|(_, collider)| collider_entity(collider))
+        .collect()
+}
+
+/// every object that overlaps the rectangle between two corners
+pub fn query_rect(world: &World, corner1: Vector, corner2: Vector) -> Vec<Entity> {
+    let geometrical_world = world.fetch::<GeometricalWorld>();
+    let colliders = world.fetch::<ColliderSet>();
+
+    let min = corner1.inf(&corner2);
+    let max = corner1.sup(&corner2);
+    let aabb = AABB::new(Point::from(min), Point::from(max));
+    // the broad phase only checks bounding boxes, so the shapes are checked against the rect
+    let rect = Cuboid::new((max - min) / 2.0);
+    let rect_pos = np::math::Isometry::new((min + max) / 2.0, 0.0);
+
+    geometrical_world
+        .interferences_with_aabb(&*colliders, &aabb, &CollisionGroups::new())
+        .filter(|(_, collider)| {
+            nc::query::proximity(&rect_pos, &rect, collider.position(), collider.shape(), 0.0)
+                == Proximity::Intersecting
+        })
+        .filter_map(|(_, collider)| collider_entity(collider))
+        .collect()
+}
+
+/// the object whose edge is closest to a point and how far away it is,
+/// which is 0 if the point is inside of it
+pub fn nearest(world: &World, point: Vector, ignored: Option<Entity>) -> Option<(Entity, f32)> {
+    let colliders = world.fetch::<ColliderSet>();
+    let point = Point::from(point);
+
+    colliders
+        .iter()
+        .filter_map(|(_, collider)| {
+            let entity = collider_entity(collider)?;
+            let distance = collider.shape().as_point_query()?.distance_to_point(
+                collider.position(),
+                &point,
+                true,
+            );
+            Some((entity, distance))
+        })
+        .filter(|(entity, _)| Some(*entity) != ignored)
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+}
@@ -0,0 +1,212 @@
+use crate::lua::lua_entity::LuaEntity;
+
+const MAX_HISTORY: usize = 100;
+// big tables like OBJECTS would flood the console otherwise
+const MAX_TABLE_DEPTH: usize = 3;
+const MAX_TABLE_ENTRIES: usize = 50;
+// tables shorter than this are printed on one line
+const INLINE_TABLE_WIDTH: usize = 60;
+
+/// The input line at the bottom of the Lua console
+#[derive(Clone, Default)]
+pub struct LuaRepl {
+    pub input: String,
+    /// the lines of a chunk that isn't finished yet, e.g. the start of a function
+    pub pending: String,
+    pub history: Vec<String>,
+    /// which history entry the up and down arrows are on, None while typing a new line
+    history_pos: Option<usize>,
+}
+
+impl LuaRepl {
+    /// clears the input and adds it to the history, returns the line that was entered
+    pub fn submit(&mut self) -> String {
+        let line = std::mem::take(&mut self.input);
+        if !line.trim().is_empty() && self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+            if self.history.len() > MAX_HISTORY {
+                self.history.remove(0);
+            }
+        }
+        self.history_pos = None;
+        line
+    }
+
+    pub fn history_prev(&mut self) -> Option<&str> {
+        let pos = match self.history_pos {
+            Some(pos) => pos.checked_sub(1)?,
+            None => self.history.len().checked_sub(1)?,
+        };
+        self.history_pos = Some(pos);
+        Some(&self.history[pos])
+    }
+
+    /// goes back to an empty line after the newest entry
+    pub fn history_next(&mut self) -> Option<&str> {
+        let pos = self.history_pos? + 1;
+        if pos < self.history.len() {
+            self.history_pos = Some(pos);
+            Some(&self.history[pos])
+        } else {
+            self.history_pos = None;
+            Some("")
+        }
+    }
+}
+
+pub struct Completion {
+    /// what to insert at the cursor
+    pub insert: String,
+    /// every match if there's more than one
+    pub candidates: Vec<String>,
+}
+
+/// Completes the global, or the field of a global table like OBJECTS.ba,
+/// that the text ends with
+pub fn complete(lua_ctx: rlua::Context, text: &str) -> Completion {
+    let word_start = text
+        .rfind(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+        .map_or(0, |i| i + 1);
+    let word = &text[word_start..];
+
+    let (path, prefix) = match word.rfind('.') {
+        Some(i) => (&word[..i], &word[i + 1..]),
+        None => ("", word),
+    };
+
+    let table = path
+        .split('.')
+        .filter(|key| !key.is_empty())
+        .try_fold(lua_ctx.globals(), |table, key| {
+            table.get::<_, rlua::Table>(key).ok()
+        });
+
+    let mut candidates: Vec<String> = table
+        .map(|table| {
+            table
+                .pairs::<rlua::Value, rlua::Value>()
+                .filter_map(|pair| match pair {
+                    Ok((rlua::Value::String(key), _)) => key.to_str().ok().map(str::to_string),
+                    _ => None,
+                })
+                .filter(|key| key.starts_with(prefix))
+                .collect()
+        })
+        .unwrap_or_default();
+    candidates.sort();
+
+    // extends the word as far as all of the candidates agree
+    let common = candidates.iter().skip(1).fold(
+        candidates.first().cloned().unwrap_or_default(),
+        |common, candidate| {
+            common
+                .chars()
+                .zip(candidate.chars())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect()
+        },
+    );
+    let insert = common.get(prefix.len()..).unwrap_or("").to_string();
+
+    if candidates.len() == 1 {
+        candidates.clear();
+    }
+    Completion { insert, candidates }
+}
+
+/// Formats values like they'd be written in Lua, e.g. { x = 1, y = 2 }
+pub fn pretty_print(value: &rlua::Value) -> String {
+    pretty_print_nested(value, 0)
+}
+
+fn pretty_print_nested(value: &rlua::Value, depth: usize) -> String {
+    match value {
+        rlua::Value::Nil => "nil".to_string(),
+        rlua::Value::Boolean(b) => b.to_string(),
+        rlua::Value::Integer(i) => i.to_string(),
+        rlua::Value::Number(n) => n.to_string(),
+        rlua::Value::String(s) => format!("{:?}", s.to_str().unwrap_or("<invalid utf-8>")),
+        rlua::Value::Table(table) => pretty_print_table(table, depth),
+        rlua::Value::Function(_) => "<function>".to_string(),
+        rlua::Value::UserData(user_data) => match user_data.borrow::<LuaEntity>() {
+            Ok(entity) => format!("<object {}>", entity.0.id()),
+            Err(_) => "<userdata>".to_string(),
+        },
+        rlua::Value::LightUserData(_) => "<userdata>".to_string(),
+        rlua::Value::Thread(_) => "<thread>".to_string(),
+        rlua::Value::Error(e) => e.to_string(),
+    }
+}
+
+fn pretty_print_table(table: &rlua::Table, depth: usize) -> String {
+    if depth >= MAX_TABLE_DEPTH {
+        return "{...}".to_string();
+    }
+
+    // the array part is printed in order, then the rest sorted by key. Only the
+    // entries that are shown get formatted, the rest are just counted.
+    let len = table.raw_len();
+    let mut entries: Vec<String> = (1..=len)
+        .take(MAX_TABLE_ENTRIES)
+        .map(|i| {
+            let value = table.raw_get(i).unwrap_or(rlua::Value::Nil);
+            pretty_print_nested(&value, depth + 1)
+        })
+        .collect();
+
+    let mut fields: Vec<(String, rlua::Value)> = table
+        .clone()
+        .pairs::<rlua::Value, rlua::Value>()
+        .filter_map(Result::ok)
+        .filter(|(key, _)| match key {
+            rlua::Value::Integer(i) => *i < 1 || *i > len,
+            _ => true,
+        })
+        .map(|(key, value)| {
+            let key = match &key {
+                rlua::Value::String(s) if is_identifier(s.to_str().unwrap_or("")) => {
+                    s.to_str().unwrap().to_string()
+                }
+                _ => format!("[{}]", pretty_print_nested(&key, MAX_TABLE_DEPTH)),
+            };
+            (key, value)
+        })
+        .collect();
+    let total = len as usize + fields.len();
+    fields.sort_by(|(key1, _), (key2, _)| key1.cmp(key2));
+    let room = MAX_TABLE_ENTRIES - entries.len();
+    entries.extend(
+        fields
+            .into_iter()
+            .take(room)
+            .map(|(key, value)| format!("{} = {}", key, pretty_print_nested(&value, depth + 1))),
+    );
+
+    if entries.is_empty() {
+        return "{}".to_string();
+    }
+    let hidden = total - entries.len();
+    if hidden > 0 {
+        entries.push(format!("... {} more", hidden));
+    }
+
+    let inline = format!("{{ {} }}", entries.join(", "));
+    if inline.len() <= INLINE_TABLE_WIDTH && !inline.contains('\n') {
+        return inline;
+    }
+
+    let indent = "    ".repeat(depth + 1);
+    let body = entries
+        .iter()
+        .map(|entry| format!("{}{},", indent, entry))
+        .collect::<Vec<String>>()
+        .join("\n");
+    format!("{{\n{}\n{}}}", body, "    ".repeat(depth))
+}
+
+pub fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
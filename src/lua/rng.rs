@@ -0,0 +1,269 @@
+use crate::resources::LuaRngRes;
+
+use super::lua_error;
+
+/// the seed that scenes start with until it's changed in the Settings menu or with rng.seed()
+pub const DEFAULT_SEED: i64 = 1;
+
+/// The random number generator behind the rng module. It's SplitMix64, so the same
+/// seed gives the same numbers on every computer, unlike Lua's math.random.
+/// The seed and math.random setting from the Settings menu are kept separate from
+/// the ones a scene sets itself, which only last until the scene is run again.
+#[derive(Clone, Debug)]
+pub struct SeededRng {
+    settings_seed: i64,
+    scene_seed: Option<i64>,
+    state: u64,
+    /// whether math.random() and math.randomseed() use this instead of Lua's own
+    pub settings_replace_math_random: bool,
+    scene_replace_math_random: Option<bool>,
+}
+
+impl Default for SeededRng {
+    fn default() -> Self {
+        SeededRng {
+            settings_seed: DEFAULT_SEED,
+            scene_seed: None,
+            state: DEFAULT_SEED as u64,
+            settings_replace_math_random: false,
+            scene_replace_math_random: None,
+        }
+    }
+}
+
+impl SeededRng {
+    /// the seed that's being used, the scene's if it set one
+    pub fn seed(&self) -> i64 {
+        self.scene_seed.unwrap_or(self.settings_seed)
+    }
+
+    pub fn scene_seed(&self) -> Option<i64> {
+        self.scene_seed
+    }
+
+    pub fn settings_seed(&self) -> i64 {
+        self.settings_seed
+    }
+
+    pub fn set_settings_seed(&mut self, seed: i64) {
+        self.settings_seed = seed;
+        self.restart();
+    }
+
+    pub fn set_scene_seed(&mut self, seed: i64) {
+        self.scene_seed = Some(seed);
+        self.restart();
+    }
+
+    pub fn replace_math_random(&self) -> bool {
+        self.scene_replace_math_random
+            .unwrap_or(self.settings_replace_math_random)
+    }
+
+    pub fn set_scene_replace_math_random(&mut self, replace: bool) {
+        self.scene_replace_math_random = Some(replace);
+    }
+
+    /// Forgets what the last scene set and goes back to the first number for the
+    /// Settings seed, done whenever a scene is run
+    pub fn start_scene(&mut self) {
+        self.scene_seed = None;
+        self.scene_replace_math_random = None;
+        self.restart();
+    }
+
+    /// goes back to the first number for the seed
+    fn restart(&mut self) {
+        self.state = self.seed() as u64;
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// from 0 up to but not including 1
+    pub fn uniform(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// a normally distributed number with a mean of 0 and a standard deviation of 1
+    pub fn normal(&mut self) -> f64 {
+        // Box-Muller, 1 - uniform() can't be 0 so the log is fine
+        let u1 = 1.0 - self.uniform();
+        let u2 = self.uniform();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+    }
+
+    /// a whole number from min to max, including both
+    pub fn integer(&mut self, min: i64, max: i64) -> i64 {
+        let range = (i128::from(max) - i128::from(min) + 1) as u128;
+        (i128::from(min) + (u128::from(self.next_u64()) % range) as i128) as i64
+    }
+}
+
+/// Makes the rng table. Its functions share the SeededRng with the Settings menu
+/// and export_lua(), which is why it isn't kept in the Lua state.
+pub fn rng_table<'lua>(
+    lua_ctx: rlua::Context<'lua>,
+    rng: &LuaRngRes,
+) -> rlua::Result<rlua::Table<'lua>> {
+    let table = lua_ctx.create_table()?;
+
+    // rng.seed(n) changes the seed and starts over, rng.seed() returns it
+    let seed_rng = rng.clone();
+    table.set(
+        "seed",
+        lua_ctx.create_function(move |_, seed: Option<rlua::Value>| {
+            let mut rng = seed_rng.lock().unwrap();
+            match seed {
+                Some(seed) => {
+                    rng.set_scene_seed(whole_number(seed, "the seed")?);
+                    Ok(None)
+                }
+                None => Ok(Some(rng.seed())),
+            }
+        })?,
+    )?;
+
+    // rng.uniform() is from 0 to 1, rng.uniform(a, b) is from a to b
+    let uniform_rng = rng.clone();
+    table.set(
+        "uniform",
+        lua_ctx.create_function(move |_, (a, b): (Option<f64>, Option<f64>)| {
+            let (a, b) = match (a, b) {
+                (None, None) => (0.0, 1.0),
+                (Some(a), Some(b)) => (a, b),
+                _ => {
+                    return Err(lua_error(
+                        "rng.uniform() takes either no arguments or a minimum and a maximum",
+                    ))
+                }
+            };
+            Ok(a + (b - a) * uniform_rng.lock().unwrap().uniform())
+        })?,
+    )?;
+
+    let normal_rng = rng.clone();
+    table.set(
+        "normal",
+        lua_ctx.create_function(move |_, (mean, sd): (Option<f64>, Option<f64>)| {
+            let sd = sd.unwrap_or(1.0);
+            if sd < 0.0 {
+                return Err(lua_error("the standard deviation can't be negative"));
+            }
+            Ok(mean.unwrap_or(0.0) + sd * normal_rng.lock().unwrap().normal())
+        })?,
+    )?;
+
+    // works like math.random(), so that it can replace it
+    let random_rng = rng.clone();
+    table.set(
+        "random",
+        lua_ctx.create_function(
+            move |_, (m, n): (Option<rlua::Value>, Option<rlua::Value>)| {
+                let mut rng = random_rng.lock().unwrap();
+                let (min, max) = match (m, n) {
+                    (None, _) => return Ok(rlua::Value::Number(rng.uniform())),
+                    (Some(m), None) => (1, whole_number(m, "the maximum")?),
+                    (Some(m), Some(n)) => (
+                        whole_number(m, "the minimum")?,
+                        whole_number(n, "the maximum")?,
+                    ),
+                };
+                if min > max {
+                    return Err(lua_error(format!(
+                        "the interval from {} to {} is empty",
+                        min, max
+                    )));
+                }
+                Ok(rlua::Value::Integer(rng.integer(min, max)))
+            },
+        )?,
+    )?;
+
+    // rng.replace_math_random(true) makes math.random() use the seed too
+    let replace_rng = rng.clone();
+    table.set(
+        "replace_math_random",
+        lua_ctx.create_function(move |_, replace: Option<bool>| {
+            let mut rng = replace_rng.lock().unwrap();
+            match replace {
+                Some(replace) => {
+                    rng.set_scene_replace_math_random(replace);
+                    Ok(None)
+                }
+                None => Ok(Some(rng.replace_math_random())),
+            }
+        })?,
+    )?;
+
+    Ok(table)
+}
+
+/// integers and floats like 5.0, but not 5.5
+fn whole_number(value: rlua::Value, what: &str) -> rlua::Result<i64> {
+    match value {
+        rlua::Value::Integer(i) => Ok(i),
+        rlua::Value::Number(n) if n.fract() == 0.0 && n.abs() < 2f64.powi(63) => Ok(n as i64),
+        _ => Err(lua_error(format!("{} has to be a whole number", what))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbers(rng: &mut SeededRng) -> (Vec<f64>, Vec<f64>, Vec<i64>) {
+        (
+            (0..20).map(|_| rng.uniform()).collect(),
+            (0..20).map(|_| rng.normal()).collect(),
+            (0..20).map(|_| rng.integer(-5, 5)).collect(),
+        )
+    }
+
+    #[test]
+    fn same_seed_same_numbers() {
+        let mut rng1 = SeededRng::default();
+        let mut rng2 = SeededRng::default();
+        rng1.set_settings_seed(42);
+        rng2.set_settings_seed(42);
+        let first_run = numbers(&mut rng1);
+        assert_eq!(first_run, numbers(&mut rng2));
+
+        // running the scene again starts over
+        rng1.start_scene();
+        assert_eq!(numbers(&mut rng1), first_run);
+
+        rng2.set_settings_seed(43);
+        assert_ne!(numbers(&mut rng2), first_run);
+    }
+
+    #[test]
+    fn scene_seed_doesnt_carry_over() {
+        let mut rng = SeededRng::default();
+        rng.set_settings_seed(7);
+        rng.set_scene_seed(99);
+        rng.set_scene_replace_math_random(true);
+        assert_eq!(rng.seed(), 99);
+        assert!(rng.replace_math_random());
+
+        rng.start_scene();
+        assert_eq!(rng.seed(), 7);
+        assert!(!rng.replace_math_random());
+    }
+
+    #[test]
+    fn integers_are_in_range() {
+        let mut rng = SeededRng::default();
+        assert!((0..1000)
+            .map(|_| rng.integer(-3, 3))
+            .all(|i| (-3..=3).contains(&i)));
+        // the whole range of i64 doesn't overflow
+        rng.integer(i64::MIN, i64::MAX);
+        assert_eq!(rng.integer(5, 5), 5);
+    }
+}
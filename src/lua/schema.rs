@@ -0,0 +1,410 @@
+use super::lua_error;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum FieldKind {
+    Number(Range),
+    String,
+    /// a string that has to be one of these, ignoring case
+    OneOf(&'static [&'static str]),
+    /// true or false, or the strings "true" and "false" that older scenes use
+    Flag,
+    Table,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Range {
+    Any,
+    AtLeast(f64),
+    Positive,
+    Between(f64, f64),
+}
+
+/// a key that a table passed to Lua functions like add_shape() can have
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Field {
+    pub name: &'static str,
+    pub kind: FieldKind,
+}
+
+const fn field(name: &'static str, kind: FieldKind) -> Field {
+    Field { name, kind }
+}
+
+/// what add_shape() and spawn() take, shape, x, y, and the size are checked
+/// for being there when the shape is made
+pub const SHAPE_FIELDS: &[Field] = &[
+    field("shape", FieldKind::OneOf(&["circle", "rect", "rectangle"])),
+    field("x", FieldKind::Number(Range::Any)),
+    field("y", FieldKind::Number(Range::Any)),
+    field("w", FieldKind::Number(Range::Positive)),
+    field("h", FieldKind::Number(Range::Positive)),
+    field("r", FieldKind::Number(Range::Positive)),
+    // the README has always said rot while the code read rotation
+    field("rot", FieldKind::Number(Range::Any)),
+    field("rotation", FieldKind::Number(Range::Any)),
+    field("mass", FieldKind::Number(Range::Positive)),
+    field("x_vel", FieldKind::Number(Range::Any)),
+    field("y_vel", FieldKind::Number(Range::Any)),
+    field("rotvel", FieldKind::Number(Range::Any)),
+    field("elasticity", FieldKind::Number(Range::Between(0.0, 1.0))),
+    field("friction", FieldKind::Number(Range::AtLeast(0.0))),
+    field("name", FieldKind::String),
+    field(
+        "status",
+        FieldKind::OneOf(&["dynamic", "static", "kinematic"]),
+    ),
+    field("color", FieldKind::Table),
+    field("update_function", FieldKind::String),
+    field("batch_function", FieldKind::String),
+    field("collision", FieldKind::Flag),
+    field("path", FieldKind::Table),
+    field("data", FieldKind::Table),
+];
+
+/// what add_force_field() takes, x, y, and the size are checked for being there
+/// when it's made
+pub const FORCE_FIELD_FIELDS: &[Field] = &[
+    field(
+        "kind",
+        FieldKind::OneOf(&["wind", "uniform", "radial", "vortex"]),
+    ),
+    field("x", FieldKind::Number(Range::Any)),
+    field("y", FieldKind::Number(Range::Any)),
+    field("r", FieldKind::Number(Range::Positive)),
+    field("w", FieldKind::Number(Range::Positive)),
+    field("h", FieldKind::Number(Range::Positive)),
+    field("strength", FieldKind::Number(Range::Any)),
+    field("angle", FieldKind::Number(Range::Any)),
+    field("name", FieldKind::String),
+];
+
+/// what add_soft_body() takes, shape, x, y, and the size are checked for being there
+/// when it's made
+pub const SOFT_BODY_FIELDS: &[Field] = &[
+    field("shape", FieldKind::OneOf(&["circle", "rect", "rectangle"])),
+    field("x", FieldKind::Number(Range::Any)),
+    field("y", FieldKind::Number(Range::Any)),
+    field("w", FieldKind::Number(Range::Positive)),
+    field("h", FieldKind::Number(Range::Positive)),
+    field("r", FieldKind::Number(Range::Positive)),
+    field("resolution", FieldKind::Number(Range::AtLeast(2.0))),
+    field("stiffness", FieldKind::Number(Range::Positive)),
+    field("damping", FieldKind::Number(Range::AtLeast(0.0))),
+    field("mass", FieldKind::Number(Range::Positive)),
+    field("elasticity", FieldKind::Number(Range::Between(0.0, 1.0))),
+    field("friction", FieldKind::Number(Range::AtLeast(0.0))),
+    field("color", FieldKind::Table),
+    field("name", FieldKind::String),
+];
+
+/// what add_pulley() takes, the bodies and wheels are checked for being there when it's made
+pub const PULLEY_FIELDS: &[Field] = &[
+    field("body1", FieldKind::String),
+    field("body2", FieldKind::String),
+    field("wheels", FieldKind::Table),
+    field("ratio", FieldKind::Number(Range::Positive)),
+    field("length", FieldKind::Number(Range::AtLeast(0.0))),
+    field("name", FieldKind::String),
+];
+
+/// what add_joint() takes, body1 and body2 are checked for being there when it's made
+pub const JOINT_FIELDS: &[Field] = &[
+    field(
+        "kind",
+        FieldKind::OneOf(&["pin", "revolute", "weld", "fixed"]),
+    ),
+    field("body1", FieldKind::String),
+    field("body2", FieldKind::String),
+    field("x", FieldKind::Number(Range::Any)),
+    field("y", FieldKind::Number(Range::Any)),
+    field("break_force", FieldKind::Number(Range::AtLeast(0.0))),
+    field("break_torque", FieldKind::Number(Range::AtLeast(0.0))),
+    field("name", FieldKind::String),
+];
+
+pub const COLOR_FIELDS: &[Field] = &[
+    field("r", FieldKind::Number(Range::Between(0.0, 255.0))),
+    field("g", FieldKind::Number(Range::Between(0.0, 255.0))),
+    field("b", FieldKind::Number(Range::Between(0.0, 255.0))),
+    field("a", FieldKind::Number(Range::Between(0.0, 255.0))),
+];
+
+/// what set_limits{} takes, the same minimums as the Settings menu
+pub const LIMITS_FIELDS: &[Field] = &[
+    field(
+        "instructions",
+        FieldKind::Number(Range::AtLeast(super::HOOK_INTERVAL as f64)),
+    ),
+    field("memory_kb", FieldKind::Number(Range::AtLeast(1024.0))),
+];
+
+/// Checks that a table only has the keys in fields and that their values have the right
+/// type and are in range. Keys that are left out aren't an error here.
+pub fn check_fields(table: &rlua::Table, fields: &[Field]) -> rlua::Result<()> {
+    table
+        .clone()
+        .pairs::<rlua::Value, rlua::Value>()
+        .try_for_each(|pair| {
+            let (key, value) = pair?;
+            let key = match key {
+                rlua::Value::String(key) => key.to_str()?.to_string(),
+                key => {
+                    return Err(lua_error(format!(
+                        "keys have to be strings, not {}",
+                        value_string(&key)
+                    )))
+                }
+            };
+            match fields.iter().find(|field| field.name == key) {
+                Some(field) => field.check(&value),
+                None => Err(unknown_key_error(&key, fields)),
+            }
+        })
+}
+
+/// the field with this name, e.g. for checking values that are set after the shape's made
+pub fn find_field(fields: &[Field], name: &str) -> Option<Field> {
+    fields.iter().find(|field| field.name == name).copied()
+}
+
+impl Field {
+    /// checks that a value has the right type and is in range
+    pub fn check(&self, value: &rlua::Value) -> rlua::Result<()> {
+        let expected = match (self.kind, value) {
+            (FieldKind::Number(range), rlua::Value::Number(_))
+            | (FieldKind::Number(range), rlua::Value::Integer(_)) => {
+                return self.check_range(range, number(value));
+            }
+            (FieldKind::String, rlua::Value::String(_)) => return Ok(()),
+            (FieldKind::OneOf(options), rlua::Value::String(s)) => {
+                let s = s.to_str()?;
+                return if options.iter().any(|option| option.eq_ignore_ascii_case(s)) {
+                    Ok(())
+                } else {
+                    Err(lua_error(format!(
+                        "\"{}\" has to be one of {}, not \"{}\"",
+                        self.name,
+                        options
+                            .iter()
+                            .map(|option| format!("\"{}\"", option))
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                        s
+                    )))
+                };
+            }
+            (FieldKind::Flag, rlua::Value::Boolean(_)) => return Ok(()),
+            (FieldKind::Flag, rlua::Value::String(s))
+                if matches!(s.to_str(), Ok("true") | Ok("false")) =>
+            {
+                return Ok(())
+            }
+            (FieldKind::Table, rlua::Value::Table(_)) => return Ok(()),
+            (FieldKind::Number(_), _) => "a number",
+            (FieldKind::String, _) | (FieldKind::OneOf(_), _) => "a string",
+            (FieldKind::Flag, _) => "true or false",
+            (FieldKind::Table, _) => "a table",
+        };
+
+        Err(lua_error(format!(
+            "\"{}\" should be {}, not {}",
+            self.name,
+            expected,
+            value_string(value)
+        )))
+    }
+
+    fn check_range(&self, range: Range, value: f64) -> rlua::Result<()> {
+        let message = match range {
+            _ if value.is_nan() => "can't be NaN".to_string(),
+            _ if value.is_infinite() => "has to be a finite number".to_string(),
+            Range::AtLeast(min) if value < min => format!("can't be less than {}", min),
+            Range::Positive if value <= 0.0 => "has to be positive".to_string(),
+            Range::Between(min, max) if value < min || value > max => {
+                format!("has to be between {} and {}", min, max)
+            }
+            _ => return Ok(()),
+        };
+        Err(lua_error(format!(
+            "\"{}\" {}, it's {}",
+            self.name, message, value
+        )))
+    }
+}
+
+fn number(value: &rlua::Value) -> f64 {
+    match *value {
+        rlua::Value::Integer(i) => i as f64,
+        rlua::Value::Number(n) => n,
+        _ => f64::NAN,
+    }
+}
+
+fn type_name(value: &rlua::Value) -> &'static str {
+    match value {
+        rlua::Value::Nil => "nil",
+        rlua::Value::Boolean(_) => "boolean",
+        rlua::Value::LightUserData(_) | rlua::Value::UserData(_) => "userdata",
+        rlua::Value::Integer(_) | rlua::Value::Number(_) => "number",
+        rlua::Value::String(_) => "string",
+        rlua::Value::Table(_) => "table",
+        rlua::Value::Function(_) => "function",
+        rlua::Value::Thread(_) => "thread",
+        rlua::Value::Error(_) => "error",
+    }
+}
+
+/// e.g. the string "5" or the number 5, so that it's clear which one it was
+fn value_string(value: &rlua::Value) -> String {
+    match value {
+        rlua::Value::String(s) => format!("the string \"{}\"", s.to_str().unwrap_or("?")),
+        rlua::Value::Boolean(b) => b.to_string(),
+        rlua::Value::Integer(_) | rlua::Value::Number(_) => {
+            format!("the number {}", number(value))
+        }
+        value => format!("a {}", type_name(value)),
+    }
+}
+
+/// misspelled keys would otherwise be ignored, so the closest one is suggested
+fn unknown_key_error(key: &str, fields: &[Field]) -> rlua::Error {
+    let closest = fields
+        .iter()
+        .map(|field| (field.name, edit_distance(key, field.name)))
+        .min_by_key(|(_, distance)| *distance)
+        .filter(|(name, distance)| *distance <= 2 && *distance < name.len());
+    match closest {
+        Some((name, _)) => lua_error(format!(
+            "unknown key \"{}\", did you mean \"{}\"?",
+            key, name
+        )),
+        None => lua_error(format!("unknown key \"{}\"", key)),
+    }
+}
+
+/// how many characters have to be added, removed, or changed to turn one string into the other
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    a.chars().enumerate().for_each(|(i, a_char)| {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        (0..b.len()).for_each(|j| {
+            let above = row[j + 1];
+            row[j + 1] = if a_char == b[j] {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        });
+    });
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(result: rlua::Result<()>) -> String {
+        match result {
+            Err(rlua::Error::RuntimeError(message)) => message,
+            result => panic!("expected an error, got {:?}", result),
+        }
+    }
+
+    fn check_shape(code: &str) -> rlua::Result<()> {
+        rlua::Lua::new().context(|lua_ctx| {
+            let table: rlua::Table = lua_ctx.load(code).eval()?;
+            check_fields(&table, SHAPE_FIELDS)
+        })
+    }
+
+    #[test]
+    fn check_fields_accepts_valid_tables() {
+        check_shape(r#"{shape = "Circle", x = 1, y = 2.5, r = 1, mass = 3, collision = "false"}"#)
+            .unwrap();
+        // leaving keys out is checked when the shape is made instead
+        check_shape("{}").unwrap();
+    }
+
+    #[test]
+    fn check_fields_rejects_bad_tables() {
+        assert_eq!(
+            message(check_shape(r#"{shape = "triangle"}"#)),
+            r#""shape" has to be one of "circle", "rect", "rectangle", not "triangle""#
+        );
+        assert_eq!(
+            message(check_shape(r#"{x = "5"}"#)),
+            r#""x" should be a number, not the string "5""#
+        );
+        assert_eq!(
+            message(check_shape("{[1] = 5}")),
+            "keys have to be strings, not the number 1"
+        );
+        assert_eq!(
+            message(check_shape("{mass = 0}")),
+            r#""mass" has to be positive, it's 0"#
+        );
+    }
+
+    #[test]
+    fn ranges() {
+        let number_field = |range| field("n", FieldKind::Number(range));
+        let check = |range, n: f64| number_field(range).check(&rlua::Value::Number(n));
+
+        assert!(check(Range::Any, -1e30).is_ok());
+        assert!(check(Range::AtLeast(2.0), 2.0).is_ok());
+        assert!(check(Range::AtLeast(2.0), 1.9).is_err());
+        assert!(check(Range::Positive, 0.001).is_ok());
+        assert!(check(Range::Positive, 0.0).is_err());
+        assert!(check(Range::Between(0.0, 1.0), 0.0).is_ok());
+        assert!(check(Range::Between(0.0, 1.0), 1.0).is_ok());
+        assert!(check(Range::Between(0.0, 1.0), 1.5).is_err());
+        assert!(number_field(Range::Any)
+            .check(&rlua::Value::Integer(i64::MAX))
+            .is_ok());
+
+        // NaN and infinity are rejected even by ranges they'd otherwise pass
+        assert_eq!(
+            message(check(Range::Any, f64::NAN)),
+            r#""n" can't be NaN, it's NaN"#
+        );
+        assert_eq!(
+            message(check(Range::AtLeast(0.0), f64::INFINITY)),
+            r#""n" has to be a finite number, it's inf"#
+        );
+        assert!(check(Range::Any, f64::NEG_INFINITY).is_err());
+    }
+
+    #[test]
+    fn edit_distances() {
+        assert_eq!(edit_distance("mass", "mass"), 0);
+        assert_eq!(edit_distance("mas", "mass"), 1);
+        assert_eq!(edit_distance("elastcity", "elasticity"), 1);
+        assert_eq!(edit_distance("rotaton", "rotation"), 1);
+        assert_eq!(edit_distance("frcition", "friction"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("abc", ""), 3);
+    }
+
+    #[test]
+    fn did_you_mean() {
+        let error = |key: &str| match unknown_key_error(key, SHAPE_FIELDS) {
+            rlua::Error::RuntimeError(message) => message,
+            e => panic!("unexpected error {:?}", e),
+        };
+        assert_eq!(
+            error("elastcity"),
+            r#"unknown key "elastcity", did you mean "elasticity"?"#
+        );
+        assert_eq!(
+            error("colour"),
+            r#"unknown key "colour", did you mean "color"?"#
+        );
+        // too far from anything to guess
+        assert_eq!(error("velocity"), r#"unknown key "velocity""#);
+        // one letter off from a one letter key isn't a suggestion either
+        assert_eq!(error("q"), r#"unknown key "q""#);
+    }
+}
@@ -1,25 +1,41 @@
 use rlua::prelude::*;
 use specs::prelude::*;
 
-use crate::components::{Color, Name, PhysicsBody, UpdateFunction};
-use crate::resources::{LuaRes, Paused};
-use crate::{BodySet, RigidBody, Vector};
+use std::collections::{BTreeMap, HashMap};
 
-use microprofile::scope;
+use crate::components::{BatchFunction, Collider, Color, Name, PhysicsBody, UpdateFunction};
+use crate::lua::{
+    body_proxy::{apply_table, BodyProxy},
+    console::LuaConsole,
+    with_memory_limit,
+};
+use crate::resources::{LuaBridgeRes, LuaLimits, LuaRes, Paused};
+use crate::{BodySet, ColliderSet, RigidBody};
 
-use nphysics2d as np;
+use microprofile::scope;
 
-pub struct LuaUpdateFnSys;
+/// Runs the update functions and batch functions of bodies. Each body gets a
+/// BodyProxy that's kept in the Lua registry and reused every frame.
+#[derive(Default)]
+pub struct LuaUpdateFnSys {
+    proxies: HashMap<Entity, rlua::RegistryKey>,
+}
 
 impl<'a> System<'a> for LuaUpdateFnSys {
     #[allow(clippy::type_complexity)]
     type SystemData = (
         WriteExpect<'a, BodySet>,
+        WriteExpect<'a, ColliderSet>,
         ReadStorage<'a, UpdateFunction>,
+        ReadStorage<'a, BatchFunction>,
         ReadStorage<'a, PhysicsBody>,
+        ReadStorage<'a, Collider>,
         ReadStorage<'a, Name>,
         WriteStorage<'a, Color>,
         Read<'a, LuaRes>,
+        ReadExpect<'a, LuaBridgeRes>,
+        WriteExpect<'a, LuaConsole>,
+        Read<'a, LuaLimits>,
         Read<'a, Paused>,
         Entities<'a>,
     );
@@ -28,110 +44,270 @@ impl<'a> System<'a> for LuaUpdateFnSys {
         &mut self,
         (
             mut body_set,
+            mut collider_set,
             update_functions,
+            batch_functions,
             physics_bodies,
+            colliders,
             names,
             mut colors,
             lua_res,
+            lua_bridge,
+            mut console,
+            limits,
             paused,
             entities,
         ): Self::SystemData,
     ) {
-        if !paused.0 {
-            lua_res.lock().unwrap().context(|lua_ctx| {
-                let globals = lua_ctx.globals();
-
-                (&update_functions, &physics_bodies, &mut colors, &entities)
-                    .join()
-                    .for_each(|(UpdateFunction(fn_name), physics_body, color, entity)| {
-                        let update_function: LuaFunction = globals.get(fn_name.as_str()).unwrap();
-                        let rigid_body = &mut body_set
-                            .get_mut(physics_body.body_handle)
-                            .unwrap()
-                            .downcast_mut::<RigidBody>()
-                            .unwrap();
-
-                        let obj_table = table_from_rigid_body(&rigid_body, &lua_ctx);
-                        let color_table = table_from_color(&color, &lua_ctx);
-                        obj_table.set("color", color_table).unwrap();
-
-                        // you can't change the name in the Lua but they're
-                        // useful to read
-                        if let Some(Name(obj_name)) = names.get(entity) {
-                            obj_table.set("name", obj_name.clone()).unwrap();
-                        }
+        if paused.0 {
+            return;
+        }
 
-                        if let Ok(changed_obj_table) =
-                            update_function.call::<_, LuaTable>(obj_table)
-                        {
-                            update_rigid_body_from_table(rigid_body, &changed_obj_table);
+        // the bodies moved since lua_update() so OBJECTS needs fresh data
+        lua_bridge
+            .lock()
+            .unwrap()
+            .snapshot_bodies(&body_set, &physics_bodies, &colors, &entities);
+
+        let lua = lua_res.lock().unwrap();
+        lua.context(|lua_ctx| {
+            let globals = lua_ctx.globals();
+            let mut bodies = BodyAccess {
+                body_set: &mut body_set,
+                collider_set: &mut collider_set,
+                physics_bodies: &physics_bodies,
+                colliders: &colliders,
+                names: &names,
+                colors: &mut colors,
+                proxies: &mut self.proxies,
+            };
+
+            // functions are looked up once per frame instead of once per body
+            let mut functions: HashMap<String, Option<LuaFunction>> = HashMap::new();
+            let mut get_function = |fn_name: &str| {
+                if let Some(function) = functions.get(fn_name) {
+                    return function.clone();
+                }
+                let function: Option<LuaFunction> = globals.get(fn_name).ok();
+                functions.insert(fn_name.to_string(), function.clone());
+                function
+            };
+
+            (&update_functions, &entities)
+                .join()
+                .for_each(|(UpdateFunction(fn_name), entity)| {
+                    let obj_name = names.get(entity).map(|Name(name)| name.clone());
+                    let update_function = match get_function(fn_name.as_str()) {
+                        Some(update_function) => update_function,
+                        None => {
+                            let e = LuaError::RuntimeError(format!(
+                                "update function {} isn't defined",
+                                fn_name
+                            ));
+                            console.log_error(&e, obj_name);
+                            return;
+                        }
+                    };
 
-                            let change_color_table: LuaTable =
-                                changed_obj_table.get("color").unwrap();
-                            update_color_from_table(&mut *color, &change_color_table);
+                    let result = bodies.proxy(lua_ctx, entity).and_then(|proxy| {
+                        let result = with_memory_limit(&lua, &limits, || {
+                            update_function.call::<_, LuaValue>(proxy.clone())
+                        });
+                        match result? {
+                            // obj is changed in place, so returning it is optional
+                            LuaValue::Nil | LuaValue::UserData(_) => bodies.apply(&proxy),
+                            LuaValue::Table(table) => bodies.apply_table(entity, &table),
+                            _ => Err(LuaError::RuntimeError(
+                                "update functions have to return obj or nothing".to_string(),
+                            )),
                         }
                     });
+                    if let Err(e) = result {
+                        console.log_error(&e, obj_name);
+                    }
+                });
+
+            // each batch function is called once with a list of all of its bodies,
+            // in order of their names so that runs with the same seed stay the same
+            let mut batches: BTreeMap<&str, Vec<Entity>> = BTreeMap::new();
+            (&batch_functions, &entities)
+                .join()
+                .for_each(|(BatchFunction(fn_name), entity)| {
+                    batches.entry(fn_name.as_str()).or_default().push(entity)
+                });
+            batches.into_iter().for_each(|(fn_name, batch)| {
+                let batch_function = match get_function(fn_name) {
+                    Some(batch_function) => batch_function,
+                    None => {
+                        let e = LuaError::RuntimeError(format!(
+                            "batch function {} isn't defined",
+                            fn_name
+                        ));
+                        console.log_error(&e, None);
+                        return;
+                    }
+                };
+
+                let result = batch
+                    .iter()
+                    .map(|entity| bodies.proxy(lua_ctx, *entity))
+                    .collect::<LuaResult<Vec<LuaAnyUserData>>>()
+                    .and_then(|proxies| {
+                        let list = lua_ctx.create_sequence_from(proxies.iter().cloned())?;
+                        let result =
+                            with_memory_limit(&lua, &limits, || batch_function.call::<_, ()>(list));
+                        // the bodies that were changed before an error still get changed
+                        proxies
+                            .iter()
+                            .try_for_each(|proxy| bodies.apply(proxy))
+                            .and(result)
+                    });
+                if let Err(e) = result {
+                    console.log_error(&e, None);
+                }
             });
-        }
+
+            let proxy_count = self.proxies.len();
+            self.proxies.retain(|entity, _| entities.is_alive(*entity));
+            if self.proxies.len() != proxy_count {
+                lua_ctx.expire_registry_values();
+            }
+        });
+        std::mem::drop(lua);
+
+        lua_bridge
+            .lock()
+            .unwrap()
+            .apply_commands(&mut body_set, &physics_bodies, &mut colors);
     }
 }
 
-fn table_from_color<'a>(color: &Color, lua_ctx: &LuaContext<'a>) -> LuaTable<'a> {
-    microprofile::scope!("lua", "Lua serialize color table");
-    let r = color.0.r * 255.0;
-    let g = color.0.g * 255.0;
-    let b = color.0.b * 255.0;
+/// what update functions need to read and write the bodies through their proxies
+struct BodyAccess<'s, 'a> {
+    body_set: &'s mut BodySet,
+    collider_set: &'s mut ColliderSet,
+    physics_bodies: &'s ReadStorage<'a, PhysicsBody>,
+    colliders: &'s ReadStorage<'a, Collider>,
+    names: &'s ReadStorage<'a, Name>,
+    colors: &'s mut WriteStorage<'a, Color>,
+    proxies: &'s mut HashMap<Entity, rlua::RegistryKey>,
+}
+
+impl<'s, 'a> BodyAccess<'s, 'a> {
+    fn body_and_collider(
+        &mut self,
+        entity: Entity,
+    ) -> LuaResult<(&mut RigidBody, &mut crate::lua::body_proxy::NpCollider)> {
+        let missing = || LuaError::RuntimeError("object no longer exists".to_string());
+        let body_handle = self
+            .physics_bodies
+            .get(entity)
+            .ok_or_else(missing)?
+            .body_handle;
+        let coll_handle = self.colliders.get(entity).ok_or_else(missing)?.coll_handle;
+        let rigid_body = self
+            .body_set
+            .get_mut(body_handle)
+            .and_then(|body| body.downcast_mut::<RigidBody>())
+            .ok_or_else(missing)?;
+        let collider = self.collider_set.get_mut(coll_handle).ok_or_else(missing)?;
+        Ok((rigid_body, collider))
+    }
 
-    let c_table = lua_ctx.create_table().unwrap();
-    c_table.set("r", r).unwrap();
-    c_table.set("g", g).unwrap();
-    c_table.set("b", b).unwrap();
+    /// The body's proxy with its fields and color refreshed, it's made the first time
+    fn proxy<'lua>(
+        &mut self,
+        lua_ctx: LuaContext<'lua>,
+        entity: Entity,
+    ) -> LuaResult<LuaAnyUserData<'lua>> {
+        microprofile::scope!("lua", "Refresh body proxy");
+        let existing = self
+            .proxies
+            .get(&entity)
+            .and_then(|key| lua_ctx.registry_value::<LuaAnyUserData>(key).ok());
+        let (rigid_body, collider) = self.body_and_collider(entity)?;
+        let proxy = match existing {
+            Some(proxy) => {
+                proxy
+                    .borrow_mut::<BodyProxy>()?
+                    .refresh(rigid_body, collider);
+                proxy
+            }
+            None => {
+                let proxy =
+                    lua_ctx.create_userdata(BodyProxy::new(entity, rigid_body, collider))?;
+                let extra_fields = lua_ctx.create_table()?;
+                extra_fields.set("color", lua_ctx.create_table()?)?;
+                proxy.set_user_value(extra_fields)?;
+                self.proxies
+                    .insert(entity, lua_ctx.create_registry_value(proxy.clone())?);
+                proxy
+            }
+        };
 
-    c_table
-}
+        {
+            // names can be changed in the GUI
+            let mut proxy = proxy.borrow_mut::<BodyProxy>()?;
+            let name = self.names.get(entity).map(|Name(name)| name.as_str());
+            if proxy.name.as_deref() != name {
+                proxy.name = name.map(str::to_string);
+            }
+        }
 
-fn update_color_from_table<'a>(color: &mut Color, table: &LuaTable<'a>) {
-    microprofile::scope!("lua", "Deserialize color table from Lua");
-    color.0.r = table.get::<_, f32>("r").unwrap() / 255.0;
-    color.0.g = table.get::<_, f32>("g").unwrap() / 255.0;
-    color.0.b = table.get::<_, f32>("b").unwrap() / 255.0;
-}
+        let extra_fields: LuaTable = proxy.get_user_value()?;
+        let color_table = match extra_fields.raw_get::<_, Option<LuaTable>>("color")? {
+            Some(color_table) => color_table,
+            None => {
+                let color_table = lua_ctx.create_table()?;
+                extra_fields.raw_set("color", color_table.clone())?;
+                color_table
+            }
+        };
+        if let Some(Color(color)) = self.colors.get(entity) {
+            color_table.set("r", color.r * 255.0)?;
+            color_table.set("g", color.g * 255.0)?;
+            color_table.set("b", color.b * 255.0)?;
+        }
 
-fn table_from_rigid_body<'a>(rigid_body: &RigidBody, lua_ctx: &LuaContext<'a>) -> LuaTable<'a> {
-    microprofile::scope!("lua", "Lua serialize body table");
-    let (pos, rot) = {
-        let isometry = rigid_body.position();
-        (isometry.translation, isometry.rotation.angle())
-    };
-    let vel: Vector = rigid_body.velocity().linear;
-
-    let obj_table = lua_ctx.create_table().unwrap();
-    obj_table.set("x", pos.x).unwrap();
-    obj_table.set("y", pos.y).unwrap();
-    obj_table.set("rot", rot).unwrap();
-    obj_table.set("x_vel", vel.x).unwrap();
-    obj_table.set("y_vel", vel.y).unwrap();
-    obj_table.set("y_vel", vel.y).unwrap();
-    obj_table.set("mass", rigid_body.augmented_mass().mass()).unwrap();
-
-    obj_table
-}
+        Ok(proxy)
+    }
 
-fn update_rigid_body_from_table<'a>(rigid_body: &mut RigidBody, table: &LuaTable<'a>) {
-    microprofile::scope!("lua", "Deserialize rigid body table from Lua");
-    let new_pos = {
-        let new_x: f32 = table.get("x").unwrap();
-        let new_y: f32 = table.get("y").unwrap();
-        let new_rot: f32 = table.get("rot").unwrap();
-        np::math::Isometry::new(Vector::new(new_x, new_y), new_rot)
-    };
-
-    let new_vel = {
-        let new_x_vel: f32 = table.get("x_vel").unwrap();
-        let new_y_vel: f32 = table.get("y_vel").unwrap();
-        Vector::new(new_x_vel, new_y_vel)
-    };
-
-    rigid_body.set_position(new_pos);
-    rigid_body.set_linear_velocity(new_vel);
+    /// writes the fields and color that the update function changed back to the body
+    fn apply(&mut self, proxy: &LuaAnyUserData) -> LuaResult<()> {
+        microprofile::scope!("lua", "Apply body proxy");
+        let entity = proxy.borrow::<BodyProxy>()?.entity;
+        let (rigid_body, collider) = self.body_and_collider(entity)?;
+        proxy
+            .borrow_mut::<BodyProxy>()?
+            .apply(rigid_body, collider)?;
+
+        let color_table: LuaTable = proxy.get_user_value::<LuaTable>()?.get("color")?;
+        self.update_color(entity, &color_table)
+    }
+
+    /// for update functions that return a table instead of obj, like they used to
+    fn apply_table(&mut self, entity: Entity, table: &LuaTable) -> LuaResult<()> {
+        let (rigid_body, collider) = self.body_and_collider(entity)?;
+        apply_table(table, rigid_body, collider)?;
+        match table.get::<_, Option<LuaTable>>("color")? {
+            Some(color_table) => self.update_color(entity, &color_table),
+            None => Ok(()),
+        }
+    }
+
+    /// the parts of the color that are missing from the table stay the same
+    fn update_color(&mut self, entity: Entity, table: &LuaTable) -> LuaResult<()> {
+        if let Some(Color(color)) = self.colors.get_mut(entity) {
+            if let Some(r) = table.get::<_, Option<f32>>("r")? {
+                color.r = r / 255.0;
+            }
+            if let Some(g) = table.get::<_, Option<f32>>("g")? {
+                color.g = g / 255.0;
+            }
+            if let Some(b) = table.get::<_, Option<f32>>("b")? {
+                color.b = b / 255.0;
+            }
+        }
+        Ok(())
+    }
 }
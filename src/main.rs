@@ -19,6 +19,8 @@ use lua::update_fn_sys::LuaUpdateFnSys;
 use gui::{imgui_wrapper::ImGuiWrapper, systems::*};
 use resources::HiDPIFactor;
 
+use main_state::joint_break_sys::JointBreakSys;
+use main_state::path_follow_sys::PathFollowSys;
 use main_state::physics_sys::PhysicsSys;
 
 const SCREEN_X: f32 = 20.0;
@@ -44,8 +46,8 @@ fn main() -> ggez::GameResult {
     let bodies: BodySet = BodySet::new();
     let colliders: ColliderSet = ColliderSet::new();
 
-    // right now the sim doesn't use any constraints or force generators
-    // so these are just left as is
+    // force fields and pulleys live in the force generator set,
+    // pins and welds live in the joint constraint set
     let joint_constraints = JointConstraintSet::new();
     let force_gens = ForceGeneratorSet::new();
 
@@ -71,11 +73,28 @@ fn main() -> ggez::GameResult {
     world.insert(resources::CreateElasticity(0.2));
     world.insert(resources::CreateShapeCentered(true));
     world.insert(resources::CreateShapeStatic(false));
+    world.insert(resources::CreateForceField(None));
+    world.insert(resources::CreateSoftBody(false));
+    world.insert(resources::CreateStiffness(100.0));
+    world.insert(resources::CreateResolution(6));
+    world.insert(resources::CreatePulley(None));
+    world.insert(resources::CreatePulleyRatio(1.0));
+    world.insert(resources::RecordPath(None));
+    world.insert(resources::RecordPathInterval(1.0));
+    world.insert(resources::CreateJoint(None));
+    world.insert(resources::CreateBreakForce(0.0));
+    world.insert(resources::CreateBreakTorque(0.0));
+    world.insert(resources::FlashBrokenJoints(true));
+    world.insert(resources::BrokenJoints::default());
+    world.insert(resources::CurrentScene(Some("lua/init.lua".to_string())));
+    world.insert(resources::HotReload::default());
+    world.insert(resources::Notification(None));
 
     world.insert(resources::FrameSteps(1));
     world.insert(resources::Paused(false));
 
     world.insert(resources::GraphPosData::default());
+    world.insert(resources::GraphExpression::default());
     world.insert(resources::MovingGraph(false));
     world.insert(resources::ScalingGraph(false));
 
@@ -103,11 +122,30 @@ fn main() -> ggez::GameResult {
 
     // new_lua_res() does a lot of stuff behind the scenes to
     // set up the Lua context
-    world.insert(lua::new_lua_res());
+    let lua_instructions_left = resources::LuaInstructionsLeft::default();
+    let lua_rng = resources::LuaRngRes::default();
+    world.insert(lua::new_lua_res(
+        lua_instructions_left.clone(),
+        lua_rng.clone(),
+    ));
+    world.insert(lua_instructions_left);
+    world.insert(lua_rng);
+    world.insert(resources::LuaLimits::default());
+    world.insert(resources::LuaBridgeRes::default());
+    world.insert(lua::console::LuaConsole::default());
+    world.insert(lua::repl::LuaRepl::default());
+    world.insert(lua::lab_controls::LabControls::default());
+    world.insert(lua::drawing::LuaDrawings::default());
+    world.insert(lua::goals::Goals::default());
+    world.insert(lua::goals::GoalResultsExport::default());
+    world.insert(resources::CurrentSceneInfo::default());
+    world.insert(resources::SceneBrowser::default());
+    world.insert(resources::PauseOnLuaError(false));
 
     world.insert(resources::FPS(60.0));
     world.insert(resources::DT(std::time::Duration::from_millis(16)));
     world.insert(resources::Timestep(0.016));
+    world.insert(resources::SimTime(0.0));
     world.insert(resources::Selected(None));
 
     world.insert(resources::Camera::default());
@@ -115,6 +153,12 @@ fn main() -> ggez::GameResult {
     // many components aren't used in proper specs systems, so just
     // register them all manually.
     world.register::<Collider>();
+    world.register::<ForceGen>();
+    world.register::<SoftBody>();
+    world.register::<SoftBodyParticle>();
+    world.register::<Pulley>();
+    world.register::<FollowPath>();
+    world.register::<Joint>();
     world.register::<InfoDisplayed>();
 
     // The specs dispatcher takes a bunch of systems and tries to
@@ -122,6 +166,8 @@ fn main() -> ggez::GameResult {
     let mut dispatcher = DispatcherBuilder::new()
         .with(PhysicsSys, "physics_sys", &[])
         .with(SelectedMoveSys, "selected_move_sys", &["physics_sys"])
+        .with(PathFollowSys, "path_follow_sys", &["physics_sys"])
+        .with(JointBreakSys, "joint_break_sys", &["physics_sys"])
         .with(
             SpeedGraphSys::default(),
             "speed_graph_sys",
@@ -137,9 +183,14 @@ fn main() -> ggez::GameResult {
         .with(XVelGraphSys::default(), "x_vel_graph_sys", &["physics_sys"])
         .with(YVelGraphSys::default(), "y_vel_graph_sys", &["physics_sys"])
         .with(RotGraphSys::default(), "rot_graph_sys", &["physics_sys"])
+        .with(
+            LuaGraphSys,
+            "lua_graph_sys",
+            &["physics_sys", "lua_update_fn"],
+        )
         .with(MinMaxGraphSys, "graph_minmax_sys", &["physics_sys"])
         .with(GraphTransformSys, "graph_transform_sys", &["physics_sys"])
-        .with(LuaUpdateFnSys, "lua_update_fn", &["physics_sys"])
+        .with(LuaUpdateFnSys::default(), "lua_update_fn", &["physics_sys"])
         .build();
 
     dispatcher.setup(&mut world);
@@ -179,6 +230,7 @@ fn main() -> ggez::GameResult {
     };
 
     main_state.add_shapes_from_lua_file("lua/init.lua");
+    main_state.reset_lua_budget();
     main_state.lua_update();
 
     ggez::event::run(ctx, event_loop, main_state)
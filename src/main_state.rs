@@ -2,23 +2,41 @@ use ggez::input::keyboard::KeyCode;
 
 use specs::prelude::*;
 
-use crate::{BodySet, Collider, ColliderSet, Vector};
+use crate::{BodySet, Collider, ColliderSet, ForceGeneratorSet, JointConstraintSet, Vector};
 
 use crate::components::*;
 
-use crate::resources::Camera;
+use crate::lua::console::LuaConsole;
+use crate::resources::{
+    Camera, CreateForceField, CreateJoint, CreatePulley, CreateSoftBody, CreationData,
+    CurrentScene, CurrentSceneInfo, HotReload, LuaRes, Notification, NotificationMessage, Paused,
+    RecordPath, SimTime,
+};
 
 use crate::gui::imgui_wrapper::{ImGuiWrapper, UiChoice};
 
 pub mod body_builder;
+pub mod force_field;
+pub mod joint;
+pub mod keyframe_path;
+pub mod pulley;
+pub mod soft_body;
 mod util;
 
+mod draw_force_field_sys;
+mod draw_joint_sys;
+mod draw_lua_sys;
+mod draw_path_sys;
+mod draw_pulley_sys;
 mod draw_shape_sys;
+mod draw_soft_body_sys;
 mod screen_resize_sys;
 
 mod event_handler;
 pub use event_handler::*;
 
+pub mod joint_break_sys;
+pub mod path_follow_sys;
 pub mod physics_sys;
 
 pub struct MainState<'a, 'b> {
@@ -34,30 +52,123 @@ impl<'a, 'b> MainState<'a, 'b> {
         // before being removed from the specs world.
         // NEVER call world.delete_entity() to remove
         // a physics object.
+
+        // deleting a group can delete entities that are queued up
+        // for deletion later, e.g. in delete_all()
+        if !self.world.is_alive(entity) {
+            return;
+        }
+
+        // soft bodies are deleted as a unit, so deleting any one
+        // of the particles deletes the whole thing
+        let soft_body_parent = self
+            .world
+            .read_storage::<SoftBodyParticle>()
+            .get(entity)
+            .map(|particle| particle.soft_body);
+        if let Some(soft_body) = soft_body_parent {
+            self.delete_entity(soft_body);
+            return;
+        }
+
+        let soft_body_particles = self
+            .world
+            .read_storage::<SoftBody>()
+            .get(entity)
+            .map(|soft_body| soft_body.particles.clone());
+        if let Some(particles) = soft_body_particles {
+            particles.iter().for_each(|particle| {
+                self.delete_attachments(*particle);
+                self.remove_physics_body(*particle);
+                self.world.delete_entity(*particle).unwrap();
+            });
+        }
+
+        self.delete_attachments(entity);
+        self.remove_physics_body(entity);
         {
-            let mut body_set = self.world.fetch_mut::<BodySet>();
-            let body_storage = self.world.read_storage::<PhysicsBody>();
-            let body_handle = body_storage.get(entity).unwrap();
+            // joints that haven't broken yet are still in the JointConstraintSet
+            let mut joint_set = self.world.fetch_mut::<JointConstraintSet>();
+            let joint_storage = self.world.read_storage::<Joint>();
+            if let Some(joint) = joint_storage.get(entity) {
+                joint_set.remove(joint.joint_handle);
+            }
+        }
+        {
+            // force fields etc. only live in the ForceGeneratorSet
+            let mut force_gen_set = self.world.fetch_mut::<ForceGeneratorSet>();
+            let force_gen_storage = self.world.read_storage::<ForceGen>();
+            if let Some(force_gen) = force_gen_storage.get(entity) {
+                force_gen_set.remove(force_gen.gen_handle);
+            }
+        }
 
-            let mut collider_set = self.world.fetch_mut::<ColliderSet>();
-            let collider_storage = self.world.read_storage::<Collider>();
-            let collider_handle = collider_storage.get(entity).unwrap();
+        {
+            // obj.data is kept in Lua
+            let lua = LuaRes::clone(&self.world.fetch::<LuaRes>());
+            lua.lock().unwrap().context(|lua_ctx| {
+                crate::lua::lua_entity::set_lua_data(lua_ctx, entity, None).unwrap();
+            });
+        }
 
+        // the side menu stays open if it's for a different object
+        if self.imgui_wrapper.find_sidemenu_entity() == Some(entity) {
+            self.imgui_wrapper.remove_sidemenu();
+        }
+        self.world.delete_entity(entity).unwrap();
+    }
+
+    /// deletes the pulleys and joints connected to a body,
+    /// only used by delete_entity()
+    fn delete_attachments(&mut self, entity: Entity) {
+        let attached: Vec<Entity> = {
+            let pulleys = self.world.read_storage::<Pulley>();
+            let joints = self.world.read_storage::<Joint>();
+            let entities = self.world.entities();
+            (&pulleys, &entities)
+                .join()
+                .filter(|(pulley, _)| pulley.body1 == entity || pulley.body2 == entity)
+                .map(|(_, e)| e)
+                .chain(
+                    (&joints, &entities)
+                        .join()
+                        .filter(|(joint, _)| joint.body1 == entity || joint.body2 == entity)
+                        .map(|(_, e)| e),
+                )
+                .collect()
+        };
+
+        attached
+            .iter()
+            .for_each(|attachment| self.delete_entity(*attachment));
+    }
+
+    /// removes the body and collider of an entity if it has them,
+    /// only used by delete_entity()
+    fn remove_physics_body(&mut self, entity: Entity) {
+        let mut body_set = self.world.fetch_mut::<BodySet>();
+        let body_storage = self.world.read_storage::<PhysicsBody>();
+
+        let mut collider_set = self.world.fetch_mut::<ColliderSet>();
+        let collider_storage = self.world.read_storage::<Collider>();
+
+        if let (Some(body_handle), Some(collider_handle)) =
+            (body_storage.get(entity), collider_storage.get(entity))
+        {
             body_set.remove(body_handle.body_handle);
             collider_set.remove(collider_handle.coll_handle);
         }
-
-        self.imgui_wrapper.remove_sidemenu();
-        self.world.delete_entity(entity).unwrap();
     }
 
     pub fn delete_all(&mut self) {
         let delete_buff: Vec<Entity> = {
             let physics_bodies = self.world.read_storage::<PhysicsBody>();
+            let force_gens = self.world.read_storage::<ForceGen>();
             let entities = self.world.entities();
             (&physics_bodies, &entities)
                 .join()
                 .map(|(_, e)| e)
+                .chain((&force_gens, &entities).join().map(|(_, e)| e))
                 .collect()
         };
 
@@ -66,6 +177,106 @@ impl<'a, 'b> MainState<'a, 'b> {
         });
     }
 
+    /// deletes everything and loads a scene from a Lua file,
+    /// it's remembered so that it can be reset later
+    pub fn load_scene(&mut self, filename: String) {
+        let new_scene = self.world.fetch::<CurrentScene>().0.as_ref() != Some(&filename);
+
+        self.delete_all();
+        self.clear_lua_handlers();
+        self.world.insert(SimTime(0.0));
+        self.lua_update();
+        self.add_shapes_from_lua_file(filename.clone());
+        self.lua_update();
+        self.world.insert(CurrentScene(Some(filename)));
+
+        // the instructions are shown when a scene is opened, but not when it's reset
+        if new_scene {
+            if self.world.fetch::<CurrentSceneInfo>().0.is_some() {
+                self.imgui_wrapper.shown_menus.insert(UiChoice::SceneInfo);
+            } else {
+                self.imgui_wrapper.shown_menus.remove(&UiChoice::SceneInfo);
+            }
+        }
+    }
+
+    /// reloads the last scene that was loaded, undoing everything since then
+    pub fn reset_scene(&mut self) {
+        let current_scene = self.world.fetch::<CurrentScene>().0.clone();
+        if let Some(filename) = current_scene {
+            self.load_scene(filename);
+        }
+    }
+
+    /// hot reloads the current scene, showing whether it worked
+    pub fn hot_reload_scene(&mut self) {
+        let current_scene = self.world.fetch::<CurrentScene>().0.clone();
+        let filename = match current_scene {
+            Some(filename) => filename,
+            None => return,
+        };
+
+        if self.rerun_scene(filename.clone()) {
+            self.notify(
+                format!("Reloaded {} with errors, see the Lua Console", filename),
+                true,
+            );
+        } else {
+            self.notify(format!("Reloaded {}", filename), false);
+        }
+    }
+
+    /// Reruns a scene in a new Lua state. The camera, graphs, lab control values,
+    /// and whether it's paused stay the same. Returns whether there were errors.
+    pub fn rerun_scene(&mut self, filename: String) -> bool {
+        let paused = self.world.fetch::<Paused>().0;
+
+        // errors from the reload are told apart from ones that were already there
+        let prev_unseen_error = std::mem::replace(
+            &mut self.world.fetch_mut::<LuaConsole>().unseen_error,
+            false,
+        );
+
+        self.keep_graphs(|main_state| {
+            main_state.reset_lua_state();
+            main_state.set_lua_paused(paused);
+            main_state.load_scene(filename);
+        });
+        self.world.insert(Paused(paused));
+        self.set_lua_paused(paused);
+
+        let mut console = self.world.fetch_mut::<LuaConsole>();
+        let error = console.unseen_error;
+        console.unseen_error |= prev_unseen_error;
+        error
+    }
+
+    /// hot reloads the scene if hot reloading is on and one of its files changed
+    pub fn poll_hot_reload(&mut self) {
+        let changed = self.world.fetch_mut::<HotReload>().files_changed();
+        if changed {
+            self.hot_reload_scene();
+        }
+    }
+
+    pub fn notify(&mut self, text: String, error: bool) {
+        self.world.insert(Notification(Some(NotificationMessage {
+            text,
+            error,
+            shown_at: std::time::Instant::now(),
+        })));
+    }
+
+    /// stops creating whatever was being created from the GUI
+    pub fn cancel_creation(&mut self) {
+        self.world.insert(CreationData(None));
+        self.world.insert(CreateForceField(None));
+        self.world.insert(CreateSoftBody(false));
+        self.world.insert(CreatePulley(None));
+        self.world.insert(CreateJoint(None));
+        self.world.insert(RecordPath(None));
+    }
+
     pub fn reactivate_all(&mut self) {
         let bodies = self.world.read_storage::<PhysicsBody>();
         let mut body_set = self.world.fetch_mut::<BodySet>();
@@ -9,6 +9,7 @@ use crate::RigidBodyDesc;
 
 use ncollide2d as nc;
 use nphysics2d as np;
+use nphysics2d::object::DefaultBodyHandle;
 
 pub struct BodyBuilder<'a> {
     pub body_set: Write<'a, BodySet>,
@@ -27,6 +28,7 @@ pub struct BodyBuilder<'a> {
     pub color: ggez::graphics::Color,
     pub name: Option<String>,
     pub update_fn: Option<String>,
+    pub batch_fn: Option<String>,
     pub collisions_enabled: bool,
 }
 
@@ -79,11 +81,19 @@ impl<'a> BodyBuilder<'a> {
             color: ggez::graphics::WHITE,
             name: None,
             update_fn: None,
+            batch_fn: None,
             collisions_enabled: true,
         }
     }
 
-    pub fn create(mut self) -> Entity {
+    pub fn create(self) -> Entity {
+        self.create_with_handle().0
+    }
+
+    /// Same as create() but also returns the handle in the BodySet,
+    /// which is needed for things like springs that are created before
+    /// the entity's PhysicsBody component is inserted.
+    pub fn create_with_handle(mut self) -> (Entity, DefaultBodyHandle) {
         let body = RigidBodyDesc::new()
             .mass(self.mass)
             .translation(self.translation)
@@ -119,6 +129,10 @@ impl<'a> BodyBuilder<'a> {
             specs_handle = specs_handle.with(UpdateFunction(f));
         }
 
+        if let Some(f) = self.batch_fn {
+            specs_handle = specs_handle.with(BatchFunction(f));
+        }
+
         let specs_handle = specs_handle.entity;
 
         self.body_set
@@ -131,6 +145,6 @@ impl<'a> BodyBuilder<'a> {
             .unwrap()
             .set_user_data(Some(Box::new(specs_handle)));
 
-        specs_handle
+        (specs_handle, body_handle)
     }
 }
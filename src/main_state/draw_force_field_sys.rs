@@ -0,0 +1,106 @@
+use ggez::graphics::{self, MeshBuilder};
+
+use specs::prelude::*;
+
+use crate::components::{ForceGen, InfoDisplayed};
+use crate::{ForceGeneratorSet, Vector};
+
+use super::force_field::{FieldShape, ForceField, ForceFieldKind};
+use super::util::draw_arrow;
+
+// distance between arrow glyphs
+const ARROW_SPACING: f32 = 1.0;
+
+pub struct DrawForceFieldsSys<'m> {
+    pub mesh_builder: &'m mut MeshBuilder,
+}
+
+impl<'a, 'm> System<'a> for DrawForceFieldsSys<'m> {
+    type SystemData = (
+        ReadStorage<'a, ForceGen>,
+        ReadStorage<'a, InfoDisplayed>,
+        ReadExpect<'a, ForceGeneratorSet>,
+        Entities<'a>,
+    );
+
+    fn run(&mut self, (force_gens, info_displayed, force_gen_set, entities): Self::SystemData) {
+        (&force_gens, &entities)
+            .join()
+            .for_each(|(force_gen, entity)| {
+                let field = match force_gen_set
+                    .get(force_gen.gen_handle)
+                    .and_then(|gen| gen.downcast_ref::<ForceField>())
+                {
+                    Some(field) => field,
+                    None => return,
+                };
+
+                let color = match field.kind {
+                    ForceFieldKind::Wind => graphics::Color::new(0.45, 0.6, 0.85, 0.6),
+                    ForceFieldKind::Radial => graphics::Color::new(0.9, 0.6, 0.2, 0.6),
+                    ForceFieldKind::Vortex => graphics::Color::new(0.7, 0.4, 0.9, 0.6),
+                };
+                let outline_width = if info_displayed.get(entity).is_some() {
+                    0.1
+                } else {
+                    0.04
+                };
+
+                let (min, max) = match field.shape {
+                    FieldShape::Rectangle(half_extents) => {
+                        self.mesh_builder.rectangle(
+                            graphics::DrawMode::stroke(outline_width),
+                            graphics::Rect::new(
+                                field.center.x - half_extents.x,
+                                field.center.y - half_extents.y,
+                                half_extents.x * 2.0,
+                                half_extents.y * 2.0,
+                            ),
+                            color,
+                        );
+                        (field.center - half_extents, field.center + half_extents)
+                    }
+                    FieldShape::Circle(r) => {
+                        self.mesh_builder.circle(
+                            graphics::DrawMode::stroke(outline_width),
+                            [field.center.x, field.center.y],
+                            r,
+                            0.01,
+                            color,
+                        );
+                        let extents = Vector::new(r, r);
+                        (field.center - extents, field.center + extents)
+                    }
+                };
+
+                // arrows are laid out on a grid centered on the field
+                let cols = ((max.x - min.x) / ARROW_SPACING).floor().max(1.0) as usize;
+                let rows = ((max.y - min.y) / ARROW_SPACING).floor().max(1.0) as usize;
+                let grid_offset = Vector::new(
+                    (max.x - min.x - (cols - 1) as f32 * ARROW_SPACING) / 2.0,
+                    (max.y - min.y - (rows - 1) as f32 * ARROW_SPACING) / 2.0,
+                );
+
+                (0..rows).for_each(|row| {
+                    (0..cols).for_each(|col| {
+                        let point =
+                            min + grid_offset + Vector::new(col as f32, row as f32) * ARROW_SPACING;
+                        if !field.contains(&point) {
+                            return;
+                        }
+
+                        if let Some(dir) = field.force_at(&point).try_normalize(1.0e-6) {
+                            let half_arrow = dir * ARROW_SPACING * 0.3;
+                            draw_arrow(
+                                self.mesh_builder,
+                                point - half_arrow,
+                                point + half_arrow,
+                                0.04,
+                                color,
+                            );
+                        }
+                    });
+                });
+            });
+    }
+}
@@ -0,0 +1,112 @@
+use ggez::graphics::{self, MeshBuilder};
+
+use specs::prelude::*;
+
+use crate::components::{Joint, PhysicsBody};
+use crate::resources::{CreateJoint, MousePos};
+use crate::{BodySet, Vector};
+
+use super::joint::JointKind;
+
+const JOINT_SIZE: f32 = 0.15;
+const LINK_WIDTH: f32 = 0.05;
+
+pub struct DrawJointsSys<'m> {
+    pub mesh_builder: &'m mut MeshBuilder,
+}
+
+impl<'m> DrawJointsSys<'m> {
+    /// draws a link from the center of the body to the anchor and the anchor itself
+    fn draw_link(
+        &mut self,
+        body_center: Vector,
+        anchor: Vector,
+        kind: JointKind,
+        color: graphics::Color,
+    ) {
+        // errors if the anchor is at the center of the body, which is fine
+        let _ = self.mesh_builder.line(
+            &[[body_center.x, body_center.y], [anchor.x, anchor.y]],
+            LINK_WIDTH,
+            color,
+        );
+
+        match kind {
+            JointKind::Pin => {
+                self.mesh_builder.circle(
+                    graphics::DrawMode::stroke(LINK_WIDTH),
+                    [anchor.x, anchor.y],
+                    JOINT_SIZE,
+                    0.01,
+                    color,
+                );
+            }
+            JointKind::Weld => {
+                self.mesh_builder.rectangle(
+                    graphics::DrawMode::fill(),
+                    graphics::Rect::new(
+                        anchor.x - JOINT_SIZE,
+                        anchor.y - JOINT_SIZE,
+                        JOINT_SIZE * 2.0,
+                        JOINT_SIZE * 2.0,
+                    ),
+                    color,
+                );
+            }
+        }
+    }
+}
+
+impl<'a, 'm> System<'a> for DrawJointsSys<'m> {
+    type SystemData = (
+        ReadStorage<'a, Joint>,
+        ReadStorage<'a, PhysicsBody>,
+        ReadExpect<'a, BodySet>,
+        Read<'a, CreateJoint>,
+        Read<'a, MousePos>,
+    );
+
+    fn run(
+        &mut self,
+        (joints, physics_bodies, body_set, create_joint, mouse_pos): Self::SystemData,
+    ) {
+        let joint_color = graphics::Color::new(0.75, 0.75, 0.8, 1.0);
+        let body_isometry = |entity| {
+            physics_bodies
+                .get(entity)
+                .and_then(|physics_body| body_set.rigid_body(physics_body.body_handle))
+                .map(|rigid_body| *rigid_body.position())
+        };
+
+        joints.join().for_each(|joint| {
+            let (isometry1, isometry2) =
+                match (body_isometry(joint.body1), body_isometry(joint.body2)) {
+                    (Some(isometry1), Some(isometry2)) => (isometry1, isometry2),
+                    _ => return,
+                };
+
+            // broken joints fade out in red
+            let color = match joint.broken_flash {
+                Some(time_left) => graphics::Color::new(1.0, 0.15, 0.1, time_left.min(1.0)),
+                None => joint_color,
+            };
+
+            let anchor1 = (isometry1 * joint.anchor1).coords;
+            let anchor2 = (isometry2 * joint.anchor2).coords;
+            self.draw_link(isometry1.translation.vector, anchor1, joint.kind, color);
+            self.draw_link(isometry2.translation.vector, anchor2, joint.kind, color);
+        });
+
+        // the joint that's being created goes from the first body to the mouse
+        if let Some(draft) = &create_joint.0 {
+            if let Some(isometry) = draft.body.and_then(body_isometry) {
+                self.draw_link(
+                    isometry.translation.vector,
+                    mouse_pos.0,
+                    draft.kind,
+                    graphics::WHITE,
+                );
+            }
+        }
+    }
+}
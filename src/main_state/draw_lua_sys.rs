@@ -0,0 +1,102 @@
+use ggez::graphics::{self, MeshBuilder, Scale, TextFragment};
+
+use specs::prelude::*;
+
+use crate::lua::drawing::{DrawingShape, LuaDrawings};
+use crate::resources::Camera;
+
+use super::util::draw_arrow;
+use super::MainState;
+
+/// text is rendered at this size and then scaled down to the size it was drawn with
+const TEXT_RESOLUTION: f32 = 32.0;
+
+/// Draws what scripts drew with draw_line() etc., except for text.
+/// Drawings in screen coordinates go in a separate mesh that the camera doesn't move.
+pub struct DrawLuaSys<'m> {
+    pub mesh_builder: &'m mut MeshBuilder,
+    pub screen: bool,
+}
+
+impl<'a, 'm> System<'a> for DrawLuaSys<'m> {
+    type SystemData = Read<'a, LuaDrawings>;
+
+    fn run(&mut self, drawings: Self::SystemData) {
+        let screen = self.screen;
+        drawings
+            .0
+            .iter()
+            .filter(|drawing| drawing.screen == screen)
+            .for_each(|drawing| {
+                let draw_mode = if drawing.width > 0.0 {
+                    graphics::DrawMode::stroke(drawing.width)
+                } else {
+                    graphics::DrawMode::fill()
+                };
+
+                // ggez errors on things like lines with a width of 0, they just aren't drawn
+                match &drawing.shape {
+                    DrawingShape::Line(points) => {
+                        let points: Vec<[f32; 2]> = points.iter().map(|p| [p.x, p.y]).collect();
+                        let _ = self
+                            .mesh_builder
+                            .line(&points, drawing.width, drawing.color);
+                    }
+                    DrawingShape::Arrow(start, end) => {
+                        draw_arrow(
+                            self.mesh_builder,
+                            *start,
+                            *end,
+                            drawing.width,
+                            drawing.color,
+                        );
+                    }
+                    DrawingShape::Circle(center, r) => {
+                        self.mesh_builder.circle(
+                            draw_mode,
+                            [center.x, center.y],
+                            *r,
+                            0.01,
+                            drawing.color,
+                        );
+                    }
+                    DrawingShape::Polygon(points) => {
+                        let points: Vec<[f32; 2]> = points.iter().map(|p| [p.x, p.y]).collect();
+                        let _ = self.mesh_builder.polygon(draw_mode, &points, drawing.color);
+                    }
+                    DrawingShape::Text(..) => {}
+                }
+            });
+    }
+}
+
+impl<'a, 'b> MainState<'a, 'b> {
+    /// text from draw_text() can't go in a mesh, so it's drawn on its own after the meshes
+    pub fn draw_lua_text(&self, ctx: &mut ggez::Context) {
+        let camera = self.world.fetch::<Camera>();
+        let drawings = self.world.fetch::<LuaDrawings>();
+
+        drawings.0.iter().for_each(|drawing| {
+            if let DrawingShape::Text(pos, text, size) = &drawing.shape {
+                let (dest, size) = if drawing.screen {
+                    (*pos, *size)
+                } else {
+                    (camera.pos + pos * camera.scale, size * camera.scale)
+                };
+                let text = graphics::Text::new(
+                    TextFragment::new(text.as_str())
+                        .scale(Scale::uniform(TEXT_RESOLUTION))
+                        .color(drawing.color),
+                );
+                let scale = size / TEXT_RESOLUTION;
+                let _ = graphics::draw(
+                    ctx,
+                    &text,
+                    graphics::DrawParam::new()
+                        .dest([dest.x, dest.y])
+                        .scale([scale, scale]),
+                );
+            }
+        });
+    }
+}
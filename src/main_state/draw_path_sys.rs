@@ -0,0 +1,81 @@
+use ggez::graphics::{self, MeshBuilder};
+
+use specs::prelude::*;
+
+use crate::components::{FollowPath, InfoDisplayed};
+use crate::resources::{MousePos, RecordPath};
+use crate::Vector;
+
+use super::keyframe_path::KeyframePath;
+
+const PATH_SAMPLES: usize = 128;
+const WAYPOINT_RADIUS: f32 = 0.1;
+
+/// Draws the path of the body in the side menu, and the path
+/// that's being recorded if there is one
+pub struct DrawPathsSys<'m> {
+    pub mesh_builder: &'m mut MeshBuilder,
+}
+
+impl<'m> DrawPathsSys<'m> {
+    fn draw_path(&mut self, points: &[Vector], waypoints: &[Vector], color: graphics::Color) {
+        let points = points.iter().map(|p| [p.x, p.y]).collect::<Vec<[f32; 2]>>();
+        if points.len() >= 2 {
+            // only errors if the points overlap, which doesn't matter
+            let _ = self.mesh_builder.line(points.as_slice(), 0.04, color);
+        }
+
+        waypoints.iter().for_each(|waypoint| {
+            self.mesh_builder.circle(
+                graphics::DrawMode::fill(),
+                [waypoint.x, waypoint.y],
+                WAYPOINT_RADIUS,
+                0.01,
+                color,
+            );
+        });
+    }
+}
+
+impl<'a, 'm> System<'a> for DrawPathsSys<'m> {
+    type SystemData = (
+        ReadStorage<'a, FollowPath>,
+        ReadStorage<'a, InfoDisplayed>,
+        Read<'a, RecordPath>,
+        Read<'a, MousePos>,
+    );
+
+    fn run(&mut self, (follow_paths, info_displayed, record_path, mouse_pos): Self::SystemData) {
+        let path_color = graphics::Color::new(0.4, 0.9, 0.6, 0.8);
+
+        (&follow_paths, &info_displayed)
+            .join()
+            .for_each(|(follow_path, _)| {
+                let path = &follow_path.path;
+                let waypoints = path
+                    .keyframes
+                    .iter()
+                    .map(|keyframe| keyframe.pos)
+                    .collect::<Vec<Vector>>();
+                self.draw_path(&sample_path(path), &waypoints, path_color);
+            });
+
+        if let Some(draft) = &record_path.0 {
+            let mut points = draft.waypoints.clone();
+            points.push(mouse_pos.0);
+            self.draw_path(&points, &draft.waypoints, graphics::WHITE);
+        }
+    }
+}
+
+/// Returns points along a single pass of the path
+fn sample_path(path: &KeyframePath) -> Vec<Vector> {
+    let duration = path.duration();
+    let mut points = (0..PATH_SAMPLES)
+        .map(|i| path.pos_at(i as f32 / PATH_SAMPLES as f32 * duration))
+        .collect::<Vec<Vector>>();
+    if let Some(last) = path.keyframes.last() {
+        points.push(last.pos);
+    }
+    points
+}
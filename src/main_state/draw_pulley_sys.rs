@@ -0,0 +1,92 @@
+use ggez::graphics::{self, MeshBuilder};
+
+use specs::prelude::*;
+
+use crate::components::{ForceGen, PhysicsBody, Pulley};
+use crate::resources::{CreatePulley, MousePos};
+use crate::{BodySet, ForceGeneratorSet, Vector};
+
+use super::pulley::PulleyConstraint;
+
+const WHEEL_RADIUS: f32 = 0.2;
+const ROPE_WIDTH: f32 = 0.04;
+
+pub struct DrawPulleysSys<'m> {
+    pub mesh_builder: &'m mut MeshBuilder,
+}
+
+impl<'m> DrawPulleysSys<'m> {
+    fn draw_rope(&mut self, points: &[Vector], wheels: &[Vector], color: graphics::Color) {
+        let points = points.iter().map(|p| [p.x, p.y]).collect::<Vec<[f32; 2]>>();
+        if points.len() >= 2 {
+            // only errors if the points overlap, which doesn't matter
+            let _ = self.mesh_builder.line(points.as_slice(), ROPE_WIDTH, color);
+        }
+
+        wheels.iter().for_each(|wheel| {
+            self.mesh_builder.circle(
+                graphics::DrawMode::stroke(ROPE_WIDTH),
+                [wheel.x, wheel.y],
+                WHEEL_RADIUS,
+                0.01,
+                color,
+            );
+        });
+    }
+}
+
+impl<'a, 'm> System<'a> for DrawPulleysSys<'m> {
+    type SystemData = (
+        ReadStorage<'a, Pulley>,
+        ReadStorage<'a, ForceGen>,
+        ReadStorage<'a, PhysicsBody>,
+        ReadExpect<'a, ForceGeneratorSet>,
+        ReadExpect<'a, BodySet>,
+        Read<'a, CreatePulley>,
+        Read<'a, MousePos>,
+    );
+
+    fn run(
+        &mut self,
+        (pulleys, force_gens, physics_bodies, force_gen_set, body_set, create_pulley, mouse_pos): Self::SystemData,
+    ) {
+        let rope_color = graphics::Color::new(0.85, 0.8, 0.65, 1.0);
+        let body_pos = |entity| {
+            physics_bodies
+                .get(entity)
+                .and_then(|physics_body| body_set.rigid_body(physics_body.body_handle))
+                .map(|rigid_body| rigid_body.position().translation.vector)
+        };
+
+        (&pulleys, &force_gens)
+            .join()
+            .for_each(|(pulley, force_gen)| {
+                let constraint = match force_gen_set
+                    .get(force_gen.gen_handle)
+                    .and_then(|gen| gen.downcast_ref::<PulleyConstraint>())
+                {
+                    Some(constraint) => constraint,
+                    None => return,
+                };
+
+                if let (Some(p1), Some(p2)) = (body_pos(pulley.body1), body_pos(pulley.body2)) {
+                    let mut rope = Vec::with_capacity(constraint.wheels.len() + 2);
+                    rope.push(p1);
+                    rope.extend(constraint.wheels.iter());
+                    rope.push(p2);
+                    self.draw_rope(&rope, &constraint.wheels, rope_color);
+                }
+            });
+
+        // the pulley that's being created follows the mouse
+        if let Some(draft) = &create_pulley.0 {
+            if let Some(p1) = draft.body.and_then(body_pos) {
+                let mut rope = Vec::with_capacity(draft.wheels.len() + 2);
+                rope.push(p1);
+                rope.extend(draft.wheels.iter());
+                rope.push(mouse_pos.0);
+                self.draw_rope(&rope, &draft.wheels, graphics::WHITE);
+            }
+        }
+    }
+}
@@ -2,7 +2,7 @@ use ggez::graphics::{self, MeshBuilder};
 
 use specs::prelude::*;
 
-use crate::components::{Collider, Color};
+use crate::components::{Collider, Color, SoftBodyParticle};
 use crate::resources::Selected;
 use crate::ColliderSet;
 
@@ -19,14 +19,19 @@ impl<'a, 'm> System<'a> for DrawShapesSys<'m> {
         Entities<'a>,
         ReadStorage<'a, Collider>,
         ReadStorage<'a, Color>,
+        ReadStorage<'a, SoftBodyParticle>,
         ReadExpect<'a, ColliderSet>,
         Read<'a, Selected>,
     );
 
-    fn run(&mut self, (entities, colliders, colors, collider_set, selected): Self::SystemData) {
-        (&colliders, &colors, &entities)
+    fn run(
+        &mut self,
+        (entities, colliders, colors, soft_body_particles, collider_set, selected): Self::SystemData,
+    ) {
+        // soft body particles are covered by the skin drawn in DrawSoftBodiesSys
+        (&colliders, &colors, &entities, !&soft_body_particles)
             .join()
-            .for_each(|(collider_comp, color, e)| {
+            .for_each(|(collider_comp, color, e, _)| {
                 let collider = collider_set
                     .get(collider_comp.coll_handle)
                     .expect("error getting collider to draw");
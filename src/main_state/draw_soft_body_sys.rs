@@ -0,0 +1,59 @@
+use ggez::graphics::{self, MeshBuilder};
+
+use specs::prelude::*;
+
+use crate::components::{Collider, Color, SoftBody};
+use crate::ColliderSet;
+
+pub struct DrawSoftBodiesSys<'m> {
+    pub mesh_builder: &'m mut MeshBuilder,
+}
+
+impl<'a, 'm> System<'a> for DrawSoftBodiesSys<'m> {
+    type SystemData = (
+        ReadStorage<'a, SoftBody>,
+        ReadStorage<'a, Color>,
+        ReadStorage<'a, Collider>,
+        ReadExpect<'a, ColliderSet>,
+    );
+
+    fn run(&mut self, (soft_bodies, colors, colliders, collider_set): Self::SystemData) {
+        (&soft_bodies, &colors)
+            .join()
+            .for_each(|(soft_body, color)| {
+                let particle_pos = |col: usize, row: usize| {
+                    soft_body.lattice[row * soft_body.cols + col]
+                        .and_then(|i| colliders.get(soft_body.particles[i]))
+                        .and_then(|collider| collider_set.get(collider.coll_handle))
+                        .map(|collider| {
+                            let translation = collider.position().translation.vector;
+                            [translation.x, translation.y]
+                        })
+                };
+
+                // the skin is a polygon for each cell of the lattice, so it
+                // deforms along with the particles. Cells on the edge of
+                // circles only have three corners.
+                (0..soft_body.rows - 1).for_each(|row| {
+                    (0..soft_body.cols - 1).for_each(|col| {
+                        let corners = [
+                            particle_pos(col, row),
+                            particle_pos(col + 1, row),
+                            particle_pos(col + 1, row + 1),
+                            particle_pos(col, row + 1),
+                        ];
+                        let points = corners.iter().flatten().copied().collect::<Vec<_>>();
+
+                        if points.len() >= 3 {
+                            // errors if the cell is squashed flat, just skip it
+                            let _ = self.mesh_builder.polygon(
+                                graphics::DrawMode::fill(),
+                                points.as_slice(),
+                                color.0,
+                            );
+                        }
+                    });
+                });
+            });
+    }
+}
@@ -1,4 +1,8 @@
 use super::body_builder::BodyBuilder;
+use super::force_field::{FieldShape, ForceField};
+use super::joint::{threshold, JointBuilder};
+use super::pulley::PulleyBuilder;
+use super::soft_body::SoftBodyBuilder;
 use super::util::*;
 use super::*;
 
@@ -6,13 +10,21 @@ use nphysics2d as np;
 
 use crate::gui::draw_creation_gui_sys::DrawCreationGUISys;
 
+use draw_force_field_sys::DrawForceFieldsSys;
+use draw_joint_sys::DrawJointsSys;
+use draw_lua_sys::DrawLuaSys;
+use draw_path_sys::DrawPathsSys;
+use draw_pulley_sys::DrawPulleysSys;
 use draw_shape_sys::DrawShapesSys;
+use draw_soft_body_sys::DrawSoftBodiesSys;
 use microprofile::scope;
 
 use crate::resources::{
-    self, Camera, CreateElasticity, CreateFriction, CreateMass, CreateShapeCentered,
-    CreateShapeStatic, CreationData, GraphPosData, HiDPIFactor, MousePos, MouseStartPos,
-    MovingGraph, Paused, ScalingGraph, ShapeInfo,
+    self, Camera, CreateBreakForce, CreateBreakTorque, CreateElasticity, CreateForceField,
+    CreateFriction, CreateJoint, CreateMass, CreatePulley, CreatePulleyRatio, CreateResolution,
+    CreateShapeCentered, CreateShapeStatic, CreateSoftBody, CreateStiffness, CreationData,
+    GraphPosData, HiDPIFactor, MousePos, MouseStartPos, MovingGraph, Paused, RecordPath,
+    ScalingGraph, ShapeInfo,
 };
 
 use ggez::{
@@ -34,10 +46,13 @@ impl<'a, 'b> EventHandler for MainState<'a, 'b> {
 
             self.world.maintain();
 
+            self.reset_lua_budget();
+            self.poll_hot_reload();
             self.process_gui_signals();
             self.lua_update();
 
             self.dispatcher.dispatch(&self.world);
+            self.show_lua_errors();
         }
 
         self.move_camera(ctx);
@@ -56,11 +71,45 @@ impl<'a, 'b> EventHandler for MainState<'a, 'b> {
         let mut mesh_builder = graphics::MeshBuilder::new();
 
         {
+            // force fields are drawn first so that they're underneath the shapes
+            let mut draw_force_fields_sys = DrawForceFieldsSys {
+                mesh_builder: &mut mesh_builder,
+            };
+            draw_force_fields_sys.run_now(&self.world);
+
+            let mut draw_soft_bodies_sys = DrawSoftBodiesSys {
+                mesh_builder: &mut mesh_builder,
+            };
+            draw_soft_bodies_sys.run_now(&self.world);
+
             let mut draw_shapes_sys = DrawShapesSys {
                 mesh_builder: &mut mesh_builder,
             };
             draw_shapes_sys.run_now(&self.world);
 
+            // ropes are attached to the centers of the shapes so they go on top
+            let mut draw_pulleys_sys = DrawPulleysSys {
+                mesh_builder: &mut mesh_builder,
+            };
+            draw_pulleys_sys.run_now(&self.world);
+
+            let mut draw_joints_sys = DrawJointsSys {
+                mesh_builder: &mut mesh_builder,
+            };
+            draw_joints_sys.run_now(&self.world);
+
+            let mut draw_paths_sys = DrawPathsSys {
+                mesh_builder: &mut mesh_builder,
+            };
+            draw_paths_sys.run_now(&self.world);
+
+            // what scripts drew goes on top of everything in the scene
+            let mut draw_lua_sys = DrawLuaSys {
+                mesh_builder: &mut mesh_builder,
+                screen: false,
+            };
+            draw_lua_sys.run_now(&self.world);
+
             // draws the outlined circle and rect if you're creating an object
             // self.draw_creation_gui(&mut mesh_builder);
             let mut draw_creation_gui_sys = DrawCreationGUISys {
@@ -77,6 +126,19 @@ impl<'a, 'b> EventHandler for MainState<'a, 'b> {
                 let drawparam = camera.make_drawparam();
                 let _ = graphics::draw(ctx, &mesh, drawparam);
             }
+
+            // drawings in screen coordinates aren't moved by the camera
+            let mut screen_mesh_builder = graphics::MeshBuilder::new();
+            let mut draw_lua_sys = DrawLuaSys {
+                mesh_builder: &mut screen_mesh_builder,
+                screen: true,
+            };
+            draw_lua_sys.run_now(&self.world);
+            if let Ok(mesh) = screen_mesh_builder.build(ctx) {
+                let _ = graphics::draw(ctx, &mesh, graphics::DrawParam::new());
+            }
+
+            self.draw_lua_text(ctx);
         }
 
         // ideally this block should be refactored to a system for each of the graphs
@@ -188,6 +250,11 @@ impl<'a, 'b> EventHandler for MainState<'a, 'b> {
         self.world
             .insert(resources::MouseStartPos(Some(mouse_point)));
 
+        // clicks the script handles don't select or create anything
+        if !self.imgui_wrapper.wants_mouse() && self.lua_mouse_event(btn, true) {
+            return;
+        }
+
         match btn {
             MouseButton::Left => {
                 {
@@ -224,7 +291,9 @@ impl<'a, 'b> EventHandler for MainState<'a, 'b> {
                     // If a sidepanel'd object is right clicked, remove sidepanel
                     // if a non sidepanel'd object is right clicked, add sidepanel
                     let mut info_displayed = self.world.write_storage::<InfoDisplayed>();
-                    match get_hovered_shape(&self.world) {
+                    let hovered = get_hovered_shape(&self.world)
+                        .or_else(|| get_hovered_force_field(&self.world));
+                    match hovered {
                         Some(entity) => {
                             if info_displayed.get(entity).is_some() {
                                 info_displayed.remove(entity).unwrap();
@@ -254,6 +323,12 @@ impl<'a, 'b> EventHandler for MainState<'a, 'b> {
         _y: f32,
     ) {
         self.imgui_wrapper.update_mouse_down((false, false, false));
+
+        if !self.imgui_wrapper.wants_mouse() && self.lua_mouse_event(btn, false) {
+            self.world.insert(resources::MouseStartPos(None));
+            return;
+        }
+
         match btn {
             MouseButton::Left => {
                 // unselect object and graph
@@ -262,6 +337,69 @@ impl<'a, 'b> EventHandler for MainState<'a, 'b> {
 
                 self.world.insert(MovingGraph(false));
 
+                if !self.imgui_wrapper.wants_mouse() {
+                    let mut record_path = self.world.fetch_mut::<RecordPath>();
+                    if let Some(draft) = record_path.0.as_mut() {
+                        draft.waypoints.push(self.world.fetch::<MousePos>().0);
+                    }
+                }
+
+                let create_pulley = self.world.fetch::<CreatePulley>().0.clone();
+                if let Some(mut draft) = create_pulley {
+                    let hovered = get_hovered_shape(&self.world);
+                    let next_draft = match (draft.body, hovered) {
+                        (None, Some(body)) => {
+                            draft.body = Some(body);
+                            Some(draft)
+                        }
+                        (Some(_), None) => {
+                            draft.wheels.push(self.world.fetch::<MousePos>().0);
+                            Some(draft)
+                        }
+                        // the rope has to go over at least one wheel
+                        (Some(body1), Some(body2))
+                            if body1 != body2 && !draft.wheels.is_empty() =>
+                        {
+                            PulleyBuilder {
+                                ratio: self.world.fetch::<CreatePulleyRatio>().0,
+                                ..PulleyBuilder::new(body1, body2, draft.wheels)
+                            }
+                            .create(&self.world);
+                            None
+                        }
+                        _ => Some(draft),
+                    };
+                    self.world.insert(CreatePulley(next_draft));
+                }
+
+                let create_joint = self.world.fetch::<CreateJoint>().0.clone();
+                if let Some(mut draft) = create_joint {
+                    let hovered = get_hovered_shape(&self.world);
+                    let next_draft = match (draft.body, hovered) {
+                        (None, Some(body)) => {
+                            draft.body = Some(body);
+                            Some(draft)
+                        }
+                        // the joint is anchored where the second body is clicked
+                        (Some(body1), Some(body2)) if body1 != body2 => {
+                            JointBuilder {
+                                break_force: threshold(self.world.fetch::<CreateBreakForce>().0),
+                                break_torque: threshold(self.world.fetch::<CreateBreakTorque>().0),
+                                ..JointBuilder::new(
+                                    draft.kind,
+                                    body1,
+                                    body2,
+                                    self.world.fetch::<MousePos>().0,
+                                )
+                            }
+                            .create(&self.world);
+                            None
+                        }
+                        _ => Some(draft),
+                    };
+                    self.world.insert(CreateJoint(next_draft));
+                }
+
                 let create_shape_opt = self.world.fetch::<CreationData>();
                 let create_shape_data = create_shape_opt.0.as_ref();
                 let create_shape_centered = self.world.fetch::<CreateShapeCentered>().0;
@@ -301,21 +439,42 @@ impl<'a, 'b> EventHandler for MainState<'a, 'b> {
                         _ => todo!(),
                     };
 
-                    BodyBuilder {
-                        translation,
-                        rotation: 0.0,
-                        restitution: self.world.fetch::<CreateElasticity>().0,
-                        friction: self.world.fetch::<CreateFriction>().0,
-                        status,
-                        ..BodyBuilder::from_world(
-                            &self.world,
-                            shape_info,
-                            self.world.fetch::<CreateMass>().0,
-                        )
+                    if let Some(kind) = self.world.fetch::<CreateForceField>().0 {
+                        let field_shape = match shape_info {
+                            ShapeInfo::Rectangle(Some(half_extents)) => {
+                                FieldShape::Rectangle(half_extents)
+                            }
+                            ShapeInfo::Circle(Some(r)) => FieldShape::Circle(r),
+                            _ => unreachable!(),
+                        };
+                        ForceField::new(kind, field_shape, translation).create(&self.world);
+                    } else if self.world.fetch::<CreateSoftBody>().0 {
+                        SoftBodyBuilder {
+                            resolution: self.world.fetch::<CreateResolution>().0 as usize,
+                            stiffness: self.world.fetch::<CreateStiffness>().0,
+                            mass: self.world.fetch::<CreateMass>().0,
+                            restitution: self.world.fetch::<CreateElasticity>().0,
+                            friction: self.world.fetch::<CreateFriction>().0,
+                            ..SoftBodyBuilder::new(shape_info, translation)
+                        }
+                        .create(&self.world);
+                    } else {
+                        BodyBuilder {
+                            translation,
+                            rotation: 0.0,
+                            restitution: self.world.fetch::<CreateElasticity>().0,
+                            friction: self.world.fetch::<CreateFriction>().0,
+                            status,
+                            ..BodyBuilder::from_world(
+                                &self.world,
+                                shape_info,
+                                self.world.fetch::<CreateMass>().0,
+                            )
+                        }
+                        .create();
                     }
-                    .create();
                     std::mem::drop(create_shape_opt);
-                    self.world.insert(CreationData(None));
+                    self.cancel_creation();
                 }
             }
 
@@ -334,15 +493,23 @@ impl<'a, 'b> EventHandler for MainState<'a, 'b> {
         _ctx: &mut ggez::Context,
         btn: KeyCode,
         keymods: KeyMods,
-        _repeat: bool,
+        repeat: bool,
     ) {
+        // keys the script handles take precedence over the hotkeys
+        if !self.imgui_wrapper.wants_keyboard() && self.lua_key_event(btn, true, repeat) {
+            self.imgui_wrapper.update_key_down(btn, keymods);
+            return;
+        }
+
         // hotkeys
         match (btn, keymods) {
             (KeyCode::B, KeyMods::NONE) => {
+                self.cancel_creation();
                 self.world
                     .insert(CreationData(Some(ShapeInfo::Rectangle(None))));
             }
             (KeyCode::C, KeyMods::NONE) => {
+                self.cancel_creation();
                 self.world
                     .insert(CreationData(Some(ShapeInfo::Circle(None))));
             }
@@ -358,6 +525,9 @@ impl<'a, 'b> EventHandler for MainState<'a, 'b> {
             (KeyCode::D, KeyMods::SHIFT) => {
                 self.delete_all();
             }
+            (KeyCode::R, KeyMods::NONE) => {
+                self.reset_scene();
+            }
             (KeyCode::D, KeyMods::NONE) => {
                 let found_sidepanel_entity = self.imgui_wrapper.find_sidemenu_entity();
 
@@ -372,6 +542,8 @@ impl<'a, 'b> EventHandler for MainState<'a, 'b> {
     }
 
     fn key_up_event(&mut self, _ctx: &mut ggez::Context, btn: KeyCode, keymods: KeyMods) {
+        // always sent so that is_key_down() doesn't get stuck
+        self.lua_key_event(btn, false, false);
         self.imgui_wrapper.update_key_up(btn, keymods);
     }
 
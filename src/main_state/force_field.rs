@@ -0,0 +1,141 @@
+use specs::prelude::*;
+
+use crate::components::ForceGen;
+use crate::{ForceGeneratorSet, Vector};
+
+use np::force_generator::ForceGenerator;
+use np::math::{Force, ForceType};
+use np::object::{BodySet, BodyStatus, DefaultBodyHandle};
+use np::solver::IntegrationParameters;
+use nphysics2d as np;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum ForceFieldKind {
+    /// pushes everything in one direction, given by the field's angle
+    Wind,
+    /// pushes away from the center, or pulls towards it if strength is negative
+    Radial,
+    /// pushes tangentially around the center
+    Vortex,
+}
+
+impl ForceFieldKind {
+    pub const ALL: [ForceFieldKind; 3] = [
+        ForceFieldKind::Wind,
+        ForceFieldKind::Radial,
+        ForceFieldKind::Vortex,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ForceFieldKind::Wind => "wind",
+            ForceFieldKind::Radial => "radial",
+            ForceFieldKind::Vortex => "vortex",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "wind" | "uniform" => Some(ForceFieldKind::Wind),
+            "radial" => Some(ForceFieldKind::Radial),
+            "vortex" => Some(ForceFieldKind::Vortex),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum FieldShape {
+    /// half extents, same as a rectangle body
+    Rectangle(Vector),
+    Circle(f32),
+}
+
+/// An area limited force generator. Every dynamic body whose center
+/// of mass is inside of the field's bounds gets pushed with a force
+/// of `strength` newtons.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct ForceField {
+    pub kind: ForceFieldKind,
+    pub shape: FieldShape,
+    pub center: Vector,
+    pub strength: f32,
+    /// direction of the force in radians, only used by wind fields
+    pub angle: f32,
+}
+
+impl ForceField {
+    pub fn new(kind: ForceFieldKind, shape: FieldShape, center: Vector) -> Self {
+        ForceField {
+            kind,
+            shape,
+            center,
+            strength: 5.0,
+            angle: 0.0,
+        }
+    }
+
+    pub fn contains(&self, point: &Vector) -> bool {
+        let offset = point - self.center;
+        match self.shape {
+            FieldShape::Rectangle(half_extents) => {
+                offset.x.abs() <= half_extents.x && offset.y.abs() <= half_extents.y
+            }
+            FieldShape::Circle(r) => offset.norm_squared() <= r * r,
+        }
+    }
+
+    /// Returns the force the field applies at the given point,
+    /// ignoring the bounds of the field
+    pub fn force_at(&self, point: &Vector) -> Vector {
+        let radius = point - self.center;
+        let radial_dir = radius
+            .try_normalize(1.0e-6)
+            .unwrap_or_else(|| Vector::new(0.0, 0.0));
+
+        match self.kind {
+            ForceFieldKind::Wind => Vector::new(self.angle.cos(), self.angle.sin()) * self.strength,
+            ForceFieldKind::Radial => radial_dir * self.strength,
+            // y is down so this goes clockwise on screen for positive strengths
+            ForceFieldKind::Vortex => Vector::new(-radial_dir.y, radial_dir.x) * self.strength,
+        }
+    }
+
+    /// Inserts the field into the ForceGeneratorSet and lazily creates its entity,
+    /// world.maintain() has to be called before the entity shows up in joins.
+    pub fn create(self, world: &World) -> Entity {
+        let gen_handle = world
+            .fetch_mut::<ForceGeneratorSet>()
+            .insert(Box::new(self));
+
+        world
+            .fetch::<LazyUpdate>()
+            .create_entity(&world.entities())
+            .with(ForceGen { gen_handle })
+            .build()
+    }
+}
+
+impl ForceGenerator<f32, DefaultBodyHandle> for ForceField {
+    fn apply(
+        &mut self,
+        _parameters: &IntegrationParameters<f32>,
+        bodies: &mut dyn BodySet<f32, Handle = DefaultBodyHandle>,
+    ) {
+        bodies.foreach_mut(&mut |_, body| {
+            if body.status() != BodyStatus::Dynamic {
+                return;
+            }
+
+            let center_of_mass = match body.part(0) {
+                Some(part) => part.center_of_mass().coords,
+                None => return,
+            };
+
+            if self.contains(&center_of_mass) {
+                let force = Force::linear(self.force_at(&center_of_mass));
+                body.apply_force(0, &force, ForceType::Force, true);
+            }
+        });
+    }
+}
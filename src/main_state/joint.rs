@@ -0,0 +1,159 @@
+use specs::prelude::*;
+
+use crate::components::{Joint, Name, PhysicsBody};
+use crate::{BodySet, JointConstraintSet, Point, Vector};
+
+use np::joint::{DefaultJointConstraintHandle, FixedConstraint, RevoluteConstraint};
+use np::object::BodyPartHandle;
+use nphysics2d as np;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum JointKind {
+    /// the bodies can rotate around the anchor
+    Pin,
+    /// the bodies are stuck together
+    Weld,
+}
+
+impl JointKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            JointKind::Pin => "pin",
+            JointKind::Weld => "weld",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "pin" | "revolute" => Some(JointKind::Pin),
+            "weld" | "fixed" => Some(JointKind::Weld),
+            _ => None,
+        }
+    }
+}
+
+/// A break force or torque from the GUI or Lua, where 0 means the joint can't break
+pub fn threshold(threshold: f32) -> Option<f32> {
+    if threshold > 0.0 {
+        Some(threshold)
+    } else {
+        None
+    }
+}
+
+/// Sets the break thresholds of a joint constraint, None means it can't break
+pub fn set_break_thresholds(
+    joint_set: &mut JointConstraintSet,
+    joint_handle: DefaultJointConstraintHandle,
+    break_force: Option<f32>,
+    break_torque: Option<f32>,
+) {
+    let break_force = break_force.unwrap_or(f32::MAX);
+    let break_torque = break_torque.unwrap_or(f32::MAX);
+
+    if let Some(constraint) = joint_set.get_mut(joint_handle) {
+        if let Some(revolute) = constraint.downcast_mut::<RevoluteConstraint<f32, _>>() {
+            revolute.set_break_force(break_force);
+            revolute.set_break_torque(break_torque);
+        } else if let Some(fixed) = constraint.downcast_mut::<FixedConstraint<f32, _>>() {
+            fixed.set_break_force(break_force);
+            fixed.set_break_torque(break_torque);
+        }
+    }
+}
+
+pub struct JointBuilder {
+    pub kind: JointKind,
+    pub body1: Entity,
+    pub body2: Entity,
+    /// world position of the anchor when the joint is created
+    pub anchor: Vector,
+    /// newtons of force the joint can take before breaking, None if it's unbreakable
+    pub break_force: Option<f32>,
+    /// same as break_force but for torque
+    pub break_torque: Option<f32>,
+    pub name: Option<String>,
+}
+
+impl JointBuilder {
+    pub fn new(kind: JointKind, body1: Entity, body2: Entity, anchor: Vector) -> Self {
+        JointBuilder {
+            kind,
+            body1,
+            body2,
+            anchor,
+            break_force: None,
+            break_torque: None,
+            name: None,
+        }
+    }
+
+    /// Inserts the joint into the JointConstraintSet and lazily creates its entity,
+    /// world.maintain() has to be called before the entity shows up in joins.
+    pub fn create(self, world: &World) -> Entity {
+        let (b1, b2) = {
+            let physics_bodies = world.read_storage::<PhysicsBody>();
+            (
+                physics_bodies.get(self.body1).unwrap().body_handle,
+                physics_bodies.get(self.body2).unwrap().body_handle,
+            )
+        };
+
+        // anchors are in the local space of each body
+        let (isometry1, isometry2) = {
+            let body_set = world.fetch::<BodySet>();
+            (
+                *body_set.rigid_body(b1).unwrap().position(),
+                *body_set.rigid_body(b2).unwrap().position(),
+            )
+        };
+        let anchor1 = isometry1.inverse_transform_point(&Point::from(self.anchor));
+        let anchor2 = isometry2.inverse_transform_point(&Point::from(self.anchor));
+
+        let mut joint_set = world.fetch_mut::<JointConstraintSet>();
+        let joint_handle = match self.kind {
+            JointKind::Pin => joint_set.insert(RevoluteConstraint::new(
+                BodyPartHandle(b1, 0),
+                BodyPartHandle(b2, 0),
+                anchor1,
+                anchor2,
+            )),
+            // the reference frames keep the bodies at the angle they're at now
+            JointKind::Weld => joint_set.insert(FixedConstraint::new(
+                BodyPartHandle(b1, 0),
+                BodyPartHandle(b2, 0),
+                anchor1,
+                isometry1.rotation.inverse(),
+                anchor2,
+                isometry2.rotation.inverse(),
+            )),
+        };
+        set_break_thresholds(
+            &mut joint_set,
+            joint_handle,
+            self.break_force,
+            self.break_torque,
+        );
+
+        let lazy_update = world.fetch::<LazyUpdate>();
+        let entity = lazy_update
+            .create_entity(&world.entities())
+            .with(Joint {
+                joint_handle,
+                kind: self.kind,
+                body1: self.body1,
+                body2: self.body2,
+                anchor1,
+                anchor2,
+                break_force: self.break_force,
+                break_torque: self.break_torque,
+                broken_flash: None,
+            })
+            .build();
+        if let Some(name) = self.name {
+            lazy_update.insert(entity, Name(name));
+        }
+
+        entity
+    }
+}
@@ -0,0 +1,60 @@
+use specs::prelude::*;
+
+use crate::components::{Joint, Name};
+use crate::resources::{BrokenJoints, FlashBrokenJoints, JointBreak, DT};
+use crate::JointConstraintSet;
+
+// seconds that broken joints are drawn for
+const BROKEN_FLASH_TIME: f32 = 1.0;
+
+/// nphysics stops solving joints once they break but leaves them in the
+/// JointConstraintSet, so this removes them and keeps track of the breaks
+pub struct JointBreakSys;
+
+impl<'a> System<'a> for JointBreakSys {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, Joint>,
+        ReadStorage<'a, Name>,
+        WriteExpect<'a, JointConstraintSet>,
+        Write<'a, BrokenJoints>,
+        Read<'a, FlashBrokenJoints>,
+        Read<'a, DT>,
+    );
+
+    fn run(
+        &mut self,
+        (entities, mut joints, names, mut joint_set, mut broken_joints, flash, dt): Self::SystemData,
+    ) {
+        let dt = dt.0.as_secs_f32();
+        let name_of = |entity| names.get(entity).map(|name: &Name| name.0.clone());
+
+        (&entities, &mut joints)
+            .join()
+            .for_each(|(entity, joint)| match joint.broken_flash {
+                None => {
+                    let broken = matches!(
+                        joint_set.get(joint.joint_handle),
+                        Some(constraint) if constraint.is_broken()
+                    );
+
+                    if broken {
+                        joint_set.remove(joint.joint_handle);
+                        joint.broken_flash = Some(if flash.0 { BROKEN_FLASH_TIME } else { 0.0 });
+                        broken_joints.0.push(JointBreak {
+                            name: name_of(entity),
+                            body1: name_of(joint.body1),
+                            body2: name_of(joint.body2),
+                        });
+                    }
+                }
+                Some(time_left) if time_left <= 0.0 => {
+                    entities.delete(entity).unwrap();
+                }
+                Some(time_left) => {
+                    joint.broken_flash = Some(time_left - dt);
+                }
+            });
+    }
+}
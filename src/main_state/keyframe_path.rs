@@ -0,0 +1,281 @@
+use crate::Vector;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Keyframe {
+    /// seconds since the start of the path
+    pub time: f32,
+    pub pos: Vector,
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PathInterpolation {
+    /// straight lines between the waypoints
+    Linear,
+    /// a Catmull-Rom spline that passes through every waypoint
+    Spline,
+}
+
+impl PathInterpolation {
+    pub const ALL: [PathInterpolation; 2] = [PathInterpolation::Linear, PathInterpolation::Spline];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PathInterpolation::Linear => "linear",
+            PathInterpolation::Spline => "spline",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "linear" => Some(PathInterpolation::Linear),
+            "spline" | "smooth" => Some(PathInterpolation::Spline),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum PathMode {
+    /// jumps back to the first waypoint after the last one
+    Loop,
+    /// goes back and forth along the path
+    PingPong,
+}
+
+impl PathMode {
+    pub const ALL: [PathMode; 2] = [PathMode::Loop, PathMode::PingPong];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PathMode::Loop => "loop",
+            PathMode::PingPong => "pingpong",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().replace(&['-', '_'][..], "").as_str() {
+            "loop" => Some(PathMode::Loop),
+            "pingpong" => Some(PathMode::PingPong),
+            _ => None,
+        }
+    }
+}
+
+/// A list of waypoints that a kinematic body moves through.
+/// The keyframes are sorted by time.
+#[derive(Clone, PartialEq, Debug)]
+pub struct KeyframePath {
+    pub keyframes: Vec<Keyframe>,
+    pub interpolation: PathInterpolation,
+    pub mode: PathMode,
+}
+
+impl KeyframePath {
+    pub fn new(mut keyframes: Vec<Keyframe>) -> Self {
+        keyframes.sort_by(|k1, k2| k1.time.total_cmp(&k2.time));
+        KeyframePath {
+            keyframes,
+            interpolation: PathInterpolation::Linear,
+            mode: PathMode::Loop,
+        }
+    }
+
+    pub fn start_time(&self) -> f32 {
+        self.keyframes.first().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time) - self.start_time()
+    }
+
+    /// Maps the time since the body started following the path to a time
+    /// between the first and last keyframes, depending on the mode.
+    pub fn local_time(&self, elapsed: f32) -> f32 {
+        let duration = self.duration();
+        if duration <= 0.0 {
+            return self.start_time();
+        }
+
+        let local = match self.mode {
+            PathMode::Loop => elapsed.rem_euclid(duration),
+            PathMode::PingPong => {
+                let t = elapsed.rem_euclid(duration * 2.0);
+                if t > duration {
+                    duration * 2.0 - t
+                } else {
+                    t
+                }
+            }
+        };
+
+        self.start_time() + local
+    }
+
+    /// Returns the position along the path at the time since it started
+    pub fn pos_at(&self, elapsed: f32) -> Vector {
+        match self.keyframes.len() {
+            0 => return Vector::new(0.0, 0.0),
+            1 => return self.keyframes[0].pos,
+            _ => {}
+        }
+
+        let time = self.local_time(elapsed);
+        let last = self.keyframes.len() - 1;
+        let i = self
+            .keyframes
+            .iter()
+            .rposition(|keyframe| keyframe.time <= time)
+            .unwrap_or(0)
+            .min(last - 1);
+
+        let (k1, k2) = (self.keyframes[i], self.keyframes[i + 1]);
+        let segment_time = k2.time - k1.time;
+        let u = if segment_time > 0.0 {
+            ((time - k1.time) / segment_time).clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+
+        match self.interpolation {
+            PathInterpolation::Linear => k1.pos + (k2.pos - k1.pos) * u,
+            PathInterpolation::Spline => {
+                // the tangents at the ends of the path use the end points themselves
+                let neighbour =
+                    |index: isize| self.keyframes[index.clamp(0, last as isize) as usize].pos;
+                let p0 = neighbour(i as isize - 1);
+                let p3 = neighbour(i as isize + 2);
+
+                let (p1, p2) = (k1.pos, k2.pos);
+                let u2 = u * u;
+                let u3 = u2 * u;
+                (p1 * 2.0
+                    + (p2 - p0) * u
+                    + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * u2
+                    + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * u3)
+                    * 0.5
+            }
+        }
+    }
+
+    /// Returns true if the path jumps back to the start between the two times
+    pub fn wraps_between(&self, elapsed: f32, next_elapsed: f32) -> bool {
+        let duration = self.duration();
+        self.mode == PathMode::Loop
+            && duration > 0.0
+            && (next_elapsed / duration).floor() > (elapsed / duration).floor()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(keyframes: &[(f32, f32)]) -> KeyframePath {
+        KeyframePath::new(
+            keyframes
+                .iter()
+                .map(|&(time, x)| Keyframe {
+                    time,
+                    pos: Vector::new(x, 0.0),
+                })
+                .collect(),
+        )
+    }
+
+    fn assert_close(a: Vector, b: Vector) {
+        assert!((a - b).norm() < 1e-4, "{:?} isn't close to {:?}", a, b);
+    }
+
+    #[test]
+    fn keyframes_are_sorted() {
+        let times: Vec<f32> = path(&[(2.0, 2.0), (0.0, 0.0), (1.0, 1.0)])
+            .keyframes
+            .iter()
+            .map(|keyframe| keyframe.time)
+            .collect();
+        assert_eq!(times, vec![0.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn loop_and_ping_pong() {
+        let mut path = path(&[(1.0, 0.0), (3.0, 2.0)]);
+        assert_eq!(path.local_time(0.0), 1.0);
+        assert_eq!(path.local_time(0.5), 1.5);
+        assert_eq!(path.local_time(2.5), 1.5);
+        assert_close(path.pos_at(2.5), Vector::new(0.5, 0.0));
+        assert!(path.wraps_between(1.9, 2.1));
+        assert!(!path.wraps_between(0.5, 1.5));
+
+        path.mode = PathMode::PingPong;
+        assert_eq!(path.local_time(0.5), 1.5);
+        assert_eq!(path.local_time(2.0), 3.0);
+        assert_eq!(path.local_time(2.5), 2.5);
+        assert_eq!(path.local_time(4.0), 1.0);
+        assert_close(path.pos_at(2.5), Vector::new(1.5, 0.0));
+        assert!(!path.wraps_between(1.9, 2.1));
+    }
+
+    #[test]
+    fn one_keyframe() {
+        let mut path = path(&[(2.0, 3.0)]);
+        assert_eq!(path.duration(), 0.0);
+        assert_eq!(path.local_time(5.0), 2.0);
+        assert!(!path.wraps_between(0.0, 10.0));
+        PathInterpolation::ALL.iter().for_each(|interpolation| {
+            path.interpolation = *interpolation;
+            assert_eq!(path.pos_at(0.0), Vector::new(3.0, 0.0));
+            assert_eq!(path.pos_at(7.5), Vector::new(3.0, 0.0));
+        });
+    }
+
+    #[test]
+    fn two_keyframes() {
+        let mut path = path(&[(0.0, 0.0), (2.0, 2.0)]);
+        PathInterpolation::ALL.iter().for_each(|interpolation| {
+            path.interpolation = *interpolation;
+            assert_close(path.pos_at(0.0), Vector::new(0.0, 0.0));
+            assert_close(path.pos_at(1.0), Vector::new(1.0, 0.0));
+        });
+
+        path.interpolation = PathInterpolation::Linear;
+        assert_close(path.pos_at(0.5), Vector::new(0.5, 0.0));
+        // with nothing on either side the spline eases in and out
+        path.interpolation = PathInterpolation::Spline;
+        let eased = path.pos_at(0.5).x;
+        assert!(eased > 0.0 && eased < 0.5);
+    }
+
+    #[test]
+    fn equal_timestamps() {
+        // two keyframes at the same time make the body jump between them
+        let jump = path(&[(0.0, 0.0), (1.0, 1.0), (1.0, 5.0), (2.0, 6.0)]);
+        assert_close(jump.pos_at(0.999), Vector::new(0.999, 0.0));
+        assert_close(jump.pos_at(1.0), Vector::new(5.0, 0.0));
+        assert_close(jump.pos_at(1.5), Vector::new(5.5, 0.0));
+
+        // all at the same time stays on the last one instead of dividing by 0
+        let mut same_time = path(&[(1.0, 0.0), (1.0, 4.0)]);
+        PathInterpolation::ALL.iter().for_each(|interpolation| {
+            same_time.interpolation = *interpolation;
+            assert_eq!(same_time.pos_at(3.0), Vector::new(4.0, 0.0));
+        });
+    }
+
+    #[test]
+    fn spline_passes_through_keyframes() {
+        let keyframes = [(0.0, 0.0), (1.0, 3.0), (2.0, -1.0), (4.0, 2.0)];
+        let mut path = path(&keyframes);
+        path.interpolation = PathInterpolation::Spline;
+        // ping-pong stays at the last keyframe at the end instead of wrapping
+        path.mode = PathMode::PingPong;
+        keyframes.iter().for_each(|&(time, x)| {
+            assert_close(path.pos_at(time), Vector::new(x, 0.0));
+        });
+
+        // the spline stays between its neighbours near the end points
+        let near_start = path.pos_at(0.01).x;
+        let near_end = path.pos_at(3.99).x;
+        assert!(near_start > 0.0 && near_start < 0.1);
+        assert!(near_end < 2.0 && near_end > 1.9);
+    }
+}
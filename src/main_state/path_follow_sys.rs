@@ -0,0 +1,83 @@
+use specs::prelude::*;
+
+use crate::components::{Collider, FollowPath, PhysicsBody};
+use crate::resources::{FrameSteps, Paused, Timestep};
+use crate::{BodySet, ColliderSet, RigidBody, Vector};
+
+/// Sets the velocity of bodies with a FollowPath so that they reach the next
+/// point on their path by the end of the next frame. Moving them with velocity
+/// instead of teleporting them means they push other bodies around properly.
+pub struct PathFollowSys;
+
+impl<'a> System<'a> for PathFollowSys {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteStorage<'a, FollowPath>,
+        ReadStorage<'a, PhysicsBody>,
+        ReadStorage<'a, Collider>,
+        WriteExpect<'a, BodySet>,
+        WriteExpect<'a, ColliderSet>,
+        Read<'a, Timestep>,
+        Read<'a, Paused>,
+        Read<'a, FrameSteps>,
+    );
+
+    fn run(
+        &mut self,
+        (
+            mut follow_paths,
+            physics_bodies,
+            colliders,
+            mut body_set,
+            mut collider_set,
+            timestep,
+            paused,
+            frame_steps,
+        ): Self::SystemData,
+    ) {
+        if paused.0 {
+            return;
+        }
+
+        let frame_time = timestep.0 * frame_steps.0 as f32;
+        if frame_time <= 0.0 {
+            return;
+        }
+
+        (&mut follow_paths, &physics_bodies, &colliders)
+            .join()
+            .for_each(|(follow_path, physics_body, collider)| {
+                let rigid_body = match body_set
+                    .get_mut(physics_body.body_handle)
+                    .and_then(|body| body.downcast_mut::<RigidBody>())
+                {
+                    Some(rigid_body) => rigid_body,
+                    None => return,
+                };
+
+                let next_elapsed = follow_path.elapsed + frame_time;
+                let target = follow_path.path.pos_at(next_elapsed);
+
+                // the body jumps straight to the start of the path
+                // when it's just been added or when a loop restarts
+                if follow_path.elapsed == 0.0
+                    || follow_path
+                        .path
+                        .wraps_between(follow_path.elapsed, next_elapsed)
+                {
+                    let mut isometry = *rigid_body.position();
+                    isometry.translation.vector = target;
+                    rigid_body.set_position(isometry);
+                    rigid_body.set_linear_velocity(Vector::new(0.0, 0.0));
+                    if let Some(collider) = collider_set.get_mut(collider.coll_handle) {
+                        collider.set_position(isometry);
+                    }
+                } else {
+                    let pos = rigid_body.position().translation.vector;
+                    rigid_body.set_linear_velocity((target - pos) / frame_time);
+                }
+
+                follow_path.elapsed = next_elapsed;
+            });
+    }
+}
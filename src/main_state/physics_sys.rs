@@ -1,8 +1,10 @@
 use specs::prelude::*;
 
-use crate::resources::{FrameSteps, Paused, Timestep};
+use crate::resources::{FrameSteps, Paused, SimTime, Timestep};
 use crate::types::*;
 
+use super::pulley::PulleyConstraint;
+
 use microprofile::scope;
 
 pub struct PhysicsSys;
@@ -19,6 +21,7 @@ impl<'a> System<'a> for PhysicsSys {
         Read<'a, Timestep>,
         Read<'a, Paused>,
         Read<'a, FrameSteps>,
+        Write<'a, SimTime>,
     );
 
     fn run(
@@ -33,6 +36,7 @@ impl<'a> System<'a> for PhysicsSys {
             timestep,
             paused,
             frame_steps,
+            mut sim_time,
         ): Self::SystemData,
     ) {
         microprofile::scope!("nphysics", "physics step");
@@ -42,8 +46,18 @@ impl<'a> System<'a> for PhysicsSys {
             mechanical_world.set_timestep(0.0);
         } else {
             mechanical_world.set_timestep(timestep.0);
+            sim_time.0 += timestep.0 * frame_steps.0 as f32;
         }
 
+        // pulleys predict where the bodies will end up to find the tension,
+        // which needs gravity
+        let gravity = mechanical_world.gravity;
+        force_generator_set.iter_mut().for_each(|(_, force_gen)| {
+            if let Some(pulley) = force_gen.downcast_mut::<PulleyConstraint>() {
+                pulley.gravity = gravity;
+            }
+        });
+
         (0..frame_steps.0).for_each(|_| {
             mechanical_world.step(
                 &mut *geometrical_world,
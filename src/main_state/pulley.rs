@@ -0,0 +1,205 @@
+use specs::prelude::*;
+
+use crate::components::{ForceGen, Name, PhysicsBody, Pulley};
+use crate::{BodySet, ForceGeneratorSet, Vector};
+
+use np::force_generator::ForceGenerator;
+use np::math::{Force, ForceType};
+use np::object::{BodyStatus, DefaultBodyHandle};
+use np::solver::IntegrationParameters;
+use nphysics2d as np;
+
+// fraction of the rope stretch that's corrected each step
+const BAUMGARTE_FACTOR: f32 = 0.2;
+
+/// A rope between two bodies running over one or more fixed pulley wheels.
+///
+/// The rope can't stretch, so `length_1 + ratio * length_2` stays at most `length`,
+/// where `length_1` and `length_2` are the lengths of the rope from each body to its
+/// closest wheel. The rope between wheels doesn't change length so it's not counted.
+/// A ratio of 2 means the second body hangs from a movable pulley block, so it moves
+/// half as far and feels twice the tension.
+///
+/// The tension is solved for every step the same way a joint would be, but it's
+/// applied as a regular force to the bodies' centers of mass.
+pub struct PulleyConstraint {
+    pub b1: DefaultBodyHandle,
+    pub b2: DefaultBodyHandle,
+    /// positions of the fixed wheels, from the first body's side to the second's
+    pub wheels: Vec<Vector>,
+    pub ratio: f32,
+    pub length: f32,
+    /// tension of the rope on the first body's side in newtons, from the last step
+    pub tension: f32,
+    /// set by PhysicsSys since force generators don't have access to the MechanicalWorld
+    pub gravity: Vector,
+}
+
+impl PulleyConstraint {
+    /// Returns the rope length that's actually in use with the bodies at the given positions
+    pub fn current_length(&self, p1: Vector, p2: Vector) -> f32 {
+        let (w1, w2) = self.end_wheels();
+        (w1 - p1).norm() + self.ratio * (w2 - p2).norm()
+    }
+
+    /// the wheels that the rope leaves from to reach the first and second bodies
+    pub fn end_wheels(&self) -> (Vector, Vector) {
+        (*self.wheels.first().unwrap(), *self.wheels.last().unwrap())
+    }
+}
+
+impl ForceGenerator<f32, DefaultBodyHandle> for PulleyConstraint {
+    fn apply(
+        &mut self,
+        parameters: &IntegrationParameters<f32>,
+        bodies: &mut dyn np::object::BodySet<f32, Handle = DefaultBodyHandle>,
+    ) {
+        let dt = parameters.dt();
+        // the timestep is 0 while paused, so keep the last tension around
+        if dt <= 0.0 {
+            return;
+        }
+
+        let get_state = |handle| {
+            bodies.get(handle).and_then(|body| {
+                let part = body.part(0)?;
+                let inv_mass = if body.status() == BodyStatus::Dynamic {
+                    1.0 / part.inertia().linear
+                } else {
+                    0.0
+                };
+                let gravity = if body.status() == BodyStatus::Dynamic && body.gravity_enabled() {
+                    self.gravity
+                } else {
+                    Vector::new(0.0, 0.0)
+                };
+
+                // velocity at the end of the step if there was no rope
+                let predicted_vel = part.velocity().linear + gravity * dt;
+                Some((part.center_of_mass().coords, predicted_vel, inv_mass))
+            })
+        };
+
+        let ((p1, v1, inv_m1), (p2, v2, inv_m2)) = match (get_state(self.b1), get_state(self.b2)) {
+            (Some(state1), Some(state2)) => (state1, state2),
+            _ => return,
+        };
+
+        let (w1, w2) = self.end_wheels();
+        let (dir1, dir2) = match (
+            (w1 - p1).try_normalize(1.0e-6),
+            (w2 - p2).try_normalize(1.0e-6),
+        ) {
+            (Some(dir1), Some(dir2)) => (dir1, dir2),
+            _ => return,
+        };
+
+        let inv_eff_mass = inv_m1 + self.ratio * self.ratio * inv_m2;
+        if inv_eff_mass <= 0.0 {
+            self.tension = 0.0;
+            return;
+        }
+
+        // how fast the rope is getting longer, and how much it's already stretched
+        let length_vel = -dir1.dot(&v1) - self.ratio * dir2.dot(&v2);
+        let stretch = self.current_length(p1, p2) - self.length;
+
+        // ropes can only pull, so a slack rope has no tension
+        let tension =
+            ((length_vel + BAUMGARTE_FACTOR * stretch / dt) / (dt * inv_eff_mass)).max(0.0);
+        self.tension = tension;
+
+        if tension > 0.0 {
+            bodies.get_mut(self.b1).unwrap().apply_force(
+                0,
+                &Force::linear(dir1 * tension),
+                ForceType::Force,
+                true,
+            );
+            bodies.get_mut(self.b2).unwrap().apply_force(
+                0,
+                &Force::linear(dir2 * tension * self.ratio),
+                ForceType::Force,
+                true,
+            );
+        }
+    }
+}
+
+pub struct PulleyBuilder {
+    pub body1: Entity,
+    pub body2: Entity,
+    pub wheels: Vec<Vector>,
+    pub ratio: f32,
+    /// defaults to the length of the rope when the pulley is created
+    pub length: Option<f32>,
+    pub name: Option<String>,
+}
+
+impl PulleyBuilder {
+    pub fn new(body1: Entity, body2: Entity, wheels: Vec<Vector>) -> Self {
+        PulleyBuilder {
+            body1,
+            body2,
+            wheels,
+            ratio: 1.0,
+            length: None,
+            name: None,
+        }
+    }
+
+    /// Inserts the pulley into the ForceGeneratorSet and lazily creates its entity,
+    /// world.maintain() has to be called before the entity shows up in joins.
+    pub fn create(self, world: &World) -> Entity {
+        assert!(!self.wheels.is_empty(), "Pulleys need at least one wheel");
+
+        let (b1, b2) = {
+            let physics_bodies = world.read_storage::<PhysicsBody>();
+            (
+                physics_bodies.get(self.body1).unwrap().body_handle,
+                physics_bodies.get(self.body2).unwrap().body_handle,
+            )
+        };
+
+        let mut constraint = PulleyConstraint {
+            b1,
+            b2,
+            wheels: self.wheels,
+            ratio: self.ratio,
+            length: 0.0,
+            tension: 0.0,
+            gravity: Vector::new(0.0, 0.0),
+        };
+        constraint.length = self.length.unwrap_or_else(|| {
+            let body_set = world.fetch::<BodySet>();
+            let pos = |handle| {
+                body_set
+                    .rigid_body(handle)
+                    .unwrap()
+                    .position()
+                    .translation
+                    .vector
+            };
+            constraint.current_length(pos(b1), pos(b2))
+        });
+
+        let gen_handle = world
+            .fetch_mut::<ForceGeneratorSet>()
+            .insert(Box::new(constraint));
+
+        let lazy_update = world.fetch::<LazyUpdate>();
+        let entity = lazy_update
+            .create_entity(&world.entities())
+            .with(ForceGen { gen_handle })
+            .with(Pulley {
+                body1: self.body1,
+                body2: self.body2,
+            })
+            .build();
+        if let Some(name) = self.name {
+            lazy_update.insert(entity, Name(name));
+        }
+
+        entity
+    }
+}
@@ -0,0 +1,247 @@
+use specs::prelude::*;
+
+use crate::components::{Color, ForceGen, Name, SoftBody, SoftBodyParticle};
+use crate::resources::ShapeInfo;
+use crate::{ForceGeneratorSet, Vector};
+
+use super::body_builder::BodyBuilder;
+
+use np::force_generator::ForceGenerator;
+use np::math::{Force, ForceType};
+use np::object::{BodySet, DefaultBodyHandle};
+use np::solver::IntegrationParameters;
+use nphysics2d as np;
+
+pub struct LatticeSpring {
+    pub b1: DefaultBodyHandle,
+    pub b2: DefaultBodyHandle,
+    pub rest_length: f32,
+}
+
+/// All of the damped springs holding a soft body together
+pub struct LatticeSprings {
+    pub springs: Vec<LatticeSpring>,
+    /// N/m for each individual spring
+    pub stiffness: f32,
+    pub damping: f32,
+}
+
+impl ForceGenerator<f32, DefaultBodyHandle> for LatticeSprings {
+    fn apply(
+        &mut self,
+        _parameters: &IntegrationParameters<f32>,
+        bodies: &mut dyn BodySet<f32, Handle = DefaultBodyHandle>,
+    ) {
+        let stiffness = self.stiffness;
+        let damping = self.damping;
+
+        self.springs.iter().for_each(|spring| {
+            let get_state = |handle| {
+                bodies
+                    .get(handle)
+                    .and_then(|body| body.part(0))
+                    .map(|part| (part.center_of_mass().coords, part.velocity().linear))
+            };
+
+            let ((p1, v1), (p2, v2)) = match (get_state(spring.b1), get_state(spring.b2)) {
+                (Some(state1), Some(state2)) => (state1, state2),
+                _ => return,
+            };
+
+            let delta = p2 - p1;
+            let len = delta.norm();
+            if len < 1.0e-6 {
+                return;
+            }
+            let dir = delta / len;
+
+            let stretch = len - spring.rest_length;
+            let relative_vel = (v2 - v1).dot(&dir);
+            let force = dir * (stiffness * stretch + damping * relative_vel);
+
+            // springs at rest shouldn't keep the particles awake forever
+            let wake_up = force.norm() > 1.0e-3;
+            bodies.get_mut(spring.b1).unwrap().apply_force(
+                0,
+                &Force::linear(force),
+                ForceType::Force,
+                wake_up,
+            );
+            bodies.get_mut(spring.b2).unwrap().apply_force(
+                0,
+                &Force::linear(-force),
+                ForceType::Force,
+                wake_up,
+            );
+        });
+    }
+}
+
+pub struct SoftBodyBuilder {
+    /// Rectangle half extents or circle radius, same as regular bodies
+    pub shape: ShapeInfo,
+    pub translation: Vector,
+    /// number of particles across the longest side
+    pub resolution: usize,
+    pub stiffness: f32,
+    pub damping: f32,
+    /// total mass, split evenly between the particles
+    pub mass: f32,
+    pub restitution: f32,
+    pub friction: f32,
+    pub color: ggez::graphics::Color,
+    pub name: Option<String>,
+}
+
+impl SoftBodyBuilder {
+    pub fn new(shape: ShapeInfo, translation: Vector) -> Self {
+        SoftBodyBuilder {
+            shape,
+            translation,
+            resolution: 6,
+            stiffness: 100.0,
+            damping: 0.5,
+            mass: 5.0,
+            restitution: 0.2,
+            friction: 0.5,
+            color: ggez::graphics::WHITE,
+            name: None,
+        }
+    }
+
+    /// Creates the particles and springs, like BodyBuilder::create()
+    /// the entities are created lazily so world.maintain() has to be called afterwards
+    pub fn create(self, world: &World) -> Entity {
+        let resolution = self.resolution.max(2);
+        let half_extents = match self.shape {
+            ShapeInfo::Rectangle(Some(half_extents)) => half_extents,
+            ShapeInfo::Circle(Some(r)) => Vector::new(r, r),
+            _ => panic!("Invalid soft body shape"),
+        };
+
+        let spacing = half_extents.x.max(half_extents.y) * 2.0 / (resolution - 1) as f32;
+        let cols = ((half_extents.x * 2.0 / spacing).round() as usize + 1).max(2);
+        let rows = ((half_extents.y * 2.0 / spacing).round() as usize + 1).max(2);
+        let top_left =
+            self.translation - Vector::new((cols - 1) as f32, (rows - 1) as f32) * spacing / 2.0;
+
+        let lattice_points = (0..rows)
+            .flat_map(|row| (0..cols).map(move |col| (col, row)))
+            .map(|(col, row)| {
+                let point = top_left + Vector::new(col as f32, row as f32) * spacing;
+                let inside = match self.shape {
+                    ShapeInfo::Circle(Some(r)) => {
+                        (point - self.translation).norm() <= r + spacing * 0.01
+                    }
+                    _ => true,
+                };
+                if inside {
+                    Some(point)
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<Option<Vector>>>();
+
+        let num_particles = lattice_points.iter().filter(|p| p.is_some()).count();
+        let particle_mass = self.mass / num_particles.max(1) as f32;
+
+        let soft_body_entity = world
+            .fetch::<LazyUpdate>()
+            .create_entity(&world.entities())
+            .build();
+
+        let mut particles = Vec::with_capacity(num_particles);
+        let mut body_handles = Vec::with_capacity(num_particles);
+        let lattice = lattice_points
+            .iter()
+            .map(|point| {
+                point.map(|point| {
+                    // particles are small enough that neighbours don't touch at rest
+                    let (entity, body_handle) = BodyBuilder {
+                        translation: point,
+                        restitution: self.restitution,
+                        friction: self.friction,
+                        color: self.color,
+                        ..BodyBuilder::from_world(
+                            world,
+                            ShapeInfo::Circle(Some(spacing * 0.45)),
+                            particle_mass,
+                        )
+                    }
+                    .create_with_handle();
+
+                    world.fetch::<LazyUpdate>().insert(
+                        entity,
+                        SoftBodyParticle {
+                            soft_body: soft_body_entity,
+                        },
+                    );
+
+                    particles.push(entity);
+                    body_handles.push((body_handle, point));
+                    particles.len() - 1
+                })
+            })
+            .collect::<Vec<Option<usize>>>();
+
+        // structural springs to the right and below, shear springs on the diagonals
+        let mut springs = Vec::new();
+        (0..rows).for_each(|row| {
+            (0..cols).for_each(|col| {
+                let i1 = match lattice[row * cols + col] {
+                    Some(i) => i,
+                    None => return,
+                };
+
+                let neighbours = [(1, 0), (0, 1), (1, 1), (-1, 1)];
+                neighbours.iter().for_each(|(d_col, d_row)| {
+                    let n_col = col as isize + d_col;
+                    let n_row = row + d_row;
+                    if n_col < 0 || n_col as usize >= cols || n_row >= rows {
+                        return;
+                    }
+
+                    if let Some(i2) = lattice[n_row * cols + n_col as usize] {
+                        let (b1, p1) = body_handles[i1];
+                        let (b2, p2) = body_handles[i2];
+                        springs.push(LatticeSpring {
+                            b1,
+                            b2,
+                            rest_length: (p2 - p1).norm(),
+                        });
+                    }
+                });
+            });
+        });
+
+        let gen_handle = world
+            .fetch_mut::<ForceGeneratorSet>()
+            .insert(Box::new(LatticeSprings {
+                springs,
+                stiffness: self.stiffness,
+                damping: self.damping,
+            }));
+
+        let lazy_update = world.fetch::<LazyUpdate>();
+        lazy_update.insert(soft_body_entity, ForceGen { gen_handle });
+        lazy_update.insert(soft_body_entity, Color(self.color));
+        lazy_update.insert(
+            soft_body_entity,
+            SoftBody {
+                particles,
+                lattice,
+                cols,
+                rows,
+                shape: self.shape,
+                resolution,
+                mass: self.mass,
+            },
+        );
+        if let Some(name) = self.name {
+            lazy_update.insert(soft_body_entity, Name(name));
+        }
+
+        soft_body_entity
+    }
+}
@@ -4,10 +4,13 @@ use ggez::graphics;
 
 use specs::prelude::*;
 
-use crate::{ColliderSet, GeometricalWorld, Point, Vector};
+use crate::{ForceGeneratorSet, Point, Vector};
 
+use crate::components::ForceGen;
+use crate::lua::queries::query_point;
 use crate::resources;
-use ncollide2d as nc;
+
+use super::force_field::ForceField;
 
 pub fn draw_circle(
     mesh_builder: &mut ggez::graphics::MeshBuilder,
@@ -94,20 +97,58 @@ pub fn draw_rect(
         .expect("error drawing rotated rect");
 }
 
+pub fn draw_arrow(
+    mesh_builder: &mut ggez::graphics::MeshBuilder,
+    start: Vector,
+    end: Vector,
+    width: f32,
+    color: graphics::Color,
+) {
+    let delta = end - start;
+    let len = delta.norm();
+    if len < 1.0e-4 {
+        return;
+    }
+
+    // the head is two lines angled back from the tip
+    let dir = delta / len;
+    let normal = Vector::new(-dir.y, dir.x);
+    let head_len = (len * 0.35).min(width * 6.0);
+    let head_left = end - dir * head_len + normal * head_len * 0.5;
+    let head_right = end - dir * head_len - normal * head_len * 0.5;
+
+    let _ = mesh_builder.line(&[[start.x, start.y], [end.x, end.y]], width, color);
+    let _ = mesh_builder.line(
+        &[
+            [head_left.x, head_left.y],
+            [end.x, end.y],
+            [head_right.x, head_right.y],
+        ],
+        width,
+        color,
+    );
+}
+
 pub fn get_hovered_shape(world: &World) -> Option<Entity> {
-    let geometrical_world = world.fetch::<GeometricalWorld>();
-    let colliders = world.fetch::<ColliderSet>();
+    let mouse_point = world.fetch::<resources::MousePos>().0;
+    query_point(world, mouse_point).into_iter().next()
+}
+
+pub fn get_hovered_force_field(world: &World) -> Option<Entity> {
+    let force_gen_set = world.fetch::<ForceGeneratorSet>();
+    let force_gens = world.read_storage::<ForceGen>();
+    let entities = world.entities();
     let mouse_point = world.fetch::<resources::MousePos>().0;
 
-    geometrical_world
-        .interferences_with_point(
-            &*colliders,
-            &Point::new(mouse_point.x, mouse_point.y),
-            &nc::pipeline::CollisionGroups::new(),
-        )
-        .map(|obj| {
-            let specs_hand = obj.1.user_data().unwrap();
-            *specs_hand.downcast_ref::<Entity>().unwrap()
+    (&force_gens, &entities)
+        .join()
+        .find(|(force_gen, _)| {
+            matches!(
+                force_gen_set
+                    .get(force_gen.gen_handle)
+                    .and_then(|gen| gen.downcast_ref::<ForceField>()),
+                Some(field) if field.contains(&mouse_point)
+            )
         })
-        .next()
+        .map(|(_, entity)| entity)
 }
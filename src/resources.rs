@@ -1,5 +1,11 @@
+use crate::main_state::force_field::ForceFieldKind;
+use crate::main_state::joint::JointKind;
 use crate::types::{Point, Vector};
 use ggez::graphics::{self, Rect};
+use specs::Entity;
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 
 #[derive(Copy, Clone)]
 pub struct MousePos(pub Vector);
@@ -33,6 +39,10 @@ impl Default for Timestep {
     }
 }
 
+#[derive(Copy, Clone, Default)]
+// simulated seconds since the scene was loaded, it doesn't go up while paused
+pub struct SimTime(pub f32);
+
 #[derive(Clone, PartialEq, Debug)]
 #[allow(dead_code)]
 pub enum ShapeInfo {
@@ -61,8 +71,116 @@ impl CreateShapeStatic {
     }
 }
 
+/// If this is Some, the shape in CreationData is used as the
+/// bounds of a new force field instead of creating a body
+#[derive(Copy, Clone, Default)]
+pub struct CreateForceField(pub Option<ForceFieldKind>);
+
+/// If this is true, the shape in CreationData is filled
+/// with a soft body lattice instead of creating a body
+#[derive(Copy, Clone, Default)]
+pub struct CreateSoftBody(pub bool);
+
+#[derive(Copy, Clone, Default)]
+pub struct CreateStiffness(pub f32);
+
+#[derive(Copy, Clone, Default)]
+pub struct CreateResolution(pub u16);
+
+/// Pulleys are created by clicking the first body, then each
+/// of the wheels, then the second body. If this is Some it holds
+/// what's been clicked so far.
+#[derive(Clone, Default)]
+pub struct CreatePulley(pub Option<PulleyDraft>);
+
+#[derive(Clone, Default)]
+pub struct PulleyDraft {
+    pub body: Option<Entity>,
+    pub wheels: Vec<Vector>,
+}
+
+#[derive(Copy, Clone, Default)]
+pub struct CreatePulleyRatio(pub f32);
+
+/// Joints are created by clicking the first body, then clicking
+/// the second body where the anchor should be
+#[derive(Clone, Default)]
+pub struct CreateJoint(pub Option<JointDraft>);
+
+#[derive(Clone)]
+pub struct JointDraft {
+    pub kind: JointKind,
+    pub body: Option<Entity>,
+}
+
+/// 0 means unbreakable
+#[derive(Copy, Clone, Default)]
+pub struct CreateBreakForce(pub f32);
+
+#[derive(Copy, Clone, Default)]
+pub struct CreateBreakTorque(pub f32);
+
+/// whether broken joints are drawn in red for a moment after they break
+#[derive(Copy, Clone, Default)]
+pub struct FlashBrokenJoints(pub bool);
+
+/// Joints that broke since the last lua_update(), used to call on_joint_break()
+#[derive(Clone, Default)]
+pub struct BrokenJoints(pub Vec<JointBreak>);
+
+#[derive(Clone, Debug)]
+pub struct JointBreak {
+    pub name: Option<String>,
+    pub body1: Option<String>,
+    pub body2: Option<String>,
+}
+
+/// If this is Some, clicks add waypoints to the path of a body
+/// instead of creating anything. It's started and finished
+/// from the body's side menu.
+#[derive(Clone, Default)]
+pub struct RecordPath(pub Option<PathDraft>);
+
+#[derive(Clone)]
+pub struct PathDraft {
+    pub entity: Entity,
+    /// starts at the body's position
+    pub waypoints: Vec<Vector>,
+}
+
+/// seconds between waypoints of recorded paths
+#[derive(Copy, Clone, Default)]
+pub struct RecordPathInterval(pub f32);
+
 pub type LuaRes = std::sync::Arc<std::sync::Mutex<rlua::Lua>>;
 
+/// shared with the LuaEntity handles that Lua code holds on to
+pub type LuaBridgeRes = std::sync::Arc<std::sync::Mutex<crate::lua::lua_entity::LuaBridge>>;
+
+/// the seeded random number generator behind the rng module, shared with the Settings menu
+pub type LuaRngRes = std::sync::Arc<std::sync::Mutex<crate::lua::rng::SeededRng>>;
+
+/// how many more Lua instructions can run this frame, counted down by the hook from new_lua_res()
+pub type LuaInstructionsLeft = std::sync::Arc<std::sync::atomic::AtomicI64>;
+
+/// Keeps an infinite loop or a runaway table in a script from freezing the program.
+/// Scenes can change these with set_limits{}, or INSTRUCTION_LIMIT and MEMORY_LIMIT_KB.
+#[derive(Copy, Clone)]
+pub struct LuaLimits {
+    /// shared by everything Lua runs in a frame, i.e. update(), update functions, and handlers
+    pub instructions_per_frame: i64,
+    pub memory_kb: usize,
+}
+
+impl Default for LuaLimits {
+    fn default() -> Self {
+        LuaLimits {
+            instructions_per_frame: 5_000_000,
+            memory_kb: 16 * 1024,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Default)]
 pub struct FPS(pub f64);
 
@@ -81,6 +199,147 @@ pub struct CreateFriction(pub f32);
 #[derive(Copy, Clone, Default)]
 pub struct FrameSteps(pub u16);
 
+/// The Lua file the current scene was loaded from, used to reset it
+#[derive(Clone, Default)]
+pub struct CurrentScene(pub Option<String>);
+
+/// The SCENE table of a scene file, shown in the Instructions window and the Load World menu
+#[derive(Clone, Default, Debug)]
+pub struct SceneInfo {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub description: Option<String>,
+    pub instructions: Option<String>,
+}
+
+/// the SceneInfo of the scene that's loaded, if it has one
+#[derive(Clone, Default)]
+pub struct CurrentSceneInfo(pub Option<SceneInfo>);
+
+/// The scenes in the lua directory for the Load World menu. Their SceneInfo comes
+/// from running them, so it's only done again when they change.
+#[derive(Clone, Default)]
+pub struct SceneBrowser {
+    pub scenes: Vec<SceneEntry>,
+    pub last_check: Option<Instant>,
+}
+
+#[derive(Clone)]
+pub struct SceneEntry {
+    pub filename: String,
+    pub modified: Option<SystemTime>,
+    pub info: Option<SceneInfo>,
+}
+
+impl SceneBrowser {
+    /// looks for new and changed scenes, at most every HOT_RELOAD_INTERVAL
+    pub fn refresh(&mut self) {
+        if matches!(self.last_check, Some(last_check) if last_check.elapsed() < HOT_RELOAD_INTERVAL)
+        {
+            return;
+        }
+        self.last_check = Some(Instant::now());
+
+        let dir = Path::new("./lua");
+        let mut filenames: Vec<String> = match std::fs::read_dir(dir) {
+            Ok(dir_entries) => dir_entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .filter(|filename| filename.ends_with(".lua"))
+                .collect(),
+            Err(e) => {
+                println!("Error reading dir: {}", e);
+                Vec::new()
+            }
+        };
+        filenames.sort();
+
+        let old_scenes = std::mem::take(&mut self.scenes);
+        self.scenes = filenames
+            .into_iter()
+            .map(|filename| {
+                let path = dir.join(&filename);
+                let modified = modified_time(&path);
+                match old_scenes
+                    .iter()
+                    .find(|scene| scene.filename == filename && scene.modified == modified)
+                {
+                    Some(scene) => scene.clone(),
+                    None => SceneEntry {
+                        info: crate::lua::read_scene_info(&path),
+                        filename,
+                        modified,
+                    },
+                }
+            })
+            .collect();
+    }
+}
+
+/// how often HotReload looks at the scene's files
+const HOT_RELOAD_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Reloads the current scene when its file, or a module it required, changes
+#[derive(Clone, Default)]
+pub struct HotReload {
+    pub enabled: bool,
+    /// the scene's files and when they were last modified as of loading the scene
+    pub files: Vec<(PathBuf, Option<SystemTime>)>,
+    pub last_check: Option<Instant>,
+}
+
+impl HotReload {
+    /// starts over with just the scene file, modules get added as they're required
+    pub fn watch_scene(&mut self, path: impl AsRef<Path>) {
+        self.files.clear();
+        self.watch(path);
+    }
+
+    pub fn watch(&mut self, path: impl AsRef<Path>) {
+        let path = path.as_ref().to_path_buf();
+        if self.files.iter().all(|(watched, _)| *watched != path) {
+            let modified = modified_time(&path);
+            self.files.push((path, modified));
+        }
+    }
+
+    /// whether any of the files were saved since the scene was loaded,
+    /// they're only looked at every HOT_RELOAD_INTERVAL
+    pub fn files_changed(&mut self) -> bool {
+        if !self.enabled
+            || matches!(self.last_check, Some(last_check) if last_check.elapsed() < HOT_RELOAD_INTERVAL)
+        {
+            return false;
+        }
+        self.last_check = Some(Instant::now());
+
+        self.files
+            .iter()
+            .any(|(path, modified)| modified_time(path) != *modified)
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// A message shown at the top of the screen for a few seconds, e.g. after a hot reload
+#[derive(Clone, Default)]
+pub struct Notification(pub Option<NotificationMessage>);
+
+#[derive(Clone)]
+pub struct NotificationMessage {
+    pub text: String,
+    pub error: bool,
+    pub shown_at: Instant,
+}
+
+/// pause the simulation when a Lua error is logged to the console
+#[derive(Copy, Clone, Default)]
+pub struct PauseOnLuaError(pub bool);
+
 #[derive(Copy, Clone, Default)]
 pub struct Paused(pub bool);
 
@@ -102,6 +361,10 @@ impl Default for GraphPosData {
     }
 }
 
+/// the Lua expression typed into a body's Add Graph menu, e.g. obj:get_mass() * 9.81
+#[derive(Clone, Default)]
+pub struct GraphExpression(pub String);
+
 #[derive(Copy, Clone, Default)]
 pub struct MovingGraph(pub bool);
 